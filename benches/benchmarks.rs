@@ -6,11 +6,11 @@ fn parse_all_streams(buffer: &'static [u8]) {
     let mut pdf = PdfDocument::new(source).unwrap();
     for pageno in 0..pdf.page_count() {
         let mut contents = pdf.page_contents(pageno).unwrap();
-        while contents.next_object().unwrap() != None {}
+        while contents.next_object().unwrap().is_some() {}
     }
 }
 
-static TRACEMONKEY_PDF: &'static [u8] = include_bytes!("../testing/tracemonkey.pdf");
+static TRACEMONKEY_PDF: &[u8] = include_bytes!("../testing/tracemonkey.pdf");
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("tracemonkey.pdf streams", |b| {
@@ -8,10 +8,27 @@ type Result<T> = ::std::result::Result<T, std::io::Error>;
 
 fn main() -> Result<()> {
     let names = load("names.txt")?;
-    generate("codegen_names.rs", "NAMES", "PdfName", names)?;
+    generate(
+        "codegen_names.rs",
+        "NAMES",
+        "PdfName",
+        names,
+        &["Other(PdfString), // a name which isn't listed in names.txt"],
+    )?;
 
     let keywords = load("keywords.txt")?;
-    generate("codegen_keywords.rs", "KEYWORDS", "PdfKeyword", keywords)?;
+    generate(
+        "codegen_keywords.rs",
+        "KEYWORDS",
+        "PdfKeyword",
+        keywords,
+        &[],
+    )?;
+    generate_arities("codegen_arities.rs", "keywords.txt")?;
+
+    generate_encodings("codegen_encodings.rs")?;
+    generate_standard_fonts("codegen_standard_fonts.rs")?;
+    generate_cid_tables("codegen_cid_tables.rs")?;
 
     Ok(())
 }
@@ -23,7 +40,7 @@ fn safe(s: String) -> String {
             '*' => d.push_str("star"),
             '\'' => d.push_str("apos"),
             '"' => d.push_str("quote"),
-            ch @ _ => d.push(ch),
+            ch => d.push(ch),
         }
     }
     d
@@ -34,25 +51,356 @@ fn load(filename: &str) -> Result<Vec<String>> {
     strings.insert("Unknown".to_owned());
     let of = File::open(filename)?;
     let file = BufReader::new(&of);
-    for (_, line) in file.lines().enumerate() {
+    for line in file.lines() {
         let l = line.unwrap().trim().to_owned();
-        if l.len() > 0 && l.chars().next().unwrap() != '#' {
-            strings.insert(l.to_owned());
+        // anything after the first word, such as an operator's arity, isn't part of the name
+        match l.split_whitespace().next() {
+            Some(word) if !word.starts_with('#') => {
+                strings.insert(word.to_owned());
+            }
+            _ => {}
         }
     }
     Ok(strings.into_iter().collect())
 }
 
-fn generate(filename: &str, target: &str, typename: &str, entries: Vec<String>) -> Result<()> {
+// the operand counts which follow operators in keywords.txt become the arity function
+fn generate_arities(filename: &str, keywords: &str) -> Result<()> {
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(filename);
+    let mut file = BufWriter::new(File::create(&path).unwrap());
+
+    writeln!(
+        &mut file,
+        "// the number of operands taken by a content stream operator, None if it isn't one"
+    )?;
+    writeln!(
+        &mut file,
+        "pub fn arity(operator: &PdfKeyword) -> Option<Arity> {{"
+    )?;
+    writeln!(&mut file, "    match operator {{")?;
+    for line in BufReader::new(File::open(keywords)?).lines() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        let (keyword, count) = match (fields.next(), fields.next()) {
+            (Some(keyword), Some(count)) if !keyword.starts_with('#') => (keyword, count),
+            _ => continue,
+        };
+        let arity = match count.strip_suffix('+') {
+            Some(count) => format!("AtLeast({})", count.parse::<usize>().unwrap()),
+            None => format!("Exactly({})", count.parse::<usize>().unwrap()),
+        };
+        writeln!(
+            &mut file,
+            "        PdfKeyword::r#{} => Some(Arity::{}),",
+            safe(keyword.to_owned()),
+            arity
+        )?;
+    }
+    writeln!(&mut file, "        _ => None,")?;
+    writeln!(&mut file, "    }}")?;
+    writeln!(&mut file, "}}")?;
+    Ok(())
+}
+
+// each encoding column of encodings.txt becomes a 256 entry table of glyph names, named after
+// the column heading, and the glyph lists become a map from glyph names to Unicode text
+fn generate_encodings(filename: &str) -> Result<()> {
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(filename);
+    let mut file = BufWriter::new(File::create(&path).unwrap());
+
+    let mut headings = Vec::new();
+    let mut tables: Vec<Vec<Option<String>>> = Vec::new();
+    for line in BufReader::new(File::open("encodings.txt")?).lines() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        if let Some(code) = line.strip_prefix("# code ") {
+            headings = code.split_whitespace().map(|h| h.to_owned()).collect();
+            tables = vec![vec![None; 256]; headings.len()];
+            continue;
+        }
+        let code = match fields.next() {
+            Some(code) if !code.starts_with('#') => usize::from_str_radix(code, 8).unwrap(),
+            _ => continue,
+        };
+        for (table, glyph) in tables.iter_mut().zip(fields) {
+            if glyph != "-" {
+                table[code] = Some(glyph.to_owned());
+            }
+        }
+    }
+    for (heading, table) in headings.iter().zip(tables.iter()) {
+        writeln!(
+            &mut file,
+            "static {}: [Option<&str>; 256] = [",
+            screaming_snake_case(heading)
+        )?;
+        for glyph in table.iter() {
+            match glyph {
+                Some(glyph) => writeln!(&mut file, "    Some({:?}),", glyph)?,
+                None => writeln!(&mut file, "    None,")?,
+            }
+        }
+        writeln!(&mut file, "];\n")?;
+    }
+
+    let mut glyphs = Vec::new();
+    for filename in ["glyphlist.txt", "zapfdingbats.txt"].iter() {
+        for line in BufReader::new(File::open(filename)?).lines() {
+            let line = line?;
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((name, values)) = line.split_once(';') {
+                let text: String = values
+                    .split_whitespace()
+                    .filter_map(|v| u32::from_str_radix(v, 16).ok())
+                    .filter_map(std::char::from_u32)
+                    .collect();
+                glyphs.push((name.to_owned(), format!("{:?}", text)));
+            }
+        }
+    }
+    write!(
+        &mut file,
+        "static GLYPH_LIST: phf::Map<&'static str, &'static str> = "
+    )?;
+    let mut builder = phf_codegen::Map::new();
+    for (name, text) in glyphs.iter() {
+        builder.entry(&name[..], text);
+    }
+    writeln!(&mut file, "{};", builder.build())?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct StandardFont {
+    name: String,
+    fields: Vec<String>,
+    widths: Vec<(String, String)>,
+}
+
+// each font in standard_fonts.txt becomes a StandardFont, with a map from glyph names to widths
+fn generate_standard_fonts(filename: &str) -> Result<()> {
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(filename);
+    let mut file = BufWriter::new(File::create(&path).unwrap());
+
+    let mut fonts: Vec<StandardFont> = Vec::new();
+    for line in BufReader::new(File::open("standard_fonts.txt")?).lines() {
+        let line = line?;
+        let (key, value) = match line.split_once(' ') {
+            Some(pair) if !line.starts_with('#') => pair,
+            _ => continue,
+        };
+        match (key, fonts.last_mut()) {
+            ("FontName", _) => fonts.push(StandardFont {
+                name: value.to_owned(),
+                ..StandardFont::default()
+            }),
+            ("FontBBox", Some(font)) => {
+                let bbox: Vec<String> = value
+                    .split(' ')
+                    .map(|v| format!("{:?}", v.parse::<f64>().unwrap()))
+                    .collect();
+                font.fields
+                    .push(format!("font_bbox: [{}]", bbox.join(", ")));
+            }
+            ("Flags", Some(font)) => font.fields.push(format!("flags: {}", value)),
+            ("Ascent", Some(font))
+            | ("Descent", Some(font))
+            | ("CapHeight", Some(font))
+            | ("ItalicAngle", Some(font)) => {
+                let field = screaming_snake_case(key).to_ascii_lowercase();
+                let value = value.parse::<f64>().unwrap();
+                font.fields.push(format!("{}: {:?}", field, value));
+            }
+            (glyph, Some(font)) => font.widths.push((
+                glyph.to_owned(),
+                format!("{:?}", value.parse::<f64>().unwrap()),
+            )),
+            _ => {}
+        }
+    }
+
+    write!(
+        &mut file,
+        "static STANDARD_FONTS: phf::Map<&'static str, StandardFont> = "
+    )?;
+    let mut builder = phf_codegen::Map::new();
+    for font in fonts.iter() {
+        let mut widths = phf_codegen::Map::new();
+        for (glyph, width) in font.widths.iter() {
+            widths.entry(&glyph[..], width);
+        }
+        builder.entry(
+            &font.name[..],
+            &format!(
+                "StandardFont {{ name: {:?}, {}, widths: {} }}",
+                font.name,
+                font.fields.join(", "),
+                widths.build()
+            ),
+        );
+    }
+    writeln!(&mut file, "{};", builder.build())?;
+    Ok(())
+}
+
+// the Adobe character collections, whose tables are read from Adobe-<ordering>.txt
+const CID_COLLECTIONS: [&str; 4] = ["Japan1", "GB1", "CNS1", "Korea1"];
+
+// the tables read from an Adobe-<ordering>.txt file
+#[derive(Default)]
+struct CidTables {
+    // (first CID, last CID, first Unicode scalar value)
+    unicode: Vec<(u32, u32, u32)>,
+    cmaps: Vec<CMapTable>,
+}
+
+#[derive(Default)]
+struct CMapTable {
+    name: String,
+    // (code length, first code, last code)
+    codespace: Vec<(u8, u32, u32)>,
+    // (code length, first code, last code, first CID)
+    horizontal: Vec<(u8, u32, u32, u32)>,
+    vertical: Vec<(u8, u32, u32, u32)>,
+}
+
+// each collection's Adobe-<ordering>.txt becomes a table from CIDs to Unicode and, for each
+// predefined CMap, a table from codes to CIDs
+//
+// The -H and -V CMaps share a table named without the suffix, H and V are named H. The Unicode
+// CMaps are represented by their UTF-32 table only, the other Unicode encodings are converted to
+// UTF-32 before the lookup.
+fn generate_cid_tables(filename: &str) -> Result<()> {
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(filename);
+    let mut file = BufWriter::new(File::create(&path).unwrap());
+
+    writeln!(
+        &mut file,
+        "static COLLECTIONS: [Collection; {}] = [",
+        CID_COLLECTIONS.len()
+    )?;
+    for ordering in CID_COLLECTIONS.iter() {
+        let filename = format!("Adobe-{}.txt", ordering);
+        let tables = read_cid_tables(BufReader::new(File::open(&filename)?))?;
+        writeln!(&mut file, "    Collection {{")?;
+        writeln!(&mut file, "        ordering: {:?},", ordering)?;
+        writeln!(&mut file, "        unicode: &{:?},", tables.unicode)?;
+        writeln!(&mut file, "        cmaps: &[")?;
+        for cmap in tables.cmaps.iter() {
+            writeln!(
+                &mut file,
+                "            CMapTable {{ name: {:?}, codespace: &{:?}, horizontal: &{:?}, \
+                 vertical: &{:?} }},",
+                cmap.name, cmap.codespace, cmap.horizontal, cmap.vertical
+            )?;
+        }
+        writeln!(&mut file, "        ],")?;
+        writeln!(&mut file, "    }},")?;
+    }
+    writeln!(&mut file, "];")?;
+    Ok(())
+}
+
+// a hex code in angle brackets, e.g. <8140>, as (code length, code)
+fn hex_code(field: &str) -> (u8, u32) {
+    let digits = field.trim_start_matches('<').trim_end_matches('>');
+    (
+        (digits.len() / 2) as u8,
+        u32::from_str_radix(digits, 16).unwrap(),
+    )
+}
+
+// the sections of Adobe-<ordering>.txt are [Unicode], with lines of a CID range and the Unicode
+// scalar value of its first CID, and one per CMap, with lines of a code range and the CID of its
+// first code, or of a codespace range
+fn read_cid_tables<R: BufRead>(reader: R) -> Result<CidTables> {
+    let mut tables = CidTables::default();
+    let mut section = String::new();
+    let mut vertical = false;
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            section = name.to_owned();
+            vertical = name == "V" || name.ends_with("-V");
+            let base = match name {
+                "H" | "V" => "H",
+                name => name
+                    .strip_suffix("-H")
+                    .or_else(|| name.strip_suffix("-V"))
+                    .unwrap_or(name),
+            };
+            if name != "Unicode" && !vertical {
+                tables.cmaps.push(CMapTable {
+                    name: base.to_owned(),
+                    ..CMapTable::default()
+                });
+            }
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if section == "Unicode" {
+            let value = u32::from_str_radix(fields[2].trim_start_matches("U+"), 16).unwrap();
+            tables.unicode.push((
+                fields[0].parse().unwrap(),
+                fields[1].parse().unwrap(),
+                value,
+            ));
+            continue;
+        }
+        let cmap = tables.cmaps.last_mut().unwrap();
+        if fields[0] == "codespace" {
+            let ((length, low), (_, high)) = (hex_code(fields[1]), hex_code(fields[2]));
+            cmap.codespace.push((length, low, high));
+            continue;
+        }
+        let ((length, low), (_, high)) = (hex_code(fields[0]), hex_code(fields[1]));
+        let entry = (length, low, high, fields[2].parse().unwrap());
+        match vertical {
+            true => cmap.vertical.push(entry),
+            false => cmap.horizontal.push(entry),
+        }
+    }
+    Ok(tables)
+}
+
+// StandardEncoding -> STANDARD_ENCODING, PDFDocEncoding -> PDF_DOC_ENCODING
+fn screaming_snake_case(s: &str) -> String {
+    let mut d = String::new();
+    for (i, ch) in s.char_indices() {
+        let word = s[i + ch.len_utf8()..].starts_with(|c: char| c.is_ascii_lowercase());
+        if i > 0 && ch.is_ascii_uppercase() && word {
+            d.push('_');
+        }
+        d.push(ch.to_ascii_uppercase());
+    }
+    d
+}
+
+fn generate(
+    filename: &str,
+    target: &str,
+    typename: &str,
+    entries: Vec<String>,
+    extra_variants: &[&str],
+) -> Result<()> {
     let path = Path::new(&env::var("OUT_DIR").unwrap()).join(filename);
     let mut file = BufWriter::new(File::create(&path).unwrap());
 
     writeln!(&mut file, "#[derive(Debug, Clone, PartialEq, Eq, Hash)]")?;
     writeln!(&mut file, "#[allow(non_camel_case_types)]")?;
+    writeln!(&mut file, "#[allow(clippy::upper_case_acronyms)]")?;
     writeln!(&mut file, "pub enum {} {{", typename)?;
     for entry in entries.iter() {
         writeln!(&mut file, "    r#{},", safe(entry.to_owned()))?;
     }
+    for variant in extra_variants.iter() {
+        writeln!(&mut file, "    {}", variant)?;
+    }
     writeln!(&mut file, "}}\n")?;
 
     write!(
@@ -0,0 +1,167 @@
+use crate::cmap::Code;
+
+include!(concat!(env!("OUT_DIR"), "/codegen_cid_tables.rs"));
+
+// the tables of one of the Adobe character collections, generated from its Adobe-<ordering>.txt
+#[derive(Debug)]
+pub(crate) struct Collection {
+    ordering: &'static str,
+    // (first CID, last CID, first Unicode scalar value), by CID
+    unicode: &'static [(u32, u32, u32)],
+    cmaps: &'static [CMapTable],
+}
+
+// the codespace and codes of a predefined CMap, the codes as (code length, first code, last code,
+// first CID), by length and code
+//
+// The vertical table holds the codes which map to another CID in vertical writing.
+#[derive(Debug)]
+struct CMapTable {
+    name: &'static str,
+    codespace: &'static [(u8, u32, u32)],
+    horizontal: &'static [(u8, u32, u32, u32)],
+    vertical: &'static [(u8, u32, u32, u32)],
+}
+
+// the collection for a /CIDSystemInfo /Ordering, e.g. Japan1
+pub(crate) fn collection(ordering: &str) -> Option<&'static Collection> {
+    COLLECTIONS
+        .iter()
+        .find(|collection| collection.ordering == ordering)
+}
+
+// the CID of a code in a table of code ranges
+fn find(codes: &[(u8, u32, u32, u32)], code: Code) -> Option<u32> {
+    let key = (code.length as u8, code.value);
+    let index = codes.partition_point(|&(length, low, _, _)| (length, low) <= key);
+    let &(length, low, high, cid) = codes.get(index.checked_sub(1)?)?;
+    (length == key.0 && code.value <= high).then(|| cid + (code.value - low))
+}
+
+impl Collection {
+    pub fn unicode(&self, cid: u32) -> Option<char> {
+        let index = self.unicode.partition_point(|&(low, _, _)| low <= cid);
+        let &(low, high, value) = self.unicode.get(index.checked_sub(1)?)?;
+        match cid <= high {
+            true => std::char::from_u32(value + (cid - low)),
+            false => None,
+        }
+    }
+
+    // the codespace of a predefined CMap, named without -H or -V, as (low, high) codes
+    pub fn codespace(&self, cmap: &str) -> Vec<(Code, Code)> {
+        self.cmaps
+            .iter()
+            .filter(|table| table.name == cmap)
+            .flat_map(|table| table.codespace.iter())
+            .map(|&(length, low, high)| {
+                let code = |value| Code {
+                    value,
+                    length: length as usize,
+                };
+                (code(low), code(high))
+            })
+            .collect()
+    }
+
+    // the CID of a code in a predefined CMap, named without -H or -V, e.g. 90ms-RKSJ
+    pub fn cid(&self, cmap: &str, vertical: bool, code: Code) -> Option<u32> {
+        let table = self.cmaps.iter().find(|table| table.name == cmap)?;
+        match vertical {
+            true => find(table.vertical, code).or_else(|| find(table.horizontal, code)),
+            false => find(table.horizontal, code),
+        }
+    }
+
+    // the CID of a character in the collection's Unicode CMaps
+    pub fn unicode_cid(&self, ch: char, vertical: bool) -> Option<u32> {
+        let table = self
+            .cmaps
+            .iter()
+            .find(|table| table.name.ends_with("-UTF32"))?;
+        self.cid(table.name, vertical, Code::new(&(ch as u32).to_be_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEST: Collection = Collection {
+        ordering: "Test1",
+        unicode: &[(1, 2, 0x20), (633, 633, 0x3000), (7887, 7887, 0x3001)],
+        cmaps: &[
+            CMapTable {
+                name: "Test-RKSJ",
+                codespace: &[(1, 0x00, 0x80), (2, 0x8140, 0x9ffc)],
+                horizontal: &[(1, 0x20, 0x21, 1), (2, 0x8140, 0x8141, 633)],
+                vertical: &[(2, 0x8141, 0x8141, 7887)],
+            },
+            CMapTable {
+                name: "UniTest-UTF32",
+                codespace: &[],
+                horizontal: &[(4, 0x20, 0x20, 1), (4, 0x3000, 0x3001, 633)],
+                vertical: &[(4, 0x3001, 0x3001, 7887)],
+            },
+        ],
+    };
+
+    #[test]
+    fn lookups() {
+        assert_eq!(TEST.unicode(633), Some('\u{3000}'));
+        assert_eq!(TEST.unicode(2), Some('!'));
+        assert_eq!(TEST.unicode(3), None);
+        assert_eq!(TEST.unicode(0), None);
+        assert_eq!(
+            TEST.cid("Test-RKSJ", false, Code::new(b"\x81\x41")),
+            Some(634)
+        );
+        assert_eq!(
+            TEST.cid("Test-RKSJ", true, Code::new(b"\x81\x41")),
+            Some(7887)
+        );
+        assert_eq!(
+            TEST.cid("Test-RKSJ", true, Code::new(b"\x81\x40")),
+            Some(633)
+        );
+        assert_eq!(TEST.cid("Test-RKSJ", false, Code::new(b"\x81\x42")), None);
+        assert_eq!(TEST.cid("Test-RKSJ", false, Code::new(b"\x00\x20")), None);
+        assert_eq!(TEST.cid("Test-EUC", false, Code::new(b"\x20")), None);
+        assert_eq!(TEST.unicode_cid('\u{3001}', true), Some(7887));
+        assert_eq!(TEST.unicode_cid('!', false), None);
+        assert_eq!(
+            TEST.codespace("Test-RKSJ")[1],
+            (Code::new(b"\x81\x40"), Code::new(b"\x9f\xfc"))
+        );
+        assert!(TEST.codespace("Test-EUC").is_empty());
+    }
+
+    #[test]
+    fn collections() {
+        for ordering in ["Japan1", "GB1", "CNS1", "Korea1"] {
+            assert_eq!(collection(ordering).unwrap().ordering, ordering);
+        }
+        assert!(collection("Identity").is_none());
+    }
+
+    #[test]
+    fn japan1() {
+        let japan1 = collection("Japan1").unwrap();
+        assert_eq!(japan1.unicode(843), Some('\u{3042}'));
+        assert_eq!(japan1.unicode(1), Some(' '));
+        assert_eq!(japan1.unicode(0), None);
+        assert_eq!(japan1.cid("H", false, Code::new(b"\x24\x22")), Some(843));
+        assert_eq!(
+            japan1.cid("90ms-RKSJ", false, Code::new(b"\x82\xa0")),
+            Some(843)
+        );
+        // the vertical form of the ideographic comma
+        assert_eq!(japan1.cid("H", false, Code::new(b"\x21\x22")), Some(634));
+        assert_eq!(japan1.cid("H", true, Code::new(b"\x21\x22")), Some(7887));
+        assert_eq!(japan1.unicode_cid('\u{3042}', false), Some(843));
+        assert_eq!(
+            japan1.codespace("H"),
+            vec![(Code::new(b"\x21\x21"), Code::new(b"\x7e\x7e"))]
+        );
+    }
+}
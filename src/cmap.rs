@@ -0,0 +1,694 @@
+use std::collections::HashMap;
+
+use crate::cid_tables::collection;
+use crate::encoding::glyph_to_unicode;
+use crate::next_object::next_object;
+use crate::pdf_source::{ByteSource, Source};
+use crate::pdf_types::*;
+
+// the longest character code a CMap can define, in bytes
+const MAX_CODE_LENGTH: usize = 4;
+
+// a character code of one to four bytes, read from a string shown in a font
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Code {
+    pub value: u32,
+    pub length: usize,
+}
+
+impl Code {
+    pub fn new(bytes: &[u8]) -> Code {
+        Code {
+            value: bytes
+                .iter()
+                .fold(0, |value, &byte| (value << 8) | byte as u32),
+            length: bytes.len(),
+        }
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        self.value.to_be_bytes()[4 - self.length.min(4)..].to_vec()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CodespaceRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+impl CodespaceRange {
+    fn contains(&self, bytes: &[u8]) -> bool {
+        bytes.len() == self.low.len()
+            && bytes
+                .iter()
+                .zip(self.low.iter().zip(self.high.iter()))
+                .all(|(byte, (low, high))| low <= byte && byte <= high)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Destination {
+    // the UTF-16 text for the first code, incremented for the codes which follow
+    Incrementing(Vec<u16>),
+    // the text for each code in the range
+    Array(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Range {
+    low: Code,
+    high: u32,
+    destination: Destination,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CidRange {
+    low: Code,
+    high: u32,
+    cid: u32,
+}
+
+// the Unicode encoding used for the character codes of a predefined Uni... CMap
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnicodeCodes {
+    Utf8,
+    Utf16,
+    Utf32,
+}
+
+impl UnicodeCodes {
+    fn decode(self, code: Code) -> Option<String> {
+        match self {
+            UnicodeCodes::Utf8 => String::from_utf8(code.bytes()).ok(),
+            UnicodeCodes::Utf16 => String::from_utf16(&utf16(&code.bytes())).ok(),
+            UnicodeCodes::Utf32 => std::char::from_u32(code.value).map(|ch| ch.to_string()),
+        }
+    }
+}
+
+// the character set of a predefined CMap for a legacy CJK encoding
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Charset {
+    ShiftJis,
+    EucJp,
+    Gbk,
+    Big5,
+    EucKr,
+    // the JIS X 0208 codes of the CMaps H and V, which are the EUC-JP codes without their high
+    // bits set
+    Jis,
+}
+
+impl Charset {
+    fn decode(self, code: Code) -> Option<String> {
+        let (encoding, high_bits) = match self {
+            Charset::ShiftJis => (encoding_rs::SHIFT_JIS, 0),
+            Charset::EucJp => (encoding_rs::EUC_JP, 0),
+            Charset::Gbk => (encoding_rs::GBK, 0),
+            Charset::Big5 => (encoding_rs::BIG5, 0),
+            Charset::EucKr => (encoding_rs::EUC_KR, 0),
+            Charset::Jis => (encoding_rs::EUC_JP, 0x80),
+        };
+        let bytes: Vec<u8> = code.bytes().iter().map(|byte| byte | high_bits).collect();
+        encoding
+            .decode_without_bom_handling_and_without_replacement(&bytes)
+            .map(|text| text.into_owned())
+    }
+}
+
+// the predefined CMaps for legacy CJK encodings, named without -H or -V, with their character
+// collection and character set, their codespaces and CIDs are in the collection's tables
+const LEGACY_CMAPS: [(&str, &str, Option<Charset>); 22] = [
+    ("H", "Japan1", Some(Charset::Jis)),
+    ("83pv-RKSJ", "Japan1", Some(Charset::ShiftJis)),
+    ("90ms-RKSJ", "Japan1", Some(Charset::ShiftJis)),
+    ("90msp-RKSJ", "Japan1", Some(Charset::ShiftJis)),
+    ("90pv-RKSJ", "Japan1", Some(Charset::ShiftJis)),
+    ("Add-RKSJ", "Japan1", Some(Charset::ShiftJis)),
+    ("Ext-RKSJ", "Japan1", Some(Charset::ShiftJis)),
+    ("EUC", "Japan1", Some(Charset::EucJp)),
+    ("GB-EUC", "GB1", Some(Charset::Gbk)),
+    ("GBpc-EUC", "GB1", Some(Charset::Gbk)),
+    ("GBK-EUC", "GB1", Some(Charset::Gbk)),
+    ("GBKp-EUC", "GB1", Some(Charset::Gbk)),
+    ("GBK2K", "GB1", Some(Charset::Gbk)),
+    ("CNS-EUC", "CNS1", None),
+    ("B5pc", "CNS1", Some(Charset::Big5)),
+    ("ETen-B5", "CNS1", Some(Charset::Big5)),
+    ("ETenms-B5", "CNS1", Some(Charset::Big5)),
+    ("HKscs-B5", "CNS1", Some(Charset::Big5)),
+    ("KSC-EUC", "Korea1", Some(Charset::EucKr)),
+    ("KSCpc-EUC", "Korea1", Some(Charset::EucKr)),
+    ("KSCms-UHC", "Korea1", Some(Charset::EucKr)),
+    ("KSCms-UHC-HW", "Korea1", Some(Charset::EucKr)),
+];
+
+// the character collections of the predefined Unicode CMaps, by name prefix
+const UNICODE_ORDERINGS: [(&str, &str); 4] = [
+    ("UniJIS", "Japan1"),
+    ("UniGB", "GB1"),
+    ("UniCNS", "CNS1"),
+    ("UniKS", "Korea1"),
+];
+
+// a CMap mapping character codes to Unicode, as found in a font's /ToUnicode stream, or to
+// CIDs, as used for the /Encoding of a Type0 font
+//
+// Of the predefined CMaps Identity-H and Identity-V, the Unicode based ones (such as
+// UniJIS-UCS2-H or UniGB-UTF16-V) and those for legacy CJK encodings (such as 90ms-RKSJ-H or
+// GBK-EUC-H) are known. The codes of the Unicode and legacy CMaps decode to Unicode directly,
+// their CIDs are looked up in the tables of their Adobe character collection, as is the text of
+// CNS-EUC codes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CMap {
+    pub name: Option<String>,
+    pub vertical: bool,
+    // the CMap named by usecmap, its mappings apply when this CMap doesn't define a code
+    pub use_cmap: Option<String>,
+    parent: Option<Box<CMap>>,
+    codespace: Vec<CodespaceRange>,
+    chars: HashMap<Code, String>,
+    ranges: Vec<Range>,
+    cid_chars: HashMap<Code, u32>,
+    cid_ranges: Vec<CidRange>,
+    unicode_codes: Option<UnicodeCodes>,
+    // the character collection of a predefined Unicode or legacy CMap, e.g. Japan1
+    ordering: Option<&'static str>,
+    // the table name and character set of a predefined legacy CMap
+    legacy: Option<(&'static str, Option<Charset>)>,
+}
+
+impl CMap {
+    pub fn parse(data: Vec<u8>) -> crate::Result<CMap> {
+        let mut source: Box<dyn Source> = Box::new(ByteSource::new(data));
+        let mut cmap = CMap::default();
+        let mut operands = vec![];
+        while let Some(object) = next_object(&mut source)? {
+            match object {
+                PdfObject::Keyword(PdfKeyword::endcodespacerange) => {
+                    for range in operands.chunks_exact(2) {
+                        if let [PdfObject::String(low), PdfObject::String(high)] = range {
+                            cmap.add_codespace_range(low, high);
+                        }
+                    }
+                }
+                PdfObject::Keyword(PdfKeyword::endbfchar) => {
+                    for mapping in operands.chunks_exact(2) {
+                        if let (PdfObject::String(code), Some(text)) =
+                            (&mapping[0], destination(&mapping[1]))
+                        {
+                            cmap.chars.insert(Code::new(code), text);
+                        }
+                    }
+                }
+                PdfObject::Keyword(PdfKeyword::endbfrange) => {
+                    for mapping in operands.chunks_exact(3) {
+                        cmap.add_range(&mapping[0], &mapping[1], &mapping[2]);
+                    }
+                }
+                PdfObject::Keyword(PdfKeyword::endcidchar) => {
+                    for mapping in operands.chunks_exact(2) {
+                        if let (PdfObject::String(code), Some(cid)) =
+                            (&mapping[0], mapping[1].as_f64())
+                        {
+                            cmap.cid_chars.insert(Code::new(code), cid as u32);
+                        }
+                    }
+                }
+                PdfObject::Keyword(PdfKeyword::endcidrange) => {
+                    for mapping in operands.chunks_exact(3) {
+                        if let (PdfObject::String(low), PdfObject::String(high), Some(cid)) =
+                            (&mapping[0], &mapping[1], mapping[2].as_f64())
+                        {
+                            if low.len() == high.len() {
+                                cmap.cid_ranges.push(CidRange {
+                                    low: Code::new(low),
+                                    high: Code::new(high).value,
+                                    cid: cid as u32,
+                                });
+                            }
+                        }
+                    }
+                }
+                PdfObject::Keyword(PdfKeyword::usecmap) => {
+                    cmap.use_cmap = operands
+                        .last()
+                        .and_then(|name| name.as_name())
+                        .map(|name| name.text());
+                }
+                // /CMapName and /WMode are set by def, which isn't a known keyword
+                PdfObject::Keyword(_) => {
+                    if let [.., key, value] = &operands[..] {
+                        match key.as_name() {
+                            Some(PdfName::CMapName) => {
+                                cmap.name = value.as_name().map(|name| name.text())
+                            }
+                            Some(PdfName::WMode) => cmap.vertical = value.as_f64() == Some(1.0),
+                            _ => {}
+                        }
+                    }
+                }
+                operand => {
+                    operands.push(operand);
+                    continue;
+                }
+            }
+            operands.clear();
+        }
+        if let Some(parent) = cmap.use_cmap.as_deref().and_then(CMap::predefined) {
+            cmap.parent = Some(Box::new(parent));
+        }
+        Ok(cmap)
+    }
+
+    // the predefined CMaps which can be constructed without their CMap files
+    pub fn predefined(name: &str) -> Option<CMap> {
+        let vertical = name.ends_with("-V");
+        let base = match name {
+            "H" | "V" => "H",
+            name => name
+                .strip_suffix("-H")
+                .or_else(|| name.strip_suffix("-V"))
+                .unwrap_or(name),
+        };
+        if let Some(&(table, ordering, charset)) = LEGACY_CMAPS.iter().find(|cmap| cmap.0 == base) {
+            let mut cmap = CMap {
+                name: Some(name.to_owned()),
+                vertical,
+                ordering: Some(ordering),
+                legacy: Some((table, charset)),
+                ..CMap::default()
+            };
+            for (low, high) in collection(ordering)?.codespace(table) {
+                cmap.add_codespace_range(&low.bytes(), &high.bytes());
+            }
+            return Some(cmap);
+        }
+        let (unicode_codes, codespace): (_, &[(&[u8], &[u8])]) = if name.starts_with("Identity-") {
+            (None, &[(b"\x00\x00", b"\xff\xff")])
+        } else if !name.starts_with("Uni") {
+            return None;
+        } else if name.contains("-UCS2-") {
+            (Some(UnicodeCodes::Utf16), &[(b"\x00\x00", b"\xff\xff")])
+        } else if name.contains("-UTF16-") {
+            (
+                Some(UnicodeCodes::Utf16),
+                &[
+                    (b"\x00\x00", b"\xd7\xff"),
+                    (b"\xe0\x00", b"\xff\xff"),
+                    (b"\xd8\x00\xdc\x00", b"\xdb\xff\xdf\xff"),
+                ],
+            )
+        } else if name.contains("-UTF8-") {
+            (
+                Some(UnicodeCodes::Utf8),
+                &[
+                    (b"\x00", b"\x7f"),
+                    (b"\xc2\x80", b"\xdf\xbf"),
+                    (b"\xe0\x80\x80", b"\xef\xbf\xbf"),
+                    (b"\xf0\x80\x80\x80", b"\xf4\xbf\xbf\xbf"),
+                ],
+            )
+        } else if name.contains("-UTF32-") {
+            (
+                Some(UnicodeCodes::Utf32),
+                &[(b"\x00\x00\x00\x00", b"\x00\x10\xff\xff")],
+            )
+        } else {
+            return None;
+        };
+        let mut cmap = CMap {
+            name: Some(name.to_owned()),
+            vertical,
+            unicode_codes,
+            ordering: UNICODE_ORDERINGS
+                .iter()
+                .find(|(prefix, _)| name.starts_with(prefix))
+                .map(|&(_, ordering)| ordering),
+            ..CMap::default()
+        };
+        for (low, high) in codespace.iter() {
+            cmap.add_codespace_range(low, high);
+        }
+        if unicode_codes.is_none() {
+            cmap.cid_ranges.push(CidRange {
+                low: Code::new(b"\x00\x00"),
+                high: 0xffff,
+                cid: 0,
+            });
+        }
+        Some(cmap)
+    }
+
+    // makes a CMap, usually one named by use_cmap, the fallback for codes this one doesn't map
+    pub fn set_parent(&mut self, parent: CMap) {
+        self.parent = Some(Box::new(parent));
+    }
+
+    pub fn cid(&self, code: Code) -> Option<u32> {
+        if let Some(&cid) = self.cid_chars.get(&code) {
+            return Some(cid);
+        }
+        if let Some(range) = self.cid_ranges.iter().rev().find(|range| {
+            range.low.length == code.length
+                && range.low.value <= code.value
+                && code.value <= range.high
+        }) {
+            return Some(range.cid + (code.value - range.low.value));
+        }
+        let cid = self.ordering.and_then(collection).and_then(|collection| {
+            match (self.legacy, self.unicode_codes) {
+                (Some((table, _)), _) => collection.cid(table, self.vertical, code),
+                (None, Some(codes)) => {
+                    let text = codes.decode(code)?;
+                    let mut chars = text.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(ch), None) => collection.unicode_cid(ch, self.vertical),
+                        _ => None,
+                    }
+                }
+                (None, None) => None,
+            }
+        });
+        cid.or_else(|| self.parent.as_ref().and_then(|parent| parent.cid(code)))
+    }
+
+    // splits a string into character codes, according to the codespace ranges
+    pub fn codes<'a>(&'a self, text: &'a [u8]) -> Codes<'a> {
+        Codes {
+            cmap: self,
+            text,
+            position: 0,
+        }
+    }
+
+    pub fn unicode(&self, code: Code) -> Option<String> {
+        if let Some(text) = self.chars.get(&code) {
+            return Some(text.clone());
+        }
+        let range = match self.ranges.iter().rev().find(|range| {
+            range.low.length == code.length
+                && range.low.value <= code.value
+                && code.value <= range.high
+        }) {
+            Some(range) => range,
+            None => {
+                return match (self.unicode_codes, self.legacy) {
+                    (Some(codes), _) => codes.decode(code),
+                    // codes without a decoder, such as those of CNS 11643, take their text from
+                    // their CID
+                    (None, Some((_, charset))) => charset
+                        .and_then(|charset| charset.decode(code))
+                        .or_else(|| {
+                            let cid = self.cid(code)?;
+                            let ch = self.ordering.and_then(collection)?.unicode(cid)?;
+                            Some(ch.to_string())
+                        }),
+                    _ => self.parent.as_ref().and_then(|parent| parent.unicode(code)),
+                };
+            }
+        };
+        let offset = code.value - range.low.value;
+        match &range.destination {
+            Destination::Incrementing(units) => {
+                let mut units = units.clone();
+                let last = units.last_mut()?;
+                *last = last.wrapping_add(offset as u16);
+                Some(String::from_utf16_lossy(&units))
+            }
+            Destination::Array(texts) => texts.get(offset as usize).cloned(),
+        }
+    }
+
+    fn add_codespace_range(&mut self, low: &[u8], high: &[u8]) {
+        if low.len() == high.len() && !low.is_empty() && low.len() <= MAX_CODE_LENGTH {
+            self.codespace.push(CodespaceRange {
+                low: low.to_vec(),
+                high: high.to_vec(),
+            });
+        }
+    }
+
+    fn add_range(&mut self, low: &PdfObject, high: &PdfObject, destination: &PdfObject) {
+        let (low, high) = match (low, high) {
+            (PdfObject::String(low), PdfObject::String(high)) if low.len() == high.len() => {
+                (Code::new(low), Code::new(high))
+            }
+            _ => return,
+        };
+        let destination = match destination {
+            PdfObject::String(text) => Destination::Incrementing(utf16(text)),
+            PdfObject::Array(texts) => Destination::Array(
+                texts
+                    .iter()
+                    .map(|text| destination_text(text).unwrap_or_default())
+                    .collect(),
+            ),
+            _ => return,
+        };
+        self.ranges.push(Range {
+            low,
+            high: high.value,
+            destination,
+        });
+    }
+
+    fn in_codespace(&self, bytes: &[u8]) -> bool {
+        self.codespace.iter().any(|range| range.contains(bytes))
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.in_codespace(bytes))
+    }
+
+    // the code length to use when no codespace range matches
+    fn default_length(&self) -> usize {
+        let parent = self.parent.as_ref().map(|parent| parent.default_length());
+        match self
+            .codespace
+            .iter()
+            .map(|range| range.low.len())
+            .min()
+            .or(parent)
+        {
+            Some(length) => length,
+            None => self
+                .chars
+                .keys()
+                .map(|code| code.length)
+                .chain(self.ranges.iter().map(|range| range.low.length))
+                .next()
+                .unwrap_or(1),
+        }
+    }
+}
+
+pub struct Codes<'a> {
+    cmap: &'a CMap,
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Iterator for Codes<'a> {
+    type Item = Code;
+
+    fn next(&mut self) -> Option<Code> {
+        let rest = &self.text[self.position..];
+        if rest.is_empty() {
+            return None;
+        }
+        let length = (1..=MAX_CODE_LENGTH.min(rest.len()))
+            .find(|&n| self.cmap.in_codespace(&rest[..n]))
+            .unwrap_or_else(|| self.cmap.default_length().min(rest.len()));
+        self.position += length;
+        Some(Code::new(&rest[..length]))
+    }
+}
+
+fn utf16(text: &[u8]) -> Vec<u16> {
+    text.chunks(2)
+        .map(|pair| match *pair {
+            [high, low] => (high as u16) << 8 | low as u16,
+            [low] => low as u16,
+            _ => 0,
+        })
+        .collect()
+}
+
+fn destination_text(object: &PdfObject) -> Option<String> {
+    match object {
+        PdfObject::String(text) => Some(String::from_utf16_lossy(&utf16(text))),
+        _ => None,
+    }
+}
+
+// a bfchar destination is UTF-16 text, or a glyph name
+fn destination(object: &PdfObject) -> Option<String> {
+    match object.as_name() {
+        Some(name) => glyph_to_unicode(&name.text()),
+        None => destination_text(object),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CMAP: &[u8] = b"/CIDInit /ProcSet findresource begin
+12 dict begin
+begincmap
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def
+/CMapName /Adobe-Identity-UCS def
+/CMapType 2 def
+2 begincodespacerange
+<00> <80>
+<8140> <9ffc>
+endcodespacerange
+3 beginbfchar
+<41> <0041>
+<8140> <3000>
+<42> /fi
+endbfchar
+3 beginbfrange
+<61> <7a> <0061>
+<30> <32> [<0030> <00660066> <d835dc00>]
+<9000> <90ff> <4e00>
+endbfrange
+endcmap
+CMapName currentdict /CMap defineresource pop
+end
+end
+";
+
+    fn decode(cmap: &CMap, text: &[u8]) -> String {
+        cmap.codes(text)
+            .map(|code| cmap.unicode(code).unwrap_or_else(|| "?".to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn bfchar_and_bfrange() {
+        let cmap = CMap::parse(CMAP.to_vec()).unwrap();
+        assert_eq!(decode(&cmap, b"Abcz"), "Abcz");
+        assert_eq!(decode(&cmap, b"B"), "\u{fb01}");
+        assert_eq!(decode(&cmap, b"0123"), "0ff\u{1d400}?");
+    }
+
+    #[test]
+    fn multi_byte_codes() {
+        let cmap = CMap::parse(CMAP.to_vec()).unwrap();
+        let codes: Vec<_> = cmap.codes(b"a\x81\x40\x90\x41\xff").collect();
+        assert_eq!(
+            codes,
+            vec![
+                Code::new(b"a"),
+                Code::new(b"\x81\x40"),
+                Code::new(b"\x90\x41"),
+                Code::new(b"\xff"),
+            ]
+        );
+        assert_eq!(decode(&cmap, b"a\x81\x40\x90\x41"), "a\u{3000}\u{4e41}");
+    }
+
+    #[test]
+    fn cid_mappings() {
+        let cmap = CMap::parse(
+            b"/CMapName /Test-H def /WMode 1 def /Identity-H usecmap \
+              1 begincodespacerange <00> <7f> endcodespacerange \
+              1 begincidchar <41> 500 endcidchar \
+              1 begincidrange <61> <7a> 600 endcidrange"
+                .to_vec(),
+        )
+        .unwrap();
+        assert_eq!(cmap.name, Some("Test-H".to_owned()));
+        assert!(cmap.vertical);
+        assert_eq!(cmap.use_cmap, Some("Identity-H".to_owned()));
+        let cids: Vec<_> = cmap
+            .codes(b"Ab\x80\x01")
+            .map(|code| cmap.cid(code))
+            .collect();
+        assert_eq!(cids, vec![Some(500), Some(601), Some(0x8001)]);
+    }
+
+    #[test]
+    fn unicode_cmaps() {
+        let cmap = CMap::predefined("UniJIS-UCS2-H").unwrap();
+        assert_eq!(decode(&cmap, b"\x30\x42\x00\x41"), "\u{3042}A");
+        assert_eq!(cmap.cid(Code::new(b"\x30\x42")), Some(843));
+        let cmap = CMap::predefined("UniGB-UTF16-V").unwrap();
+        assert!(cmap.vertical);
+        assert_eq!(
+            decode(&cmap, b"\xd8\x35\xdc\x00\x4e\x00"),
+            "\u{1d400}\u{4e00}"
+        );
+        let cmap = CMap::predefined("UniKS-UTF8-H").unwrap();
+        assert_eq!(decode(&cmap, "a\u{ac00}".as_bytes()), "a\u{ac00}");
+        assert_eq!(CMap::predefined("UniXX-UCS2-H").unwrap().ordering, None);
+    }
+
+    #[test]
+    fn legacy_cmaps() {
+        let cmap = CMap::predefined("90ms-RKSJ-H").unwrap();
+        let codes: Vec<_> = cmap.codes(b"A\x82\xa0\xb1\x88\x9f").collect();
+        assert_eq!(
+            codes.iter().map(|code| code.length).collect::<Vec<_>>(),
+            vec![1, 2, 1, 2]
+        );
+        assert_eq!(
+            decode(&cmap, b"A\x82\xa0\xb1\x88\x9f"),
+            "A\u{3042}\u{ff71}\u{4e9c}"
+        );
+        assert_eq!(cmap.cid(codes[1]), Some(843));
+        assert_eq!(
+            decode(&CMap::predefined("EUC-V").unwrap(), b"\xa4\xa2"),
+            "\u{3042}"
+        );
+        let cmap = CMap::predefined("H").unwrap();
+        assert_eq!(decode(&cmap, b"\x24\x22"), "\u{3042}");
+        assert_eq!(cmap.cid(Code::new(b"\x24\x22")), Some(843));
+        assert_eq!(cmap.legacy, Some(("H", Some(Charset::Jis))));
+        let cmap = CMap::predefined("GBK-EUC-H").unwrap();
+        assert_eq!(decode(&cmap, b"a\xc4\xe3\xba\xc3"), "a\u{4f60}\u{597d}");
+        assert_eq!(cmap.ordering, Some("GB1"));
+        let cmap = CMap::predefined("ETen-B5-V").unwrap();
+        assert!(cmap.vertical);
+        assert_eq!(decode(&cmap, b"\xa4\xa4\xa4\xe5"), "\u{4e2d}\u{6587}");
+        assert_eq!(
+            decode(&CMap::predefined("KSC-EUC-H").unwrap(), b"\xb0\xa1"),
+            "\u{ac00}"
+        );
+        assert_eq!(
+            decode(&CMap::predefined("KSCms-UHC-H").unwrap(), b"\x8c\x63"),
+            "\u{b620}"
+        );
+        // CNS 11643 has no decoder, its text comes from the CID
+        let cmap = CMap::predefined("CNS-EUC-H").unwrap();
+        assert_eq!(
+            decode(&cmap, b"a\xc4\xa1\x8e\xa2\xa1\xa1"),
+            "a\u{4e00}\u{4e42}"
+        );
+        assert_eq!(cmap.cid(Code::new(b"\xc4\xa1")), Some(595));
+        assert_eq!(CMap::predefined("Unknown-H"), None);
+    }
+
+    #[test]
+    fn use_predefined_cmap() {
+        let cmap = CMap::parse(
+            b"/CMapName /Test-RKSJ-H def /90ms-RKSJ-H usecmap \
+              1 begincidchar <82a0> 843 endcidchar"
+                .to_vec(),
+        )
+        .unwrap();
+        let codes: Vec<_> = cmap.codes(b"\x82\xa0\x88\x9f").collect();
+        assert_eq!(codes, vec![Code::new(b"\x82\xa0"), Code::new(b"\x88\x9f")]);
+        assert_eq!(cmap.cid(codes[0]), Some(843));
+        assert_eq!(decode(&cmap, b"\x82\xa0\x88\x9f"), "\u{3042}\u{4e9c}");
+    }
+
+    #[test]
+    fn no_codespace() {
+        let cmap = CMap::parse(b"1 beginbfchar <0003> <0020> endbfchar".to_vec()).unwrap();
+        assert_eq!(decode(&cmap, b"\x00\x03\x00\x03"), "  ");
+    }
+}
@@ -0,0 +1,215 @@
+use crate::pdf_types::*;
+use crate::standard_fonts::strip_subset_tag;
+
+include!(concat!(env!("OUT_DIR"), "/codegen_encodings.rs"));
+
+// maps single byte character codes to glyph names
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encoding {
+    glyphs: Vec<Option<String>>,
+}
+
+impl Default for Encoding {
+    fn default() -> Encoding {
+        Encoding::standard()
+    }
+}
+
+impl Encoding {
+    fn from_table(table: &[Option<&str>; 256]) -> Encoding {
+        Encoding {
+            glyphs: table.iter().map(|glyph| glyph.map(str::to_owned)).collect(),
+        }
+    }
+
+    pub fn standard() -> Encoding {
+        Encoding::from_table(&STANDARD_ENCODING)
+    }
+
+    pub fn symbol() -> Encoding {
+        Encoding::from_table(&SYMBOL_ENCODING)
+    }
+
+    pub fn zapf_dingbats() -> Encoding {
+        Encoding::from_table(&ZAPF_DINGBATS_ENCODING)
+    }
+
+    pub fn pdf_doc() -> Encoding {
+        Encoding::from_table(&PDF_DOC_ENCODING)
+    }
+
+    // the encoding named by a /Encoding or /BaseEncoding entry
+    pub fn from_name(name: &PdfName) -> Option<Encoding> {
+        match name {
+            PdfName::StandardEncoding => Some(Encoding::standard()),
+            PdfName::WinAnsiEncoding => Some(Encoding::from_table(&WIN_ANSI_ENCODING)),
+            PdfName::MacRomanEncoding => Some(Encoding::from_table(&MAC_ROMAN_ENCODING)),
+            PdfName::MacExpertEncoding => Some(Encoding::from_table(&MAC_EXPERT_ENCODING)),
+            _ => None,
+        }
+    }
+
+    // the encoding a font uses when its dictionary doesn't name one
+    //
+    // This is only known for the symbolic standard fonts, anything else is assumed to use
+    // StandardEncoding.
+    pub fn builtin(base_font: &str) -> Encoding {
+        match strip_subset_tag(base_font) {
+            "Symbol" => Encoding::symbol(),
+            "ZapfDingbats" => Encoding::zapf_dingbats(),
+            _ => Encoding::standard(),
+        }
+    }
+
+    // applies a /Differences array: a code followed by the glyph names for consecutive codes
+    pub fn apply_differences(&mut self, differences: &[PdfObject]) {
+        let mut code = 0;
+        for item in differences.iter() {
+            match item {
+                PdfObject::Number(PdfNumber::Integer(n)) => code = *n as usize,
+                PdfObject::Name(_) | PdfObject::Symbol(_) => {
+                    if code < self.glyphs.len() {
+                        self.glyphs[code] = glyph_name(item);
+                    }
+                    code += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn glyph_name(&self, code: u8) -> Option<&str> {
+        self.glyphs[code as usize].as_deref()
+    }
+}
+
+fn glyph_name(object: &PdfObject) -> Option<String> {
+    object.as_name().map(|name| name.text())
+}
+
+// the Unicode text for a glyph name, using the Adobe Glyph List and ZapfDingbats glyph list,
+// as well as the uniXXXX and uXXXX[XX] conventions
+pub fn glyph_to_unicode(name: &str) -> Option<String> {
+    // a suffix such as ".sc" or ".alt" names a variant of the same character
+    let name = name.split('.').next().unwrap_or("");
+    if let Some(text) = GLYPH_LIST.get(name) {
+        return Some((*text).to_owned());
+    }
+    // ligatures may be named by their components, as in "f_f_i"
+    if name.contains('_') {
+        return name.split('_').map(glyph_to_unicode).collect();
+    }
+    if let Some(hex) = name.strip_prefix("uni") {
+        if !hex.is_empty() && hex.len() % 4 == 0 {
+            let units: Option<Vec<u16>> = (0..hex.len())
+                .step_by(4)
+                .map(|i| hex_value(&hex[i..i + 4]).map(|unit| unit as u16))
+                .collect();
+            return units
+                .filter(|units| units.iter().all(|&unit| !(0xd800..0xe000).contains(&unit)))
+                .and_then(|units| String::from_utf16(&units).ok());
+        }
+    }
+    if let Some(hex) = name.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) {
+            return hex_value(hex)
+                .and_then(std::char::from_u32)
+                .map(|ch| ch.to_string());
+        }
+    }
+    None
+}
+
+fn hex_value(hex: &str) -> Option<u32> {
+    if hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        u32::from_str_radix(hex, 16).ok()
+    } else {
+        None
+    }
+}
+
+// decodes a text string such as a document title, which is either UTF-16BE with a byte order
+// mark or PDFDocEncoding
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(b"\xfe\xff") {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    bytes
+        .iter()
+        .map(|&byte| match PDF_DOC_ENCODING[byte as usize] {
+            Some(glyph) => glyph_to_unicode(glyph).unwrap_or_default(),
+            // the control characters which PDFDocEncoding leaves undefined are passed through
+            None if byte < 0x20 => (byte as char).to_string(),
+            None => char::REPLACEMENT_CHARACTER.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differences() {
+        let mut encoding = Encoding::standard();
+        encoding.apply_differences(&[
+            PdfObject::Number(PdfNumber::Integer(1)),
+            PdfObject::Symbol(b"fi".to_vec()),
+            PdfObject::Symbol(b"fl".to_vec()),
+            PdfObject::Number(PdfNumber::Integer(0x41)),
+            PdfObject::Symbol(b"Aring".to_vec()),
+        ]);
+        assert_eq!(encoding.glyph_name(1), Some("fi"));
+        assert_eq!(encoding.glyph_name(2), Some("fl"));
+        assert_eq!(encoding.glyph_name(3), None);
+        assert_eq!(encoding.glyph_name(0x41), Some("Aring"));
+        assert_eq!(encoding.glyph_name(0x42), Some("B"));
+        assert_eq!(encoding.glyph_name(0x27), Some("quoteright"));
+    }
+
+    #[test]
+    fn glyph_names() {
+        assert_eq!(glyph_to_unicode("a"), Some("a".to_owned()));
+        assert_eq!(glyph_to_unicode("ffi"), Some("\u{fb03}".to_owned()));
+        assert_eq!(glyph_to_unicode("uni00410042"), Some("AB".to_owned()));
+        assert_eq!(glyph_to_unicode("u1F600"), Some("\u{1f600}".to_owned()));
+        assert_eq!(glyph_to_unicode("A.sc"), Some("A".to_owned()));
+        assert_eq!(glyph_to_unicode("Aogonek"), Some("\u{104}".to_owned()));
+        assert_eq!(glyph_to_unicode("a20"), Some("\u{2714}".to_owned()));
+        assert_eq!(glyph_to_unicode("f_f_i"), Some("ffi".to_owned()));
+        assert_eq!(glyph_to_unicode("uniD800"), None);
+        assert_eq!(glyph_to_unicode("uni00e9"), Some("\u{e9}".to_owned()));
+        assert_eq!(glyph_to_unicode("g123"), None);
+    }
+
+    #[test]
+    fn builtin_encodings() {
+        let win_ansi = Encoding::from_name(&PdfName::WinAnsiEncoding).unwrap();
+        assert_eq!(win_ansi.glyph_name(0x80), Some("Euro"));
+        assert_eq!(win_ansi.glyph_name(0x27), Some("quotesingle"));
+        let mac_roman = Encoding::from_name(&PdfName::MacRomanEncoding).unwrap();
+        assert_eq!(mac_roman.glyph_name(0x8e), Some("eacute"));
+        assert_eq!(
+            Encoding::builtin("ABCDEF+Symbol").glyph_name(0x61),
+            Some("alpha")
+        );
+        assert_eq!(
+            Encoding::builtin("ZapfDingbats").glyph_name(0x34),
+            Some("a20")
+        );
+        assert_eq!(Encoding::builtin("Helvetica"), Encoding::standard());
+    }
+
+    #[test]
+    fn text_strings() {
+        assert_eq!(
+            decode_text_string(b"caf\xe9 \x8dquoted\x8e"),
+            "caf\u{e9} \u{201c}quoted\u{201d}"
+        );
+        assert_eq!(decode_text_string(b"\xfe\xff\x00A\x04\x10"), "A\u{410}");
+    }
+}
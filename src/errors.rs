@@ -1,7 +1,14 @@
+use std::fmt;
+use std::io::{Read, SeekFrom};
+
 use thiserror::Error;
 
+use crate::pdf_source::Source;
 use crate::pdf_types::*;
 
+// number of bytes on either side of an error offset included in its excerpt
+const EXCERPT_RADIUS: u64 = 16;
+
 #[derive(Error, Debug)]
 pub enum PdfError {
     #[error("decompression error: {0:?}")]
@@ -10,12 +17,18 @@ pub enum PdfError {
     #[error("internal error: {0:?}")]
     InternalError(&'static str),
 
+    #[error("unsupported: {0}")]
+    Unsupported(&'static str),
+
     #[error("invalid pdf file: {0:?}")]
     InvalidPdf(&'static str),
 
     #[error("pdf keyword expected: {0:?}")]
     KeywordExpected(PdfKeyword),
 
+    #[error("wrong number of operands ({found}) for operator {operator:?}")]
+    InvalidOperandCount { operator: PdfKeyword, found: usize },
+
     #[error("reference not found")]
     InvalidReference,
 
@@ -28,6 +41,12 @@ pub enum PdfError {
     #[error("unexpected end of file")]
     EndOfFile,
 
+    #[error("{error} at {location}")]
+    Located {
+        error: Box<PdfError>,
+        location: Location,
+    },
+
     #[error("I/O error")]
     IoError(#[from] std::io::Error),
 
@@ -36,4 +55,84 @@ pub enum PdfError {
 
     #[error("failed to parse float")]
     ParseFloatError(#[from] std::num::ParseFloatError),
+
+    #[error("invalid search pattern")]
+    PatternError(#[from] regex::Error),
+}
+
+// where in the source a parse error was detected
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub offset: u64,
+    pub reference: Option<Reference>,
+    pub excerpt: Vec<u8>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "offset {}", self.offset)?;
+        if let Some(reference) = self.reference {
+            write!(f, " in object {} {}", reference.id, reference.gen)?;
+        }
+        write!(f, " near \"{}\"", self.excerpt.escape_ascii())
+    }
+}
+
+impl PdfError {
+    // attaches the current source position (and the object being parsed) to an error
+    pub(crate) fn locate(
+        self,
+        source: &mut Box<dyn Source>,
+        reference: Option<Reference>,
+    ) -> PdfError {
+        match self {
+            PdfError::Located {
+                error,
+                mut location,
+            } => {
+                location.reference = location.reference.or(reference);
+                PdfError::Located { error, location }
+            }
+            PdfError::IoError(_) => self,
+            error => match source.seek(SeekFrom::Current(0)) {
+                Ok(offset) => PdfError::Located {
+                    error: Box::new(error),
+                    location: Location {
+                        offset,
+                        reference,
+                        excerpt: excerpt(source, offset),
+                    },
+                },
+                Err(_) => error,
+            },
+        }
+    }
+
+    // the underlying error, without location information
+    pub fn kind(&self) -> &PdfError {
+        match self {
+            PdfError::Located { error, .. } => error.kind(),
+            error => error,
+        }
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            PdfError::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+}
+
+fn excerpt(source: &mut Box<dyn Source>, offset: u64) -> Vec<u8> {
+    let mut excerpt = vec![];
+    if source
+        .seek(SeekFrom::Start(offset.saturating_sub(EXCERPT_RADIUS)))
+        .is_ok()
+    {
+        let length = offset.min(EXCERPT_RADIUS) + EXCERPT_RADIUS;
+        let _ = source.by_ref().take(length).read_to_end(&mut excerpt);
+    }
+    let _ = source.seek(SeekFrom::Start(offset));
+    excerpt
 }
@@ -0,0 +1,351 @@
+use crate::layout::{analyze, LayoutParams, PageLayout, Word};
+use crate::normalize::{normalize_runs, normalize_text, NormalizeOptions};
+use crate::pdf_document::PdfDocument;
+use crate::rect::Rect;
+use crate::standard_fonts::strip_subset_tag;
+use crate::text_runs::text_runs;
+
+const HOCR_HEAD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN"
+    "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">
+<html xmlns="http://www.w3.org/1999/xhtml" xml:lang="en" lang="en">
+ <head>
+  <title></title>
+  <meta http-equiv="Content-Type" content="text/html;charset=utf-8"/>
+"#;
+
+const HOCR_CAPABILITIES: &str = "ocr_page ocr_par ocr_line ocrx_word ocrp_font";
+
+const ALTO_HEAD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<alto xmlns="http://www.loc.gov/standards/alto/ns-v4#"
+    xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+    xsi:schemaLocation="http://www.loc.gov/standards/alto/ns-v4# http://www.loc.gov/alto/v4/alto-4-2.xsd">
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportOptions {
+    // the resolution of the page image coordinates are given for, 72 for points
+    pub dpi: f64,
+    pub params: LayoutParams,
+    // clean-up applied to the text of words, without dehyphenation since words stay on their
+    // lines
+    pub normalize: Option<NormalizeOptions>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> ExportOptions {
+        ExportOptions {
+            dpi: 72.0,
+            params: LayoutParams::default(),
+            normalize: Some(NormalizeOptions::default()),
+        }
+    }
+}
+
+// the layout of a page, with the size of the page and the scale to image space
+struct ExportPage {
+    layout: PageLayout,
+    width: f64,
+    height: f64,
+    scale: f64,
+}
+
+impl ExportPage {
+    fn new(
+        document: &mut PdfDocument,
+        pageno: u32,
+        options: &ExportOptions,
+    ) -> crate::Result<ExportPage> {
+        let mut runs = text_runs(document, pageno)?;
+        if let Some(normalize) = &options.normalize {
+            normalize_runs(&mut runs, normalize);
+        }
+        let mut layout = analyze(&runs, &options.params);
+        if let Some(normalize) = options.normalize {
+            let normalize = NormalizeOptions {
+                dehyphenate: false,
+                ..normalize
+            };
+            let words = layout
+                .blocks
+                .iter_mut()
+                .flat_map(|block| block.lines.iter_mut())
+                .flat_map(|line| line.words.iter_mut());
+            for word in words {
+                word.text = normalize_text(&word.text, &normalize);
+            }
+        }
+        let (width, height) = document.page_size(pageno)?;
+        Ok(ExportPage {
+            layout,
+            width,
+            height,
+            scale: options.dpi / 72.0,
+        })
+    }
+
+    fn size(&self) -> (i64, i64) {
+        (
+            (self.width * self.scale).round() as i64,
+            (self.height * self.scale).round() as i64,
+        )
+    }
+
+    // converts a rectangle in page space, with its origin at the lower left, to image space,
+    // with its origin at the top left, as left, top, right and bottom, clipped to the page
+    fn image_box(&self, rect: &Rect) -> [i64; 4] {
+        let (width, height) = self.size();
+        let x = |x: f64| ((x * self.scale).round() as i64).clamp(0, width);
+        let y = |y: f64| (((self.height - y) * self.scale).round() as i64).clamp(0, height);
+        [x(rect.x0), y(rect.y1), x(rect.x1), y(rect.y0)]
+    }
+}
+
+// escapes text for XML content and attribute values, dropping the control characters XML
+// doesn't allow
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            _ if ch < ' ' || ch == '\u{fffe}' || ch == '\u{ffff}' => {}
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+// font sizes in points, to hundredths
+fn points(size: f64) -> f64 {
+    (size * 100.0).round() / 100.0
+}
+
+fn font_family(word: &Word) -> &str {
+    strip_subset_tag(&word.base_font)
+}
+
+// the angle of a block's text counterclockwise from upright, from 0 to 359
+fn rotation(angle: f64) -> i64 {
+    (angle.round() as i64).rem_euclid(360)
+}
+
+fn hocr_bbox(bbox: [i64; 4]) -> String {
+    format!("bbox {} {} {} {}", bbox[0], bbox[1], bbox[2], bbox[3])
+}
+
+// serializes the text of every page to hOCR, with a paragraph for each block of page_layout
+//
+// Boxes are given in image space, with the origin at the top left of the page as displayed
+// and the resolution of ExportOptions::dpi, and words carry their font name and size.
+pub fn hocr(document: &mut PdfDocument, options: &ExportOptions) -> crate::Result<String> {
+    let mut out = String::from(HOCR_HEAD);
+    out.push_str(&format!(
+        "  <meta name=\"ocr-system\" content=\"llpr {}\"/>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    out.push_str(&format!(
+        "  <meta name=\"ocr-capabilities\" content=\"{}\"/>\n </head>\n <body>\n",
+        HOCR_CAPABILITIES
+    ));
+    for pageno in 0..document.page_count() {
+        let page = ExportPage::new(document, pageno, options)?;
+        let (width, height) = page.size();
+        let id = pageno + 1;
+        out.push_str(&format!(
+            "  <div class=\"ocr_page\" id=\"page_{}\" title=\"bbox 0 0 {} {}; ppageno {}; scan_res {} {}\">\n",
+            id, width, height, pageno, options.dpi, options.dpi
+        ));
+        let (mut pars, mut lines, mut words) = (0, 0, 0);
+        for block in page.layout.blocks.iter() {
+            pars += 1;
+            out.push_str(&format!(
+                "   <p class=\"ocr_par\" id=\"par_{}_{}\" title=\"{}\">\n",
+                id,
+                pars,
+                hocr_bbox(page.image_box(&block.bbox))
+            ));
+            let textangle = match rotation(block.angle) {
+                0 => String::new(),
+                angle => format!("; textangle {}", angle),
+            };
+            for line in block.lines.iter() {
+                lines += 1;
+                out.push_str(&format!(
+                    "    <span class=\"ocr_line\" id=\"line_{}_{}\" title=\"{}{}\">",
+                    id,
+                    lines,
+                    hocr_bbox(page.image_box(&line.bbox)),
+                    textangle
+                ));
+                for (w, word) in line.words.iter().enumerate() {
+                    words += 1;
+                    if w > 0 {
+                        out.push(' ');
+                    }
+                    out.push_str(&format!(
+                        "<span class=\"ocrx_word\" id=\"word_{}_{}\" title=\"{}; x_font &quot;{}&quot;; x_fsize {}\">{}</span>",
+                        id,
+                        words,
+                        hocr_bbox(page.image_box(&word.bbox)),
+                        escape(font_family(word)),
+                        points(word.size),
+                        escape(&word.text)
+                    ));
+                }
+                out.push_str("</span>\n");
+            }
+            out.push_str("   </p>\n");
+        }
+        out.push_str("  </div>\n");
+    }
+    out.push_str(" </body>\n</html>\n");
+    Ok(out)
+}
+
+fn alto_position(bbox: [i64; 4]) -> String {
+    format!(
+        "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+        bbox[0],
+        bbox[1],
+        bbox[2] - bbox[0],
+        bbox[3] - bbox[1]
+    )
+}
+
+// serializes the text of every page to ALTO v4, with a TextBlock for each block of
+// page_layout
+//
+// Positions are in pixels of a page image at ExportOptions::dpi, with the origin at the top
+// left of the page as displayed. Each distinct font and size becomes a TextStyle.
+pub fn alto(document: &mut PdfDocument, options: &ExportOptions) -> crate::Result<String> {
+    let mut styles: Vec<(String, f64)> = vec![];
+    let mut layout = String::new();
+    for pageno in 0..document.page_count() {
+        let page = ExportPage::new(document, pageno, options)?;
+        let (width, height) = page.size();
+        let id = pageno + 1;
+        layout.push_str(&format!(
+            "  <Page ID=\"p{}\" PHYSICAL_IMG_NR=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\">\n",
+            id, id, width, height
+        ));
+        layout.push_str(&format!(
+            "   <PrintSpace HPOS=\"0\" VPOS=\"0\" WIDTH=\"{}\" HEIGHT=\"{}\">\n",
+            width, height
+        ));
+        for (b, block) in page.layout.blocks.iter().enumerate() {
+            let block_id = format!("p{}_b{}", id, b + 1);
+            let rotation = match rotation(block.angle) {
+                0 => String::new(),
+                angle => format!(" ROTATION=\"{}\"", angle),
+            };
+            layout.push_str(&format!(
+                "    <TextBlock ID=\"{}\" {}{}>\n",
+                block_id,
+                alto_position(page.image_box(&block.bbox)),
+                rotation
+            ));
+            for (l, line) in block.lines.iter().enumerate() {
+                let line_id = format!("{}_l{}", block_id, l + 1);
+                layout.push_str(&format!(
+                    "     <TextLine ID=\"{}\" {}>\n",
+                    line_id,
+                    alto_position(page.image_box(&line.bbox))
+                ));
+                for (w, word) in line.words.iter().enumerate() {
+                    if w > 0 {
+                        layout.push_str("      <SP/>\n");
+                    }
+                    let style = (font_family(word).to_owned(), points(word.size));
+                    let style = match styles.iter().position(|known| *known == style) {
+                        Some(style) => style,
+                        None => {
+                            styles.push(style);
+                            styles.len() - 1
+                        }
+                    };
+                    layout.push_str(&format!(
+                        "      <String ID=\"{}_w{}\" {} STYLEREFS=\"font{}\" CONTENT=\"{}\"/>\n",
+                        line_id,
+                        w + 1,
+                        alto_position(page.image_box(&word.bbox)),
+                        style,
+                        escape(&word.text)
+                    ));
+                }
+                layout.push_str("     </TextLine>\n");
+            }
+            layout.push_str("    </TextBlock>\n");
+        }
+        layout.push_str("   </PrintSpace>\n  </Page>\n");
+    }
+    let mut out = String::from(ALTO_HEAD);
+    out.push_str(" <Description>\n  <MeasurementUnit>pixel</MeasurementUnit>\n");
+    out.push_str(&format!(
+        "  <OCRProcessing ID=\"ocr\">\n   <ocrProcessingStep>\n    <processingSoftware>\n     <softwareName>llpr</softwareName>\n     <softwareVersion>{}</softwareVersion>\n    </processingSoftware>\n   </ocrProcessingStep>\n  </OCRProcessing>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    out.push_str(" </Description>\n <Styles>\n");
+    for (i, (family, size)) in styles.iter().enumerate() {
+        out.push_str(&format!(
+            "  <TextStyle ID=\"font{}\" FONTFAMILY=\"{}\" FONTSIZE=\"{}\"/>\n",
+            i,
+            escape(family),
+            size
+        ));
+    }
+    out.push_str(" </Styles>\n <Layout>\n");
+    out.push_str(&layout);
+    out.push_str(" </Layout>\n</alto>\n");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files;
+
+    #[test]
+    fn minimal_pdf_hocr() {
+        let mut document = test_files::open("minimal.pdf");
+        let hocr = hocr(&mut document, &ExportOptions::default()).unwrap();
+        assert!(hocr.contains("title=\"bbox 0 0 300 144; ppageno 0; scan_res 72 72\""));
+        // Times-Roman at 18 points on the bottom edge of the page, its descent cut off
+        assert!(hocr.contains(
+            "<span class=\"ocrx_word\" id=\"word_1_1\" title=\"bbox 0 132 40 144; x_font &quot;Times-Roman&quot;; x_fsize 18\">Hello</span>"
+        ));
+        assert!(hocr.contains(">Hello</span> <span class=\"ocrx_word\" id=\"word_1_2\""));
+        let options = ExportOptions {
+            dpi: 144.0,
+            ..ExportOptions::default()
+        };
+        let hocr = super::hocr(&mut document, &options).unwrap();
+        assert!(hocr.contains("bbox 0 0 600 288; ppageno 0; scan_res 144 144"));
+        assert!(hocr.contains("title=\"bbox 0 263 80 288; x_font"));
+    }
+
+    #[test]
+    fn minimal_pdf_alto() {
+        let mut document = test_files::open("minimal.pdf");
+        let alto = alto(&mut document, &ExportOptions::default()).unwrap();
+        assert!(
+            alto.contains("<TextStyle ID=\"font0\" FONTFAMILY=\"Times-Roman\" FONTSIZE=\"18\"/>")
+        );
+        assert!(
+            alto.contains("<Page ID=\"p1\" PHYSICAL_IMG_NR=\"1\" WIDTH=\"300\" HEIGHT=\"144\">")
+        );
+        assert!(alto.contains(
+            "<String ID=\"p1_b1_l1_w1\" HPOS=\"0\" VPOS=\"132\" WIDTH=\"40\" HEIGHT=\"12\" STYLEREFS=\"font0\" CONTENT=\"Hello\"/>\n      <SP/>"
+        ));
+        assert!(alto.find("<Styles>") < alto.find("<Layout>"));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("a<b & \"c\"\u{1}"), "a&lt;b &amp; &quot;c&quot;");
+    }
+}
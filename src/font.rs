@@ -0,0 +1,734 @@
+use crate::cid_tables::collection;
+use crate::cmap::{CMap, Code};
+use crate::dictionary::Access;
+use crate::encoding::{glyph_to_unicode, Encoding};
+use crate::pdf_document::PdfDocument;
+use crate::pdf_types::*;
+use crate::standard_fonts::StandardFont;
+use crate::PdfError;
+
+// the maximum depth of /UseCMap chains between embedded CMaps
+const MAX_CMAP_DEPTH: usize = 8;
+const DEFAULT_CID_WIDTH: f64 = 1000.0;
+const DEFAULT_VERTICAL_ADVANCE: f64 = -1000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+enum FontKind {
+    // Type1, MMType1, TrueType and Type3 fonts, whose character codes are single bytes
+    Simple {
+        encoding: Encoding,
+        first_char: u32,
+        // indexed by code - first_char, None for an entry which isn't a number
+        widths: Vec<Option<f64>>,
+        // the metrics used when a standard font has no /Widths
+        standard: Option<&'static StandardFont>,
+        descriptor: Option<FontDescriptor>,
+        // scales widths to thousandths of text space, Type3 widths are in the glyph space set
+        // by /FontMatrix
+        width_scale: f64,
+    },
+    // Type0 fonts, whose encoding CMap maps multi-byte codes to CIDs in the descendant font
+    Composite {
+        cmap: CMap,
+        descendant: Box<CidFont>,
+    },
+}
+
+// the metrics and style of a font, from its /FontDescriptor, in thousandths of a unit of
+// text space
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FontDescriptor {
+    pub font_name: String,
+    pub flags: u32,
+    // [llx lly urx ury]
+    pub font_bbox: [f64; 4],
+    pub italic_angle: f64,
+    pub ascent: f64,
+    pub descent: f64,
+    pub cap_height: f64,
+    // the width of codes which /Widths doesn't cover
+    pub missing_width: f64,
+}
+
+impl FontDescriptor {
+    pub const FIXED_PITCH: u32 = 1;
+    pub const SERIF: u32 = 1 << 1;
+    pub const SYMBOLIC: u32 = 1 << 2;
+    pub const SCRIPT: u32 = 1 << 3;
+    pub const NONSYMBOLIC: u32 = 1 << 5;
+    pub const ITALIC: u32 = 1 << 6;
+    pub const ALL_CAP: u32 = 1 << 16;
+    pub const SMALL_CAP: u32 = 1 << 17;
+    pub const FORCE_BOLD: u32 = 1 << 18;
+
+    fn new(document: &mut PdfDocument, dictionary: &Dictionary) -> crate::Result<FontDescriptor> {
+        let number = |name| {
+            dictionary
+                .get(&name)
+                .and_then(|n| n.as_f64())
+                .unwrap_or(0.0)
+        };
+        let mut font_bbox = [0.0; 4];
+        let bbox = resolve_array(document, dictionary, PdfName::FontBBox)?;
+        for (value, object) in font_bbox.iter_mut().zip(bbox.iter()) {
+            *value = object.as_f64().unwrap_or(0.0);
+        }
+        Ok(FontDescriptor {
+            font_name: base_font_name(dictionary.get(&PdfName::FontName)),
+            flags: dictionary.get_u32(PdfName::Flags).unwrap_or(0),
+            font_bbox,
+            italic_angle: number(PdfName::ItalicAngle),
+            ascent: number(PdfName::Ascent),
+            descent: number(PdfName::Descent),
+            cap_height: number(PdfName::CapHeight),
+            missing_width: number(PdfName::MissingWidth),
+        })
+    }
+
+    // the descriptor of a font, or the built-in metrics of a standard font without one
+    fn load(
+        document: &mut PdfDocument,
+        dictionary: &Dictionary,
+        standard: Option<&StandardFont>,
+    ) -> crate::Result<Option<FontDescriptor>> {
+        if let Some(descriptor) = dictionary.get(&PdfName::FontDescriptor) {
+            if let PdfObject::Dictionary(descriptor) = document.resolve(descriptor.clone())? {
+                return Ok(Some(FontDescriptor::new(document, &descriptor)?));
+            }
+        }
+        Ok(standard.map(|standard| FontDescriptor {
+            font_name: standard.name.to_owned(),
+            flags: standard.flags,
+            font_bbox: standard.font_bbox,
+            italic_angle: standard.italic_angle,
+            ascent: standard.ascent,
+            descent: standard.descent,
+            cap_height: standard.cap_height,
+            missing_width: 0.0,
+        }))
+    }
+
+    pub fn is_fixed_pitch(&self) -> bool {
+        self.flags & FontDescriptor::FIXED_PITCH != 0
+    }
+
+    pub fn is_serif(&self) -> bool {
+        self.flags & FontDescriptor::SERIF != 0
+    }
+
+    pub fn is_symbolic(&self) -> bool {
+        self.flags & FontDescriptor::SYMBOLIC != 0
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.flags & FontDescriptor::ITALIC != 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CidToGid {
+    Identity,
+    Map(Vec<u16>),
+}
+
+// the descendant of a Type0 font, which selects glyphs by CID
+#[derive(Debug, Clone, PartialEq)]
+pub struct CidFont {
+    pub subtype: PdfName,
+    pub base_font: String,
+    // the /CIDSystemInfo character collection, e.g. Adobe-Japan1
+    pub registry: String,
+    pub ordering: String,
+    pub supplement: i64,
+    pub cid_to_gid: CidToGid,
+    pub descriptor: Option<FontDescriptor>,
+    default_width: f64,
+    widths: Vec<(u32, u32, f64)>,
+    default_vertical_advance: f64,
+    vertical_advances: Vec<(u32, u32, f64)>,
+}
+
+impl CidFont {
+    fn new(document: &mut PdfDocument, dictionary: &Dictionary) -> crate::Result<CidFont> {
+        let subtype = match dictionary.get_name(PdfName::Subtype) {
+            Some(subtype @ PdfName::CIDFontType0) | Some(subtype @ PdfName::CIDFontType2) => {
+                subtype
+            }
+            _ => return Err(PdfError::InvalidPdf("unknown CIDFont type")),
+        };
+        let (registry, ordering, supplement) = match dictionary.get(&PdfName::CIDSystemInfo) {
+            Some(info) => match document.resolve(info.clone())? {
+                PdfObject::Dictionary(info) => (
+                    text(info.get_string(PdfName::Registry)),
+                    text(info.get_string(PdfName::Ordering)),
+                    info.get_i32(PdfName::Supplement).unwrap_or(0) as i64,
+                ),
+                _ => (String::new(), String::new(), 0),
+            },
+            None => (String::new(), String::new(), 0),
+        };
+        let cid_to_gid = match dictionary.get(&PdfName::CIDToGIDMap) {
+            Some(PdfObject::Reference(reference)) => {
+                let data = document.stream_data(*reference)?;
+                CidToGid::Map(
+                    data.chunks_exact(2)
+                        .map(|pair| (pair[0] as u16) << 8 | pair[1] as u16)
+                        .collect(),
+                )
+            }
+            _ => CidToGid::Identity,
+        };
+        let default_vertical_advance = match resolve_array(document, dictionary, PdfName::DW2)?[..]
+        {
+            [_, ref w1] => w1.as_f64().unwrap_or(DEFAULT_VERTICAL_ADVANCE),
+            _ => DEFAULT_VERTICAL_ADVANCE,
+        };
+        let vertical_advances = cid_widths(&resolve_array(document, dictionary, PdfName::W2)?, 3);
+        Ok(CidFont {
+            subtype,
+            base_font: base_font(dictionary),
+            registry,
+            ordering,
+            supplement,
+            cid_to_gid,
+            descriptor: FontDescriptor::load(document, dictionary, None)?,
+            default_width: dictionary
+                .get(&PdfName::DW)
+                .and_then(|dw| dw.as_f64())
+                .unwrap_or(DEFAULT_CID_WIDTH),
+            widths: cid_widths(&resolve_array(document, dictionary, PdfName::W)?, 1),
+            default_vertical_advance,
+            vertical_advances,
+        })
+    }
+
+    pub fn gid(&self, cid: u32) -> u32 {
+        match &self.cid_to_gid {
+            CidToGid::Identity => cid,
+            CidToGid::Map(map) => map.get(cid as usize).cloned().unwrap_or(0) as u32,
+        }
+    }
+
+    pub fn width(&self, cid: u32) -> f64 {
+        lookup(&self.widths, cid).unwrap_or(self.default_width)
+    }
+
+    // the Unicode text of a CID in an Adobe character collection, e.g. Adobe-Japan1
+    pub fn unicode(&self, cid: u32) -> Option<String> {
+        match self.registry.as_str() {
+            "Adobe" => collection(&self.ordering)?
+                .unicode(cid)
+                .map(|ch| ch.to_string()),
+            _ => None,
+        }
+    }
+
+    // the vertical displacement for vertical writing, usually negative
+    pub fn vertical_advance(&self, cid: u32) -> f64 {
+        lookup(&self.vertical_advances, cid).unwrap_or(self.default_vertical_advance)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    pub subtype: PdfName,
+    pub base_font: String,
+    kind: FontKind,
+    to_unicode: Option<CMap>,
+}
+
+impl Font {
+    // loads a font from its font dictionary, resolving references through the document
+    pub fn new(document: &mut PdfDocument, dictionary: &Dictionary) -> crate::Result<Font> {
+        let subtype = match dictionary.get_name(PdfName::Subtype) {
+            Some(subtype @ PdfName::Type1)
+            | Some(subtype @ PdfName::MMType1)
+            | Some(subtype @ PdfName::TrueType)
+            | Some(subtype @ PdfName::Type3)
+            | Some(subtype @ PdfName::Type0) => subtype,
+            _ => return Err(PdfError::InvalidPdf("unknown font type")),
+        };
+        let kind = match subtype {
+            PdfName::Type0 => Font::composite(document, dictionary)?,
+            _ => Font::simple(document, dictionary)?,
+        };
+        let to_unicode = match dictionary.get_reference(PdfName::ToUnicode) {
+            // the text is then decoded through the encoding, as without a /ToUnicode CMap
+            Some(reference) => document.stream_data(reference).and_then(CMap::parse).ok(),
+            None => None,
+        };
+        Ok(Font {
+            subtype,
+            base_font: base_font(dictionary),
+            kind,
+            to_unicode,
+        })
+    }
+
+    fn simple(document: &mut PdfDocument, dictionary: &Dictionary) -> crate::Result<FontKind> {
+        let base_font = base_font(dictionary);
+        let builtin = Encoding::builtin(&base_font);
+        let encoding = match dictionary.get(&PdfName::Encoding) {
+            Some(encoding) => match document.resolve(encoding.clone())? {
+                PdfObject::Name(name) => Encoding::from_name(&name).unwrap_or(builtin),
+                PdfObject::Dictionary(dict) => {
+                    let mut encoding = dict
+                        .get_name(PdfName::BaseEncoding)
+                        .and_then(|name| Encoding::from_name(&name))
+                        .unwrap_or(builtin);
+                    if let Some(differences) = dict.get(&PdfName::Differences) {
+                        if let PdfObject::Array(differences) =
+                            document.resolve(differences.clone())?
+                        {
+                            encoding.apply_differences(&differences);
+                        }
+                    }
+                    encoding
+                }
+                _ => builtin,
+            },
+            None => builtin,
+        };
+        let standard = StandardFont::find(&base_font);
+        let width_scale = match dictionary.get_name(PdfName::Subtype) {
+            Some(PdfName::Type3) => {
+                match resolve_array(document, dictionary, PdfName::FontMatrix)?.first() {
+                    Some(a) => a.as_f64().map_or(1.0, |a| a * 1000.0),
+                    None => 1.0,
+                }
+            }
+            _ => 1.0,
+        };
+        // an invalid entry keeps its place, its glyph gets the descriptor's /MissingWidth
+        let mut widths = vec![];
+        for width in resolve_array(document, dictionary, PdfName::Widths)? {
+            widths.push(document.resolve(width).ok().and_then(|w| w.as_f64()));
+        }
+        Ok(FontKind::Simple {
+            encoding,
+            first_char: dictionary.get_u32(PdfName::FirstChar).unwrap_or(0),
+            widths,
+            standard,
+            descriptor: FontDescriptor::load(document, dictionary, standard)?,
+            width_scale,
+        })
+    }
+
+    fn composite(document: &mut PdfDocument, dictionary: &Dictionary) -> crate::Result<FontKind> {
+        let cmap = match dictionary.get(&PdfName::Encoding) {
+            Some(PdfObject::Reference(reference)) => embedded_cmap(document, *reference, 0)?,
+            Some(name) => match name.as_name() {
+                Some(name) => match CMap::predefined(&name.text()) {
+                    Some(cmap) => cmap,
+                    None => return Err(PdfError::Unsupported("predefined CMap")),
+                },
+                None => return Err(PdfError::InvalidPdf("invalid Type0 font encoding")),
+            },
+            None => return Err(PdfError::InvalidPdf("Encoding missing from Type0 font")),
+        };
+        let descendant = match resolve_array(document, dictionary, PdfName::DescendantFonts)?
+            .into_iter()
+            .next()
+        {
+            Some(descendant) => match document.resolve(descendant)? {
+                PdfObject::Dictionary(descendant) => CidFont::new(document, &descendant)?,
+                _ => return Err(PdfError::InvalidPdf("invalid descendant font")),
+            },
+            None => {
+                return Err(PdfError::InvalidPdf(
+                    "DescendantFonts missing from Type0 font",
+                ))
+            }
+        };
+        Ok(FontKind::Composite {
+            cmap,
+            descendant: Box::new(descendant),
+        })
+    }
+
+    pub fn descendant(&self) -> Option<&CidFont> {
+        match &self.kind {
+            FontKind::Composite { descendant, .. } => Some(descendant),
+            FontKind::Simple { .. } => None,
+        }
+    }
+
+    // the font descriptor, which belongs to the descendant of a composite font
+    pub fn descriptor(&self) -> Option<&FontDescriptor> {
+        match &self.kind {
+            FontKind::Simple { descriptor, .. } => descriptor.as_ref(),
+            FontKind::Composite { descendant, .. } => descendant.descriptor.as_ref(),
+        }
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        match &self.kind {
+            FontKind::Composite { cmap, .. } => cmap.vertical,
+            FontKind::Simple { .. } => false,
+        }
+    }
+
+    // splits a string shown in this font into character codes
+    pub fn codes(&self, text: &[u8]) -> Vec<Code> {
+        match &self.kind {
+            FontKind::Composite { cmap, .. } => cmap.codes(text).collect(),
+            FontKind::Simple { .. } => text.iter().map(|&byte| Code::new(&[byte])).collect(),
+        }
+    }
+
+    // the CID selected by a code, for composite fonts whose CMap maps the code
+    pub fn cid(&self, code: Code) -> Option<u32> {
+        match &self.kind {
+            FontKind::Composite { cmap, .. } => cmap.cid(code),
+            FontKind::Simple { .. } => None,
+        }
+    }
+
+    // the Unicode text for a code, the /ToUnicode CMap takes precedence over the encoding
+    //
+    // The codes of composite fonts map to Unicode through a Unicode or legacy CJK CMap, or by
+    // their CID, through the tables of the descendant's Adobe character collection.
+    pub fn unicode(&self, code: Code) -> Option<String> {
+        if let Some(text) = self.to_unicode.as_ref().and_then(|cmap| cmap.unicode(code)) {
+            return Some(text);
+        }
+        match &self.kind {
+            FontKind::Simple { encoding, .. } => encoding
+                .glyph_name(code.value as u8)
+                .and_then(glyph_to_unicode),
+            FontKind::Composite { cmap, descendant } => cmap
+                .unicode(code)
+                .or_else(|| descendant.unicode(cmap.cid(code)?)),
+        }
+    }
+
+    // the Unicode text for a string shown in this font
+    pub fn decode(&self, text: &[u8]) -> String {
+        self.codes(text)
+            .into_iter()
+            .map(|code| self.unicode(code).unwrap_or_else(|| '\u{fffd}'.to_string()))
+            .collect()
+    }
+
+    // the horizontal advance of a glyph, in thousandths of a unit of text space
+    pub fn width(&self, code: Code) -> f64 {
+        match &self.kind {
+            FontKind::Simple {
+                encoding,
+                first_char,
+                widths,
+                standard,
+                descriptor,
+                width_scale,
+            } => {
+                let width = match (code.value.checked_sub(*first_char), standard) {
+                    (Some(index), _) if !widths.is_empty() => {
+                        widths.get(index as usize).cloned().flatten()
+                    }
+                    (_, Some(standard)) if widths.is_empty() => encoding
+                        .glyph_name(code.value as u8)
+                        .and_then(|glyph| standard.width(glyph)),
+                    _ => None,
+                };
+                width.unwrap_or_else(|| descriptor.as_ref().map_or(0.0, |d| d.missing_width))
+                    * width_scale
+            }
+            FontKind::Composite { descendant, .. } => match self.cid(code) {
+                Some(cid) => descendant.width(cid),
+                None => descendant.default_width,
+            },
+        }
+    }
+
+    // the vertical advance of a glyph in vertical writing, in thousandths of a unit of text space
+    pub fn vertical_advance(&self, code: Code) -> f64 {
+        match &self.kind {
+            FontKind::Composite { descendant, .. } => match self.cid(code) {
+                Some(cid) => descendant.vertical_advance(cid),
+                None => descendant.default_vertical_advance,
+            },
+            FontKind::Simple { .. } => 0.0,
+        }
+    }
+}
+
+fn base_font(dictionary: &Dictionary) -> String {
+    base_font_name(dictionary.get(&PdfName::BaseFont))
+}
+
+fn base_font_name(name: Option<&PdfObject>) -> String {
+    match name.and_then(|b| b.as_name()) {
+        Some(name) => name.text(),
+        None => String::new(),
+    }
+}
+
+fn text(string: Option<PdfString>) -> String {
+    String::from_utf8_lossy(&string.unwrap_or_default()).into_owned()
+}
+
+fn resolve_array(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+    key: PdfName,
+) -> crate::Result<Vec<PdfObject>> {
+    match dictionary.get(&key) {
+        Some(array) => match document.resolve(array.clone())? {
+            PdfObject::Array(array) => Ok(*array),
+            _ => Ok(vec![]),
+        },
+        None => Ok(vec![]),
+    }
+}
+
+// an embedded CMap stream, with the CMap named by usecmap or /UseCMap as its parent
+fn embedded_cmap(
+    document: &mut PdfDocument,
+    reference: Reference,
+    depth: usize,
+) -> crate::Result<CMap> {
+    let mut cmap = CMap::parse(document.stream_data(reference)?)?;
+    let use_cmap = match document.resolve(PdfObject::Reference(reference))? {
+        PdfObject::Dictionary(dictionary) => dictionary.get(&PdfName::UseCMap).cloned(),
+        _ => None,
+    };
+    match use_cmap {
+        Some(PdfObject::Reference(parent)) if depth < MAX_CMAP_DEPTH => {
+            let parent = embedded_cmap(document, parent, depth + 1)?;
+            cmap.set_parent(parent);
+        }
+        Some(PdfObject::Reference(_)) => {
+            return Err(PdfError::InvalidPdf("UseCMap chain is too long"));
+        }
+        Some(name) => {
+            if let Some(parent) = name
+                .as_name()
+                .and_then(|name| CMap::predefined(&name.text()))
+            {
+                cmap.set_parent(parent);
+            }
+        }
+        None => {}
+    }
+    Ok(cmap)
+}
+
+// the entries of a /W or /W2 array, either "c [w1 w2 ...]" or "first last w", where each width
+// is a group of values of which the first is kept
+fn cid_widths(array: &[PdfObject], group: usize) -> Vec<(u32, u32, f64)> {
+    let mut widths = vec![];
+    let mut index = 0;
+    while index + 1 < array.len() {
+        let first = match array[index].as_f64() {
+            Some(first) => first as u32,
+            None => break,
+        };
+        match &array[index + 1] {
+            PdfObject::Array(values) => {
+                for (n, values) in values.chunks_exact(group).enumerate() {
+                    if let Some(width) = values[0].as_f64() {
+                        let cid = first + n as u32;
+                        widths.push((cid, cid, width));
+                    }
+                }
+                index += 2;
+            }
+            last => {
+                if let (Some(last), Some(width)) = (
+                    last.as_f64(),
+                    array.get(index + 2).and_then(|width| width.as_f64()),
+                ) {
+                    widths.push((first, last as u32, width));
+                }
+                index += 2 + group;
+            }
+        }
+    }
+    widths
+}
+
+fn lookup(widths: &[(u32, u32, f64)], cid: u32) -> Option<f64> {
+    widths
+        .iter()
+        .find(|&&(first, last, _)| first <= cid && cid <= last)
+        .map(|&(_, _, width)| width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::next_object::next_object;
+    use crate::pdf_source::{ByteSliceSource, Source};
+    use crate::test_files;
+
+    fn font(id: u32) -> Font {
+        let mut document = test_files::open("tracemonkey.pdf");
+        match document.resolve(PdfObject::Reference(Reference::new(id, 0))) {
+            Ok(PdfObject::Dictionary(dictionary)) => Font::new(&mut document, &dictionary).unwrap(),
+            obj => panic!("unexpected object: {:?}", obj),
+        }
+    }
+
+    // a font from a dictionary with direct objects only
+    fn direct_font(dictionary: &'static [u8]) -> Font {
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(dictionary));
+        match next_object(&mut source) {
+            Ok(Some(PdfObject::Dictionary(dictionary))) => {
+                Font::new(&mut test_files::open("tracemonkey.pdf"), &dictionary).unwrap()
+            }
+            obj => panic!("unexpected object: {:?}", obj),
+        }
+    }
+
+    #[test]
+    fn differences() {
+        let font = font(9);
+        assert_eq!(font.base_font, "KUYGUP+NimbusRomNo9L-Regu");
+        assert_eq!(font.decode(b"\x02nd it\x27s"), "\u{fb01}nd it\u{2019}s");
+    }
+
+    #[test]
+    fn to_unicode() {
+        let font = font(127);
+        assert_eq!(font.subtype, PdfName::TrueType);
+        assert_eq!(font.decode(b"\x21\x22\x23\x24\x25"), "Trunk");
+    }
+
+    #[test]
+    fn unicode_cmap() {
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /MS-Mincho /Encoding /UniJIS-UCS2-H
+                 /DescendantFonts [<< /Type /Font /Subtype /CIDFontType0 /BaseFont /MS-Mincho
+                     /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 2 >>
+                     /DW 1000 >>] >>",
+        );
+        assert_eq!(font.decode(b"\x30\x42\x00\x41"), "\u{3042}A");
+        let descendant = font.descendant().unwrap();
+        assert_eq!(descendant.ordering, "Japan1");
+        assert_eq!(descendant.supplement, 2);
+        assert!(!font.is_vertical());
+    }
+
+    #[test]
+    fn legacy_cmap() {
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /MS-Gothic /Encoding /90ms-RKSJ-H
+                 /DescendantFonts [<< /Type /Font /Subtype /CIDFontType2 /BaseFont /MS-Gothic
+                     /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 2 >>
+                     /DW 1000 /W [0 [250]] >>] >>",
+        );
+        assert_eq!(font.decode(b"A\x82\xa0"), "A\u{3042}");
+        // codes whose CID isn't known, or isn't in /W, have the default width, not CID 0's
+        assert_eq!(font.width(Code::new(b"\x82\xa0")), 1000.0);
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /MS-Mincho /Encoding /UniJIS-UCS2-H
+                 /DescendantFonts [<< /Type /Font /Subtype /CIDFontType0 /BaseFont /MS-Mincho
+                     /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 2 >>
+                     /DW 1000 /W [0 [250]] >>] >>",
+        );
+        assert_eq!(font.width(Code::new(b"\x30\x42")), 1000.0);
+    }
+
+    #[test]
+    fn identity_cjk_font() {
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /KozMinPro /Encoding /Identity-H
+                 /DescendantFonts [<< /Type /Font /Subtype /CIDFontType0 /BaseFont /KozMinPro
+                     /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 6 >>
+                     >>] >>",
+        );
+        // without a /ToUnicode CMap the text comes from the Adobe-Japan1 table
+        assert_eq!(
+            font.decode(b"\x03\x4b\x00\x01\x00\x00"),
+            "\u{3042} \u{fffd}"
+        );
+    }
+
+    #[test]
+    fn cid_widths() {
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /Test /Encoding /Identity-V
+                 /DescendantFonts [<< /Type /Font /Subtype /CIDFontType2 /BaseFont /Test
+                     /DW 900 /W [1 [500 600] 100 200 250] /W2 [1 [-800 250 880]] >>] >>",
+        );
+        assert!(font.is_vertical());
+        let codes = font.codes(b"\x00\x01\x00\x02\x00\x96\x01\x00");
+        assert_eq!(
+            codes.iter().map(|&code| font.cid(code)).collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(150), Some(256)]
+        );
+        assert_eq!(
+            codes
+                .iter()
+                .map(|&code| font.width(code))
+                .collect::<Vec<_>>(),
+            vec![500.0, 600.0, 250.0, 900.0]
+        );
+        assert_eq!(font.vertical_advance(codes[0]), -800.0);
+        assert_eq!(font.vertical_advance(codes[1]), -1000.0);
+        assert_eq!(font.decode(b"\x00\x01"), "\u{fffd}");
+    }
+
+    #[test]
+    fn standard_font_metrics() {
+        let font = direct_font(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>");
+        assert_eq!(font.width(Code::new(b"W")), 944.0);
+        assert_eq!(font.width(Code::new(b"\x01")), 0.0);
+        let descriptor = font.descriptor().unwrap();
+        assert_eq!(descriptor.font_bbox, [-166.0, -225.0, 1000.0, 931.0]);
+        assert_eq!(descriptor.ascent, 718.0);
+        assert!(!descriptor.is_serif() && !descriptor.is_symbolic());
+    }
+
+    #[test]
+    fn missing_width() {
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /TrueType /BaseFont /Arial /FirstChar 65 /Widths [ 700 710 ]
+                /FontDescriptor << /Type /FontDescriptor /FontName /Arial /Flags 32
+                    /FontBBox [ -665 -325 2000 1040 ] /ItalicAngle 0 /Ascent 905
+                    /Descent -212 /CapHeight 716 /MissingWidth 250 >> >>",
+        );
+        assert_eq!(font.width(Code::new(b"A")), 700.0);
+        assert_eq!(font.width(Code::new(b"B")), 710.0);
+        assert_eq!(font.width(Code::new(b"W")), 250.0);
+        let descriptor = font.descriptor().unwrap();
+        assert_eq!(descriptor.font_name, "Arial");
+        assert_eq!(descriptor.descent, -212.0);
+        assert_eq!(descriptor.cap_height, 716.0);
+        // references are resolved, 99 0 R is the integer 1401 in tracemonkey.pdf, and entries
+        // which aren't numbers don't shift the widths after them
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /TrueType /BaseFont /Arial /FirstChar 65
+                /Widths [ 700 null 99 0 R /W 9999 0 R 730 ]
+                /FontDescriptor << /Type /FontDescriptor /FontName /Arial /Flags 32
+                    /MissingWidth 250 >> >>",
+        );
+        assert_eq!(font.width(Code::new(b"A")), 700.0);
+        assert_eq!(font.width(Code::new(b"B")), 250.0);
+        assert_eq!(font.width(Code::new(b"C")), 1401.0);
+        assert_eq!(font.width(Code::new(b"D")), 250.0);
+        assert_eq!(font.width(Code::new(b"E")), 250.0);
+        assert_eq!(font.width(Code::new(b"F")), 730.0);
+    }
+
+    #[test]
+    fn type3_widths() {
+        let font = direct_font(
+            b"<< /Type /Font /Subtype /Type3 /FontBBox [0 0 10 10] /FontMatrix [0.1 0 0 0.1 0 0]
+                /CharProcs << >> /Encoding << /Differences [65 /A] >> /FirstChar 65
+                /Widths [ 6 ] >>",
+        );
+        assert_eq!(font.width(Code::new(b"A")), 600.0);
+        assert_eq!(font.width(Code::new(b"B")), 0.0);
+    }
+
+    #[test]
+    fn malformed_to_unicode() {
+        // an embedded Type1 font program, which isn't CMap syntax
+        let mut document = test_files::open("tracemonkey.pdf");
+        assert!(CMap::parse(document.stream_data(Reference::new(5, 0)).unwrap()).is_err());
+        let font =
+            direct_font(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /ToUnicode 5 0 R >>");
+        assert_eq!(font.decode(b"Hi"), "Hi");
+    }
+}
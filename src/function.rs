@@ -0,0 +1,746 @@
+use crate::dictionary::Access;
+use crate::next_object::next_object;
+use crate::pdf_document::PdfDocument;
+use crate::pdf_source::{ByteSource, Source};
+use crate::pdf_types::*;
+use crate::PdfError;
+
+// the deepest a PostScript calculator function's stack can grow
+const MAX_STACK: usize = 100;
+
+// how deeply stitching functions can be nested, which stops self-referencing functions
+const MAX_DEPTH: usize = 8;
+
+// the most inputs of a sampled function, each doubles the samples interpolated
+const MAX_SAMPLED_INPUTS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+enum FunctionKind {
+    // samples of n outputs on a grid of Size points in each of m inputs, interpolated linearly
+    Sampled {
+        size: Vec<usize>,
+        encode: Vec<f64>,
+        decode: Vec<f64>,
+        samples: Vec<f64>,
+    },
+    Exponential {
+        c0: Vec<f64>,
+        c1: Vec<f64>,
+        n: f64,
+    },
+    // one input, split at bounds between functions
+    Stitching {
+        functions: Vec<Function>,
+        bounds: Vec<f64>,
+        encode: Vec<f64>,
+    },
+    PostScript(Array),
+}
+
+// a PDF function, as used by tint transforms and shadings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    domain: Vec<f64>,
+    range: Option<Vec<f64>>,
+    kind: FunctionKind,
+}
+
+// the numbers of an array entry, with references resolved
+fn numbers(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+    key: PdfName,
+) -> crate::Result<Option<Vec<f64>>> {
+    let array = match dictionary.get(&key) {
+        Some(object) => document.resolve(object.clone())?,
+        None => return Ok(None),
+    };
+    match array {
+        PdfObject::Array(array) => {
+            let mut numbers = vec![];
+            for object in array.into_iter() {
+                match document.resolve(object)?.as_f64() {
+                    Some(number) => numbers.push(number),
+                    None => return Err(PdfError::InvalidPdf("number expected in function")),
+                }
+            }
+            Ok(Some(numbers))
+        }
+        _ => Err(PdfError::InvalidPdf("array expected in function")),
+    }
+}
+
+fn stream_data(document: &mut PdfDocument, reference: Option<Reference>) -> crate::Result<Vec<u8>> {
+    match reference {
+        Some(reference) => document.stream_data(reference),
+        None => Err(PdfError::InvalidPdf("function stream expected")),
+    }
+}
+
+fn interpolate(x: f64, x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
+    if x1 == x0 {
+        y0
+    } else {
+        y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+    }
+}
+
+// reads n samples of bits each, packed without padding, or as many as the data holds
+fn unpack_samples(data: &[u8], bits: usize, count: usize) -> crate::Result<Vec<f64>> {
+    if !matches!(bits, 1 | 2 | 4 | 8 | 12 | 16 | 24 | 32) {
+        return Err(PdfError::InvalidPdf("invalid function BitsPerSample"));
+    }
+    let count = count.min(data.len() * 8 / bits);
+    let mut samples = Vec::with_capacity(count);
+    let (mut buffer, mut buffered) = (0u64, 0);
+    let mut bytes = data.iter();
+    while samples.len() < count {
+        while buffered < bits {
+            buffer = (buffer << 8) | *bytes.next().unwrap_or(&0) as u64;
+            buffered += 8;
+        }
+        buffered -= bits;
+        samples.push(((buffer >> buffered) & ((1 << bits) - 1)) as f64);
+    }
+    Ok(samples)
+}
+
+impl Function {
+    // a function dictionary or stream, or a reference to one
+    pub fn new(document: &mut PdfDocument, object: &PdfObject) -> crate::Result<Function> {
+        Function::load(document, object, 0)
+    }
+
+    fn load(
+        document: &mut PdfDocument,
+        object: &PdfObject,
+        depth: usize,
+    ) -> crate::Result<Function> {
+        let reference = match object {
+            PdfObject::Reference(reference) => Some(*reference),
+            _ => None,
+        };
+        let dictionary = match document.resolve(object.clone())? {
+            PdfObject::Dictionary(dictionary) => dictionary,
+            _ => return Err(PdfError::InvalidPdf("function dictionary expected")),
+        };
+        let domain = numbers(document, &dictionary, PdfName::Domain)?
+            .ok_or(PdfError::InvalidPdf("Domain missing from function"))?;
+        let range = numbers(document, &dictionary, PdfName::Range)?;
+        // both are pairs of bounds, one for each input or output
+        if domain.is_empty() || domain.len() % 2 != 0 {
+            return Err(PdfError::InvalidPdf("invalid function Domain"));
+        }
+        if range.as_ref().is_some_and(|range| range.len() % 2 != 0) {
+            return Err(PdfError::InvalidPdf("invalid function Range"));
+        }
+        let kind = match dictionary.get_i32(PdfName::FunctionType) {
+            Some(0) => {
+                let range = range
+                    .as_ref()
+                    .ok_or(PdfError::InvalidPdf("Range missing from function"))?;
+                let size: Vec<usize> = numbers(document, &dictionary, PdfName::Size)?
+                    .ok_or(PdfError::InvalidPdf("Size missing from function"))?
+                    .into_iter()
+                    .map(|size| size.max(1.0) as usize)
+                    .collect();
+                if 2 * size.len() != domain.len() || size.len() > MAX_SAMPLED_INPUTS {
+                    return Err(PdfError::InvalidPdf("invalid sampled function"));
+                }
+                let encode =
+                    numbers(document, &dictionary, PdfName::Encode)?.unwrap_or_else(|| {
+                        size.iter()
+                            .flat_map(|&size| vec![0.0, (size - 1) as f64])
+                            .collect()
+                    });
+                let decode = numbers(document, &dictionary, PdfName::Decode)?
+                    .unwrap_or_else(|| range.clone());
+                let bits = dictionary.get_u32(PdfName::BitsPerSample).unwrap_or(8) as usize;
+                let count = size
+                    .iter()
+                    .try_fold(range.len() / 2, |count, &size| count.checked_mul(size))
+                    .ok_or(PdfError::InvalidPdf("invalid sampled function"))?;
+                let data = stream_data(document, reference)?;
+                let samples = unpack_samples(&data, bits, count)?;
+                let max = ((1u64 << bits) - 1) as f64;
+                let samples = samples.into_iter().map(|sample| sample / max).collect();
+                FunctionKind::Sampled {
+                    size,
+                    encode,
+                    decode,
+                    samples,
+                }
+            }
+            Some(2) => FunctionKind::Exponential {
+                c0: numbers(document, &dictionary, PdfName::C0)?.unwrap_or_else(|| vec![0.0]),
+                c1: numbers(document, &dictionary, PdfName::C1)?.unwrap_or_else(|| vec![1.0]),
+                n: match dictionary.get(&PdfName::N) {
+                    Some(n) => document.resolve(n.clone())?.as_f64().unwrap_or(1.0),
+                    None => return Err(PdfError::InvalidPdf("N missing from function")),
+                },
+            },
+            Some(3) if depth < MAX_DEPTH => {
+                if domain.len() != 2 {
+                    return Err(PdfError::InvalidPdf("invalid stitching function"));
+                }
+                let functions = match dictionary.get(&PdfName::Functions) {
+                    Some(functions) => document.resolve(functions.clone())?,
+                    None => return Err(PdfError::InvalidPdf("Functions missing from function")),
+                };
+                let functions = match functions {
+                    PdfObject::Array(functions) => functions
+                        .iter()
+                        .map(|function| Function::load(document, function, depth + 1))
+                        .collect::<crate::Result<Vec<Function>>>()?,
+                    _ => return Err(PdfError::InvalidPdf("invalid stitching function")),
+                };
+                let bounds = numbers(document, &dictionary, PdfName::Bounds)?.unwrap_or_default();
+                let encode = numbers(document, &dictionary, PdfName::Encode)?.unwrap_or_default();
+                if functions.is_empty()
+                    || bounds.len() + 1 != functions.len()
+                    || encode.len() != 2 * functions.len()
+                {
+                    return Err(PdfError::InvalidPdf("invalid stitching function"));
+                }
+                FunctionKind::Stitching {
+                    functions,
+                    bounds,
+                    encode,
+                }
+            }
+            Some(4) => {
+                let data = stream_data(document, reference)?;
+                let mut source: Box<dyn Source> = Box::new(ByteSource::new(data));
+                match next_object(&mut source)? {
+                    Some(PdfObject::Procedure(procedure)) => FunctionKind::PostScript(procedure),
+                    _ => {
+                        return Err(PdfError::InvalidPdf(
+                            "invalid PostScript calculator function",
+                        ))
+                    }
+                }
+            }
+            _ => return Err(PdfError::InvalidPdf("invalid FunctionType")),
+        };
+        Ok(Function {
+            domain,
+            range,
+            kind,
+        })
+    }
+
+    // the number of outputs, 0 if it can only be found by evaluating the function
+    pub fn outputs(&self) -> usize {
+        match (&self.range, &self.kind) {
+            (Some(range), _) => range.len() / 2,
+            (None, FunctionKind::Exponential { c0, .. }) => c0.len(),
+            (None, FunctionKind::Stitching { functions, .. }) => functions[0].outputs(),
+            _ => 0,
+        }
+    }
+
+    // evaluates the function, inputs are clipped to its domain and outputs to its range
+    pub fn eval(&self, input: &[f64]) -> Vec<f64> {
+        let input: Vec<f64> = self
+            .domain
+            .chunks(2)
+            .enumerate()
+            .map(|(i, domain)| match domain {
+                [min, max] => input
+                    .get(i)
+                    .cloned()
+                    .unwrap_or(*min)
+                    .clamp(*min, max.max(*min)),
+                _ => 0.0,
+            })
+            .collect();
+        let mut output = match &self.kind {
+            FunctionKind::Sampled {
+                size,
+                encode,
+                decode,
+                samples,
+            } => sampled(&input, size, encode, decode, samples, &self.domain),
+            FunctionKind::Exponential { c0, c1, n } => {
+                let x = input.first().cloned().unwrap_or(0.0);
+                c0.iter()
+                    .zip(c1.iter())
+                    .map(|(c0, c1)| c0 + x.powf(*n) * (c1 - c0))
+                    .collect()
+            }
+            FunctionKind::Stitching {
+                functions,
+                bounds,
+                encode,
+            } => {
+                let x = input.first().cloned().unwrap_or(0.0);
+                let k = bounds.iter().take_while(|&&bound| x >= bound).count();
+                let low = if k == 0 {
+                    self.domain[0]
+                } else {
+                    bounds[k - 1]
+                };
+                let high = if k == bounds.len() {
+                    self.domain[1]
+                } else {
+                    bounds[k]
+                };
+                functions[k].eval(&[interpolate(x, low, high, encode[2 * k], encode[2 * k + 1])])
+            }
+            FunctionKind::PostScript(procedure) => {
+                let mut stack: Vec<Value> = input.iter().map(|&x| Value::Number(x)).collect();
+                match execute(procedure, &mut stack) {
+                    Some(()) => stack.iter().map(|value| value.number()).collect(),
+                    None => vec![],
+                }
+            }
+        };
+        if let Some(range) = &self.range {
+            output.resize(range.len() / 2, 0.0);
+            for (value, range) in output.iter_mut().zip(range.chunks(2)) {
+                *value = value.clamp(range[0], range[1].max(range[0]));
+            }
+        }
+        output
+    }
+}
+
+// multilinear interpolation between the samples around the encoded input
+fn sampled(
+    input: &[f64],
+    size: &[usize],
+    encode: &[f64],
+    decode: &[f64],
+    samples: &[f64],
+    domain: &[f64],
+) -> Vec<f64> {
+    let outputs = decode.len() / 2;
+    // the sample grid position of each input, and the fraction of the way to the next one
+    let mut positions = vec![];
+    for (i, &size) in size.iter().enumerate() {
+        let x = input.get(i).cloned().unwrap_or(0.0);
+        let (d0, d1) = (domain[2 * i], domain[2 * i + 1]);
+        let e = encode.get(2 * i..2 * i + 2).unwrap_or(&[0.0, 0.0]);
+        let e = interpolate(x, d0, d1, e[0], e[1]).clamp(0.0, (size - 1) as f64);
+        let index = (e.floor() as usize).min(size.saturating_sub(2));
+        positions.push((index, e - index as f64));
+    }
+    let mut output = vec![0.0; outputs];
+    for corner in 0..(1usize << positions.len()) {
+        let mut weight = 1.0;
+        let (mut offset, mut stride) = (0, 1);
+        for (i, &(index, fraction)) in positions.iter().enumerate() {
+            let next = corner >> i & 1 == 1;
+            weight *= if next { fraction } else { 1.0 - fraction };
+            let index = if next && size[i] > 1 {
+                index + 1
+            } else {
+                index
+            };
+            offset += index * stride;
+            stride *= size[i];
+        }
+        if weight == 0.0 {
+            continue;
+        }
+        for (j, value) in output.iter_mut().enumerate() {
+            *value += weight * samples.get(offset * outputs + j).cloned().unwrap_or(0.0);
+        }
+    }
+    output
+        .into_iter()
+        .enumerate()
+        .map(|(j, value)| interpolate(value, 0.0, 1.0, decode[2 * j], decode[2 * j + 1]))
+        .collect()
+}
+
+// an operand of a PostScript calculator function
+#[derive(Debug, Clone, Copy)]
+enum Value<'a> {
+    Number(f64),
+    Boolean(bool),
+    Procedure(&'a [PdfObject]),
+}
+
+impl Value<'_> {
+    fn number(&self) -> f64 {
+        match self {
+            Value::Number(number) => *number,
+            Value::Boolean(boolean) => *boolean as u8 as f64,
+            Value::Procedure(_) => 0.0,
+        }
+    }
+
+    // an integer operand, such as the shift taken by roll
+    fn integer(&self) -> Option<i64> {
+        match self {
+            Value::Number(number) if number.fract() == 0.0 && number.abs() < i64::MAX as f64 => {
+                Some(*number as i64)
+            }
+            _ => None,
+        }
+    }
+
+    // a non-negative integer operand, such as the counts taken by copy, index and roll
+    fn count(&self) -> Option<usize> {
+        usize::try_from(self.integer()?).ok()
+    }
+}
+
+// runs a PostScript calculator procedure, None if it fails
+fn execute<'a>(procedure: &'a [PdfObject], stack: &mut Vec<Value<'a>>) -> Option<()> {
+    use PdfKeyword::*;
+    for object in procedure.iter() {
+        let operator = match object {
+            PdfObject::Number(number) => {
+                stack.push(Value::Number(number.as_f64()));
+                continue;
+            }
+            PdfObject::Boolean(boolean) => {
+                stack.push(Value::Boolean(*boolean));
+                continue;
+            }
+            PdfObject::Procedure(procedure) => {
+                stack.push(Value::Procedure(procedure));
+                continue;
+            }
+            PdfObject::Keyword(operator) => operator,
+            _ => return None,
+        };
+        match operator {
+            r#if => {
+                let procedure = stack.pop()?;
+                if let (Value::Boolean(condition), Value::Procedure(procedure)) =
+                    (stack.pop()?, procedure)
+                {
+                    if condition {
+                        execute(procedure, stack)?;
+                    }
+                } else {
+                    return None;
+                }
+            }
+            ifelse => {
+                let (otherwise, then) = (stack.pop()?, stack.pop()?);
+                match (stack.pop()?, then, otherwise) {
+                    (
+                        Value::Boolean(condition),
+                        Value::Procedure(then),
+                        Value::Procedure(otherwise),
+                    ) => execute(if condition { then } else { otherwise }, stack)?,
+                    _ => return None,
+                }
+            }
+            pop => {
+                stack.pop()?;
+            }
+            exch => {
+                let (top, below) = (stack.pop()?, stack.pop()?);
+                stack.extend([top, below]);
+            }
+            dup => stack.push(*stack.last()?),
+            copy => {
+                let count = stack.pop()?.count()?;
+                let start = stack.len().checked_sub(count)?;
+                stack.extend_from_within(start..);
+            }
+            index => {
+                let depth = stack.pop()?.count()?;
+                let value = *stack.get(stack.len().checked_sub(depth.checked_add(1)?)?)?;
+                stack.push(value);
+            }
+            roll => {
+                let shift = stack.pop()?.integer()?;
+                let count = stack.pop()?.count()?;
+                let start = stack.len().checked_sub(count)?;
+                if count > 0 {
+                    let shift = shift.rem_euclid(count as i64) as usize;
+                    stack[start..].rotate_right(shift);
+                }
+            }
+            operator => {
+                let value = operate(operator, stack)?;
+                stack.push(value);
+            }
+        }
+        if stack.len() > MAX_STACK {
+            return None;
+        }
+    }
+    Some(())
+}
+
+// the arithmetic, relational, boolean and bitwise operators
+fn operate<'a>(operator: &PdfKeyword, stack: &mut Vec<Value<'a>>) -> Option<Value<'a>> {
+    use PdfKeyword::*;
+    let number = |stack: &mut Vec<Value>| stack.pop().map(|value| value.number());
+    Some(match operator {
+        abs => Value::Number(number(stack)?.abs()),
+        neg => Value::Number(-number(stack)?),
+        ceiling => Value::Number(number(stack)?.ceil()),
+        floor => Value::Number(number(stack)?.floor()),
+        round => Value::Number((number(stack)? + 0.5).floor()),
+        truncate | cvi => Value::Number(number(stack)?.trunc()),
+        cvr => Value::Number(number(stack)?),
+        sqrt => Value::Number(number(stack)?.sqrt()),
+        // angles are in degrees
+        sin => Value::Number(number(stack)?.to_radians().sin()),
+        cos => Value::Number(number(stack)?.to_radians().cos()),
+        ln => Value::Number(number(stack)?.ln()),
+        log => Value::Number(number(stack)?.log10()),
+        not => match stack.pop()? {
+            Value::Boolean(boolean) => Value::Boolean(!boolean),
+            value => Value::Number(!(value.number() as i64) as f64),
+        },
+        _ => {
+            let (second, first) = (stack.pop()?, stack.pop()?);
+            let (left, right) = (first.number(), second.number());
+            match operator {
+                add => Value::Number(left + right),
+                sub => Value::Number(left - right),
+                mul => Value::Number(left * right),
+                div if right != 0.0 => Value::Number(left / right),
+                idiv if right as i64 != 0 => Value::Number((left as i64 / right as i64) as f64),
+                r#mod if right as i64 != 0 => Value::Number((left as i64 % right as i64) as f64),
+                atan => Value::Number(left.atan2(right).to_degrees().rem_euclid(360.0)),
+                exp => Value::Number(left.powf(right)),
+                eq => Value::Boolean(left == right),
+                ne => Value::Boolean(left != right),
+                gt => Value::Boolean(left > right),
+                ge => Value::Boolean(left >= right),
+                lt => Value::Boolean(left < right),
+                le => Value::Boolean(left <= right),
+                and | or | xor => match (first, second) {
+                    (Value::Boolean(first), Value::Boolean(second)) => {
+                        Value::Boolean(match operator {
+                            and => first && second,
+                            or => first || second,
+                            _ => first ^ second,
+                        })
+                    }
+                    _ => {
+                        let (left, right) = (left as i64, right as i64);
+                        Value::Number(match operator {
+                            and => left & right,
+                            or => left | right,
+                            _ => left ^ right,
+                        } as f64)
+                    }
+                },
+                bitshift => {
+                    let (left, shift) = (left as i64, right as i64);
+                    Value::Number(if shift >= 0 {
+                        left.checked_shl(shift as u32).unwrap_or(0)
+                    } else {
+                        left.checked_shr(-shift as u32).unwrap_or(0)
+                    } as f64)
+                }
+                _ => return None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files;
+
+    fn numbers(values: &[f64]) -> PdfObject {
+        PdfObject::Array(Box::new(
+            values
+                .iter()
+                .map(|&value| PdfObject::Number(PdfNumber::Real(value)))
+                .collect(),
+        ))
+    }
+
+    fn postscript(program: &[u8], domain: &[f64], range: &[f64]) -> Function {
+        let mut source: Box<dyn Source> = Box::new(ByteSource::new(program.to_vec()));
+        let procedure = match next_object(&mut source).unwrap() {
+            Some(PdfObject::Procedure(procedure)) => procedure,
+            object => panic!("procedure expected, found {:?}", object),
+        };
+        Function {
+            domain: domain.to_vec(),
+            range: Some(range.to_vec()),
+            kind: FunctionKind::PostScript(procedure),
+        }
+    }
+
+    #[test]
+    fn exponential_and_stitching() {
+        let mut document = test_files::open("minimal.pdf");
+        let mut dictionary: Dictionary = Box::default();
+        dictionary.insert(
+            PdfName::FunctionType,
+            PdfObject::Number(PdfNumber::Integer(2)),
+        );
+        dictionary.insert(PdfName::Domain, numbers(&[0.0, 1.0]));
+        dictionary.insert(PdfName::C0, numbers(&[1.0, 0.0]));
+        dictionary.insert(PdfName::C1, numbers(&[0.0, 0.5]));
+        dictionary.insert(PdfName::N, PdfObject::Number(PdfNumber::Integer(2)));
+        let exponential = PdfObject::Dictionary(dictionary);
+        let function = Function::new(&mut document, &exponential).unwrap();
+        assert_eq!(function.outputs(), 2);
+        assert_eq!(function.eval(&[0.5]), vec![0.75, 0.125]);
+        // inputs are clipped to the domain
+        assert_eq!(function.eval(&[2.0]), vec![0.0, 0.5]);
+
+        let mut dictionary: Dictionary = Box::default();
+        dictionary.insert(
+            PdfName::FunctionType,
+            PdfObject::Number(PdfNumber::Integer(3)),
+        );
+        dictionary.insert(PdfName::Domain, numbers(&[0.0, 2.0]));
+        let functions = vec![exponential.clone(), exponential];
+        dictionary.insert(PdfName::Functions, PdfObject::Array(Box::new(functions)));
+        dictionary.insert(PdfName::Bounds, numbers(&[1.0]));
+        dictionary.insert(PdfName::Encode, numbers(&[0.0, 1.0, 1.0, 0.0]));
+        let function = Function::new(&mut document, &PdfObject::Dictionary(dictionary)).unwrap();
+        assert_eq!(function.eval(&[0.5]), vec![0.75, 0.125]);
+        assert_eq!(function.eval(&[1.5]), vec![0.75, 0.125]);
+        assert_eq!(function.eval(&[2.0]), vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn sampled_function() {
+        // two inputs on a 2 x 2 grid, one output
+        let function = Function {
+            domain: vec![0.0, 1.0, 0.0, 1.0],
+            range: Some(vec![0.0, 1.0]),
+            kind: FunctionKind::Sampled {
+                size: vec![2, 2],
+                encode: vec![0.0, 1.0, 0.0, 1.0],
+                decode: vec![0.0, 1.0],
+                samples: unpack_samples(&[0x00, 0xff, 0xff, 0x00], 8, 4)
+                    .unwrap()
+                    .into_iter()
+                    .map(|sample| sample / 255.0)
+                    .collect(),
+            },
+        };
+        assert_eq!(function.eval(&[0.0, 0.0]), vec![0.0]);
+        assert_eq!(function.eval(&[1.0, 0.0]), vec![1.0]);
+        assert_eq!(function.eval(&[0.0, 1.0]), vec![1.0]);
+        assert_eq!(function.eval(&[0.5, 0.5]), vec![0.5]);
+        assert_eq!(function.eval(&[0.25, 0.0]), vec![0.25]);
+        assert_eq!(
+            unpack_samples(&[0xab, 0xcd], 4, 4).unwrap(),
+            vec![10.0, 11.0, 12.0, 13.0]
+        );
+        assert_eq!(
+            unpack_samples(&[0xab, 0xcd, 0xe0], 12, 2).unwrap(),
+            vec![2748.0, 3552.0]
+        );
+        // the count can't exceed what the data holds
+        assert_eq!(
+            unpack_samples(&[0xab, 0xcd, 0xe0], 8, usize::MAX).unwrap(),
+            vec![171.0, 205.0, 224.0]
+        );
+        assert!(unpack_samples(&[0xab], 64, 1).is_err());
+    }
+
+    #[test]
+    fn invalid_functions() {
+        let mut document = test_files::open("minimal.pdf");
+        let function = |function_type: i64, entries: &[(PdfName, &[f64])]| {
+            let mut dictionary: Dictionary = Box::default();
+            dictionary.insert(
+                PdfName::FunctionType,
+                PdfObject::Number(PdfNumber::Integer(function_type)),
+            );
+            dictionary.insert(PdfName::N, PdfObject::Number(PdfNumber::Integer(1)));
+            for (key, values) in entries.iter() {
+                dictionary.insert(key.clone(), numbers(values));
+            }
+            PdfObject::Dictionary(dictionary)
+        };
+        let invalid = [
+            function(2, &[(PdfName::Domain, &[0.0])]),
+            function(2, &[(PdfName::Domain, &[]), (PdfName::Range, &[0.0, 1.0])]),
+            function(
+                2,
+                &[(PdfName::Domain, &[0.0, 1.0]), (PdfName::Range, &[0.0])],
+            ),
+            // a sampled function needs a domain for each dimension of Size
+            function(
+                0,
+                &[
+                    (PdfName::Domain, &[0.0, 1.0]),
+                    (PdfName::Range, &[0.0, 1.0]),
+                    (PdfName::Size, &[2.0, 2.0]),
+                ],
+            ),
+            function(
+                0,
+                &[
+                    (PdfName::Domain, &[0.0, 1.0, 0.0, 1.0]),
+                    (PdfName::Range, &[0.0, 1.0]),
+                    (PdfName::Size, &[1e19, 1e19]),
+                ],
+            ),
+            // a stitching function has one input
+            function(
+                3,
+                &[
+                    (PdfName::Domain, &[0.0, 1.0, 0.0, 1.0]),
+                    (PdfName::Functions, &[]),
+                ],
+            ),
+        ];
+        for object in invalid.iter() {
+            assert!(matches!(
+                Function::new(&mut document, object),
+                Err(PdfError::InvalidPdf(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn postscript_calculator() {
+        // a DeviceN tint transform to CMYK
+        let function = postscript(
+            b"{ 0 0 0 4 -1 roll }",
+            &[0.0, 1.0],
+            &[0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0],
+        );
+        assert_eq!(function.eval(&[0.25]), vec![0.0, 0.0, 0.0, 0.25]);
+        let function = postscript(
+            b"{ dup 0.5 gt { 2 mul 1 sub } { pop 0 } ifelse dup 0 eq { 3 exch pop } if }",
+            &[0.0, 1.0],
+            &[0.0, 10.0],
+        );
+        assert_eq!(function.eval(&[0.75]), vec![0.5]);
+        assert_eq!(function.eval(&[0.25]), vec![3.0]);
+        let function = postscript(
+            b"{ 2 copy add 3 1 roll mul 1 index exch sub 7 3 idiv 2 bitshift 1 2 lt xor }",
+            &[0.0, 10.0, 0.0, 10.0],
+            &[-100.0, 100.0, -100.0, 100.0, -100.0, 100.0],
+        );
+        // 2 copy add: 2 3 5, 3 1 roll: 5 2 3, mul: 5 6, 1 index: 5 6 5, exch sub: 5 -1
+        assert_eq!(function.eval(&[2.0, 3.0]), vec![5.0, -1.0, 9.0]);
+        // a stack underflow fails, leaving zero outputs
+        let function = postscript(b"{ add add }", &[0.0, 1.0], &[0.0, 1.0]);
+        assert_eq!(function.eval(&[1.0]), vec![0.0]);
+        // as do negative, fractional and huge counts
+        for program in [
+            &b"{ -1 index }"[..],
+            b"{ 1e30 index }",
+            b"{ 0.5 index }",
+            b"{ -1 copy }",
+            b"{ 1.5 copy }",
+            b"{ -1 1 roll }",
+            b"{ 1 0.5 roll }",
+        ] {
+            let function = postscript(program, &[0.0, 1.0], &[0.0, 1.0]);
+            assert_eq!(function.eval(&[1.0]), vec![0.0]);
+        }
+        let function = postscript(
+            b"{ 0 index add 1.0 copy mul 1 -1 roll }",
+            &[0.0, 1.0],
+            &[0.0, 10.0],
+        );
+        assert_eq!(function.eval(&[1.0]), vec![4.0]);
+    }
+}
@@ -0,0 +1,262 @@
+use std::rc::Rc;
+
+use crate::cmap::Code;
+use crate::font::Font;
+use crate::matrix::Matrix;
+use crate::pdf_types::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorSpace {
+    DeviceGray,
+    DeviceRGB,
+    DeviceCMYK,
+    Pattern,
+    // any other color space, as its definition array, e.g. [/ICCBased <<...>>]
+    Other(PdfObject),
+}
+
+impl ColorSpace {
+    // the color space named by a CS/cs operand or a /ColorSpace resource
+    pub fn from_object(object: PdfObject) -> ColorSpace {
+        match object {
+            PdfObject::Name(PdfName::DeviceGray) => ColorSpace::DeviceGray,
+            PdfObject::Name(PdfName::DeviceRGB) => ColorSpace::DeviceRGB,
+            PdfObject::Name(PdfName::DeviceCMYK) => ColorSpace::DeviceCMYK,
+            PdfObject::Name(PdfName::Pattern) => ColorSpace::Pattern,
+            PdfObject::Array(array) if array.len() == 1 => {
+                ColorSpace::from_object(array.into_iter().next().unwrap())
+            }
+            object => ColorSpace::Other(object),
+        }
+    }
+
+    // the number of color components, 0 if it can't be determined
+    pub fn components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray => 1,
+            ColorSpace::DeviceRGB => 3,
+            ColorSpace::DeviceCMYK => 4,
+            ColorSpace::Pattern => 0,
+            ColorSpace::Other(PdfObject::Array(array)) => match array.first() {
+                Some(PdfObject::Name(PdfName::CalGray))
+                | Some(PdfObject::Name(PdfName::Indexed))
+                | Some(PdfObject::Name(PdfName::Separation)) => 1,
+                Some(PdfObject::Name(PdfName::CalRGB)) | Some(PdfObject::Name(PdfName::Lab)) => 3,
+                Some(PdfObject::Name(PdfName::DeviceN)) => match array.get(1) {
+                    Some(PdfObject::Array(names)) => names.len(),
+                    _ => 0,
+                },
+                Some(PdfObject::Name(PdfName::ICCBased)) => match array.get(1) {
+                    Some(PdfObject::Dictionary(dict)) => match dict.get(&PdfName::N) {
+                        Some(PdfObject::Number(PdfNumber::Integer(n))) => *n as usize,
+                        _ => 0,
+                    },
+                    _ => 0,
+                },
+                _ => 0,
+            },
+            ColorSpace::Other(_) => 0,
+        }
+    }
+
+    // the color selected when the color space is set
+    pub fn initial_color(&self) -> Vec<f64> {
+        match self {
+            ColorSpace::DeviceCMYK => vec![0.0, 0.0, 0.0, 1.0],
+            ColorSpace::Other(PdfObject::Array(array))
+                if array.first() == Some(&PdfObject::Name(PdfName::Separation))
+                    || array.first() == Some(&PdfObject::Name(PdfName::DeviceN)) =>
+            {
+                vec![1.0; self.components()]
+            }
+            space => vec![0.0; space.components()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color {
+    pub space: ColorSpace,
+    pub components: Vec<f64>,
+    // the /Pattern resource name, for the Pattern color space
+    pub pattern: Option<PdfName>,
+}
+
+impl Color {
+    pub fn new(space: ColorSpace) -> Color {
+        Color {
+            components: space.initial_color(),
+            space,
+            pattern: None,
+        }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Color {
+        Color::new(ColorSpace::DeviceGray)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextState {
+    pub char_spacing: f64,
+    pub word_spacing: f64,
+    // Tz operand divided by 100
+    pub horizontal_scaling: f64,
+    pub leading: f64,
+    // the /Font resource name and size set by Tf, and the font if it could be loaded
+    pub font_name: Option<PdfName>,
+    pub font: Option<Rc<Font>>,
+    pub font_size: f64,
+    pub render_mode: u32,
+    pub rise: f64,
+    // the text matrix and text line matrix, reset by BT
+    pub matrix: Matrix,
+    pub line_matrix: Matrix,
+}
+
+impl Default for TextState {
+    fn default() -> TextState {
+        TextState {
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            leading: 0.0,
+            font_name: None,
+            font: None,
+            font_size: 0.0,
+            render_mode: 0,
+            rise: 0.0,
+            matrix: Matrix::identity(),
+            line_matrix: Matrix::identity(),
+        }
+    }
+}
+
+impl TextState {
+    // moves to the start of the next line, offset by (tx, ty) from the start of the current line
+    pub fn next_line(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = Matrix::translate(tx, ty).multiply(&self.line_matrix);
+        self.matrix = self.line_matrix;
+    }
+
+    fn vertical(&self) -> bool {
+        self.font.as_ref().is_some_and(|font| font.is_vertical())
+    }
+
+    // the code of each glyph in a string and the displacement (tx, ty) it causes, in unscaled
+    // text space units
+    pub fn glyph_advances(&self, text: &[u8]) -> Vec<(Code, (f64, f64))> {
+        let codes = match &self.font {
+            Some(font) => font.codes(text),
+            None => text.iter().map(|&byte| Code::new(&[byte])).collect(),
+        };
+        let vertical = self.vertical();
+        codes
+            .into_iter()
+            .map(|code| {
+                let width = match &self.font {
+                    Some(font) if vertical => font.vertical_advance(code),
+                    Some(font) => font.width(code),
+                    None => 0.0,
+                };
+                let mut advance = width / 1000.0 * self.font_size + self.char_spacing;
+                // word spacing only applies to the single byte code 32
+                if code == Code::new(b" ") {
+                    advance += self.word_spacing;
+                }
+                if vertical {
+                    (code, (0.0, advance))
+                } else {
+                    (code, (advance * self.horizontal_scaling, 0.0))
+                }
+            })
+            .collect()
+    }
+
+    // the displacement (tx, ty) caused by showing a string, in unscaled text space units
+    pub fn displacement(&self, text: &[u8]) -> (f64, f64) {
+        self.glyph_advances(text)
+            .into_iter()
+            .fold((0.0, 0.0), |(tx, ty), (_, (dx, dy))| (tx + dx, ty + dy))
+    }
+
+    // moves the text matrix past a string which has been shown
+    pub fn advance(&mut self, text: &[u8]) {
+        let (tx, ty) = self.displacement(text);
+        self.matrix = Matrix::translate(tx, ty).multiply(&self.matrix);
+    }
+
+    // moves the text matrix by a TJ adjustment, in thousandths of a unit of text space
+    pub fn adjust(&mut self, adjustment: f64) {
+        let advance = -adjustment / 1000.0 * self.font_size;
+        let (tx, ty) = if self.vertical() {
+            (0.0, advance)
+        } else {
+            (advance * self.horizontal_scaling, 0.0)
+        };
+        self.matrix = Matrix::translate(tx, ty).multiply(&self.matrix);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DashPattern {
+    pub array: Vec<f64>,
+    pub phase: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphicsState {
+    pub ctm: Matrix,
+    pub line_width: f64,
+    pub line_cap: u32,
+    pub line_join: u32,
+    pub miter_limit: f64,
+    pub dash: DashPattern,
+    pub rendering_intent: PdfName,
+    pub flatness: f64,
+    pub stroke_color: Color,
+    pub fill_color: Color,
+    pub stroke_alpha: f64,
+    pub fill_alpha: f64,
+    pub text: TextState,
+}
+
+impl Default for GraphicsState {
+    fn default() -> GraphicsState {
+        GraphicsState {
+            ctm: Matrix::identity(),
+            line_width: 1.0,
+            line_cap: 0,
+            line_join: 0,
+            miter_limit: 10.0,
+            dash: DashPattern::default(),
+            rendering_intent: PdfName::RelativeColorimetric,
+            flatness: 1.0,
+            stroke_color: Color::default(),
+            fill_color: Color::default(),
+            stroke_alpha: 1.0,
+            fill_alpha: 1.0,
+            text: TextState::default(),
+        }
+    }
+}
+
+impl GraphicsState {
+    pub fn new(ctm: Matrix) -> GraphicsState {
+        GraphicsState {
+            ctm,
+            ..GraphicsState::default()
+        }
+    }
+
+    // sets the dash pattern from d operands or a /D graphics state parameter, if they are valid
+    pub fn set_dash(&mut self, array: &PdfObject, phase: &PdfObject) {
+        if let (PdfObject::Array(array), Some(phase)) = (array, phase.as_f64()) {
+            if let Some(array) = array.iter().map(|item| item.as_f64()).collect() {
+                self.dash = DashPattern { array, phase };
+            }
+        }
+    }
+}
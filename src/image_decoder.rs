@@ -0,0 +1,810 @@
+use std::collections::HashMap;
+
+use crate::dictionary::Access;
+use crate::function::Function;
+use crate::graphics_state::ColorSpace;
+use crate::images::{entry, PageImage};
+use crate::pdf_document::PdfDocument;
+use crate::pdf_types::*;
+use crate::streams::decode_stream;
+use crate::PdfError;
+
+// how deeply color spaces can be nested, e.g. an Indexed space over a Separation space
+const MAX_DEPTH: usize = 4;
+
+// the D65 white point of sRGB
+const D65: [f64; 3] = [0.9505, 1.0, 1.089];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelFormat {
+    Gray,
+    Rgb,
+    Rgba,
+}
+
+impl PixelFormat {
+    pub fn channels(&self) -> usize {
+        match self {
+            PixelFormat::Gray => 1,
+            PixelFormat::Rgb => 3,
+            PixelFormat::Rgba => 4,
+        }
+    }
+}
+
+// 8-bit pixels, row by row from the top left, without padding
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+    pub pixels: Vec<u8>,
+}
+
+// converts the components of a color space to RGB, each from 0 to 1
+//
+// Calibrated spaces are treated as their device equivalents and ICC profiles are not applied,
+// their alternate space is used instead.
+#[derive(Debug)]
+enum Converter {
+    Gray,
+    Rgb,
+    Cmyk,
+    Lab {
+        range: [f64; 4],
+    },
+    Indexed {
+        base: Box<Converter>,
+        hival: usize,
+        lookup: Vec<u8>,
+    },
+    // Separation and DeviceN, with the tint transform given as one function or one for each
+    // component of the alternate space
+    Tint {
+        components: usize,
+        functions: Vec<Function>,
+        alternate: Box<Converter>,
+    },
+}
+
+fn resolved_numbers(document: &mut PdfDocument, object: PdfObject) -> crate::Result<Vec<f64>> {
+    match document.resolve(object)? {
+        PdfObject::Array(array) => {
+            let mut numbers = vec![];
+            for object in array.into_iter() {
+                numbers.push(document.resolve(object)?.as_f64().unwrap_or(0.0));
+            }
+            Ok(numbers)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+impl Converter {
+    fn new(document: &mut PdfDocument, space: &ColorSpace) -> crate::Result<Converter> {
+        match space {
+            ColorSpace::DeviceGray => Ok(Converter::Gray),
+            ColorSpace::DeviceRGB => Ok(Converter::Rgb),
+            ColorSpace::DeviceCMYK => Ok(Converter::Cmyk),
+            ColorSpace::Pattern => Err(PdfError::InvalidPdf("invalid image color space")),
+            ColorSpace::Other(object) => Converter::from_object(document, object.clone(), 0),
+        }
+    }
+
+    fn from_object(
+        document: &mut PdfDocument,
+        object: PdfObject,
+        depth: usize,
+    ) -> crate::Result<Converter> {
+        let invalid = PdfError::InvalidPdf("invalid image color space");
+        if depth > MAX_DEPTH {
+            return Err(invalid);
+        }
+        let array = match document.resolve(object)? {
+            PdfObject::Name(PdfName::DeviceGray) | PdfObject::Name(PdfName::CalGray) => {
+                return Ok(Converter::Gray)
+            }
+            PdfObject::Name(PdfName::DeviceRGB) | PdfObject::Name(PdfName::CalRGB) => {
+                return Ok(Converter::Rgb)
+            }
+            PdfObject::Name(PdfName::DeviceCMYK) => return Ok(Converter::Cmyk),
+            PdfObject::Array(array) if !array.is_empty() => array,
+            _ => return Err(invalid),
+        };
+        let family = array[0].as_name();
+        let parameter = |index: usize| array.get(index).cloned().unwrap_or(PdfObject::Null);
+        match family {
+            Some(PdfName::DeviceGray) | Some(PdfName::CalGray) => Ok(Converter::Gray),
+            Some(PdfName::DeviceRGB) | Some(PdfName::CalRGB) => Ok(Converter::Rgb),
+            Some(PdfName::DeviceCMYK) => Ok(Converter::Cmyk),
+            Some(PdfName::Lab) => {
+                let dictionary = match document.resolve(parameter(1))? {
+                    PdfObject::Dictionary(dictionary) => dictionary,
+                    _ => Box::default(),
+                };
+                let range = match dictionary.get(&PdfName::Range) {
+                    Some(range) => resolved_numbers(document, range.clone())?,
+                    None => vec![],
+                };
+                Ok(Converter::Lab {
+                    range: match range[..] {
+                        [a0, a1, b0, b1] => [a0, a1, b0, b1],
+                        _ => [-100.0, 100.0, -100.0, 100.0],
+                    },
+                })
+            }
+            Some(PdfName::ICCBased) => {
+                let dictionary = match document.resolve(parameter(1))? {
+                    PdfObject::Dictionary(dictionary) => dictionary,
+                    _ => return Err(invalid),
+                };
+                if let Some(alternate) = dictionary.get(&PdfName::Alternate) {
+                    return Converter::from_object(document, alternate.clone(), depth + 1);
+                }
+                match dictionary.get(&PdfName::N) {
+                    Some(n) => match document.resolve(n.clone())?.as_f64() {
+                        Some(1.0) => Ok(Converter::Gray),
+                        Some(3.0) => Ok(Converter::Rgb),
+                        Some(4.0) => Ok(Converter::Cmyk),
+                        _ => Err(invalid),
+                    },
+                    None => Err(invalid),
+                }
+            }
+            Some(PdfName::Indexed) => {
+                let base = Converter::from_object(document, parameter(1), depth + 1)?;
+                let hival = document.resolve(parameter(2))?.as_f64().unwrap_or(0.0);
+                let lookup = match parameter(3) {
+                    PdfObject::Reference(reference) => match document.resolve(parameter(3))? {
+                        PdfObject::String(lookup) => lookup,
+                        _ => document.stream_data(reference)?,
+                    },
+                    PdfObject::String(lookup) => lookup,
+                    _ => return Err(invalid),
+                };
+                Ok(Converter::Indexed {
+                    base: Box::new(base),
+                    hival: hival.clamp(0.0, 255.0) as usize,
+                    lookup,
+                })
+            }
+            Some(PdfName::Separation) | Some(PdfName::DeviceN) => {
+                let components = match (family, document.resolve(parameter(1))?) {
+                    (Some(PdfName::DeviceN), PdfObject::Array(names)) => names.len(),
+                    _ => 1,
+                };
+                let alternate = Converter::from_object(document, parameter(2), depth + 1)?;
+                let functions = match document.resolve(parameter(3))? {
+                    PdfObject::Array(functions) => functions
+                        .iter()
+                        .map(|function| Function::new(document, function))
+                        .collect::<crate::Result<Vec<Function>>>()?,
+                    _ => vec![Function::new(document, &parameter(3))?],
+                };
+                Ok(Converter::Tint {
+                    components,
+                    functions,
+                    alternate: Box::new(alternate),
+                })
+            }
+            _ => Err(PdfError::Unsupported("image color space")),
+        }
+    }
+
+    fn components(&self) -> usize {
+        match self {
+            Converter::Gray | Converter::Indexed { .. } => 1,
+            Converter::Rgb | Converter::Lab { .. } => 3,
+            Converter::Cmyk => 4,
+            Converter::Tint { components, .. } => *components,
+        }
+    }
+
+    // whether every color is a shade of gray
+    fn is_gray(&self) -> bool {
+        match self {
+            Converter::Gray => true,
+            Converter::Indexed { base, .. } => base.is_gray(),
+            Converter::Tint { alternate, .. } => alternate.is_gray(),
+            _ => false,
+        }
+    }
+
+    // the range of each component, the default /Decode array of an image
+    fn default_decode(&self, bits_per_component: u32) -> Vec<f64> {
+        match self {
+            Converter::Indexed { .. } => vec![0.0, ((1u32 << bits_per_component) - 1) as f64],
+            Converter::Lab { range, .. } => {
+                vec![0.0, 100.0, range[0], range[1], range[2], range[3]]
+            }
+            converter => [0.0, 1.0].repeat(converter.components()),
+        }
+    }
+
+    fn to_rgb(&self, components: &[f64]) -> [f64; 3] {
+        let component = |i: usize| components.get(i).cloned().unwrap_or(0.0);
+        match self {
+            Converter::Gray => [component(0); 3],
+            Converter::Rgb => [component(0), component(1), component(2)],
+            Converter::Cmyk => {
+                let k = 1.0 - component(3);
+                [
+                    (1.0 - component(0)) * k,
+                    (1.0 - component(1)) * k,
+                    (1.0 - component(2)) * k,
+                ]
+            }
+            Converter::Lab { range } => lab_to_rgb(
+                component(0),
+                component(1).clamp(range[0], range[1]),
+                component(2).clamp(range[2], range[3]),
+            ),
+            Converter::Indexed {
+                base,
+                hival,
+                lookup,
+            } => {
+                let index = (component(0).round().max(0.0) as usize).min(*hival);
+                let n = base.components();
+                let decode = base.default_decode(8);
+                let entry: Vec<f64> = (0..n)
+                    .map(|i| {
+                        let value = lookup.get(index * n + i).cloned().unwrap_or(0) as f64;
+                        decode[2 * i] + value / 255.0 * (decode[2 * i + 1] - decode[2 * i])
+                    })
+                    .collect();
+                base.to_rgb(&entry)
+            }
+            Converter::Tint {
+                functions,
+                alternate,
+                ..
+            } => {
+                let alternate_components: Vec<f64> = match &functions[..] {
+                    [function] => function.eval(components),
+                    functions => functions
+                        .iter()
+                        .map(|function| function.eval(components).first().cloned().unwrap_or(0.0))
+                        .collect(),
+                };
+                alternate.to_rgb(&alternate_components)
+            }
+        }
+    }
+
+    // whether colors are expensive enough to convert that they should be cached
+    fn is_slow(&self) -> bool {
+        match self {
+            Converter::Lab { .. } | Converter::Tint { .. } => true,
+            Converter::Indexed { base, .. } => base.is_slow(),
+            _ => false,
+        }
+    }
+}
+
+// CIE L*a*b* to sRGB
+fn lab_to_rgb(l: f64, a: f64, b: f64) -> [f64; 3] {
+    let g = |x: f64| {
+        if x >= 6.0 / 29.0 {
+            x * x * x
+        } else {
+            108.0 / 841.0 * (x - 4.0 / 29.0)
+        }
+    };
+    let m = (l + 16.0) / 116.0;
+    // XYZ relative to the white point of sRGB, rather than that of the color space, which
+    // maps white to white
+    let x = g(m + a / 500.0) * D65[0];
+    let y = g(m) * D65[1];
+    let z = g(m - b / 200.0) * D65[2];
+    let linear = [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ];
+    linear.map(|v| {
+        let v = v.clamp(0.0, 1.0);
+        if v <= 0.0031308 {
+            12.92 * v
+        } else {
+            1.055 * v.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+fn to_u8(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// the rows of an image which its data covers, the last of which may be partial, so that the
+// samples of an image with a bogus /Width or /Height can't outgrow its data
+fn covered_rows(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    components: usize,
+    bits: u32,
+) -> crate::Result<usize> {
+    let row_bits = width
+        .checked_mul(components)
+        .and_then(|samples| samples.checked_mul(bits as usize));
+    match row_bits.map(|bits| bits.div_ceil(8)) {
+        Some(row_bytes) if row_bytes > 0 && row_bytes <= data.len() => {
+            Ok(height.min(data.len().div_ceil(row_bytes)))
+        }
+        _ => Err(PdfError::InvalidPdf(
+            "image data is too short for its Width",
+        )),
+    }
+}
+
+// the samples of an image, each row starting on a byte boundary, padded with zeros if the data
+// is short
+fn unpack(data: &[u8], width: usize, height: usize, components: usize, bits: u32) -> Vec<u16> {
+    let per_row = width * components;
+    let row_bytes = (per_row * bits as usize).div_ceil(8);
+    let mut samples = Vec::with_capacity(per_row * height);
+    for row in 0..height {
+        let start = (row * row_bytes).min(data.len());
+        let row = &data[start..(start + row_bytes).min(data.len())];
+        for i in 0..per_row {
+            let sample = match bits {
+                16 => {
+                    let high = row.get(2 * i).cloned().unwrap_or(0) as u16;
+                    let low = row.get(2 * i + 1).cloned().unwrap_or(0) as u16;
+                    high << 8 | low
+                }
+                8 => row.get(i).cloned().unwrap_or(0) as u16,
+                bits => {
+                    let bit = i * bits as usize;
+                    let byte = row.get(bit / 8).cloned().unwrap_or(0);
+                    let shift = 8 - bits as usize - bit % 8;
+                    ((byte >> shift) & ((1u8 << bits) - 1)) as u16
+                }
+            };
+            samples.push(sample);
+        }
+    }
+    samples
+}
+
+fn number(object: Option<PdfObject>) -> Option<f64> {
+    object.and_then(|object| object.as_f64())
+}
+
+// the decoded data of an image, and its dimensions, which must be positive
+fn dimensions(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+) -> crate::Result<(usize, usize)> {
+    let width = number(entry(document, dictionary, PdfName::Width)?).unwrap_or(0.0);
+    let height = number(entry(document, dictionary, PdfName::Height)?).unwrap_or(0.0);
+    if width < 1.0 || height < 1.0 {
+        return Err(PdfError::InvalidPdf("invalid image dimensions"));
+    }
+    Ok((width as usize, height as usize))
+}
+
+fn decode_array(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+    default: Vec<f64>,
+) -> crate::Result<Vec<f64>> {
+    let decode = match dictionary.get(&PdfName::Decode) {
+        Some(decode) => resolved_numbers(document, decode.clone())?,
+        None => vec![],
+    };
+    Ok(if decode.len() == default.len() {
+        decode
+    } else {
+        default
+    })
+}
+
+// which pixels a 1-bit stencil mask paints, those whose samples decode to 0
+fn stencil(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+    data: &[u8],
+) -> crate::Result<(usize, usize, Vec<bool>)> {
+    let (width, height) = dimensions(document, dictionary)?;
+    let height = covered_rows(data, width, height, 1, 1)?;
+    let decode = decode_array(document, dictionary, vec![0.0, 1.0])?;
+    let painted = unpack(data, width, height, 1, 1)
+        .into_iter()
+        .map(|sample| (sample == 0) == (decode[0] < decode[1]))
+        .collect();
+    Ok((width, height, painted))
+}
+
+// scales a mask to the size of an image, picking the nearest mask pixel
+fn resample<T: Copy>(mask: &[T], size: (usize, usize), to: (usize, usize)) -> Vec<T> {
+    let ((width, height), (to_width, to_height)) = (size, to);
+    let mut resampled = Vec::with_capacity(to_width * to_height);
+    for y in 0..to_height {
+        let row = y * height / to_height * width;
+        for x in 0..to_width {
+            resampled.push(mask[row + x * width / to_width]);
+        }
+    }
+    resampled
+}
+
+// the alpha channel given by an image's /SMask or /Mask, if it has one
+fn alpha(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+    size: (usize, usize),
+    samples: &[u16],
+    components: usize,
+) -> crate::Result<Option<Vec<u8>>> {
+    if let Some(smask) = dictionary.get_reference(PdfName::SMask) {
+        let mask = decode_xobject(document, smask, true)?;
+        let channels = mask.format.channels();
+        let values: Vec<u8> = mask.pixels.iter().step_by(channels).cloned().collect();
+        let mask_size = (mask.width as usize, mask.height as usize);
+        return Ok(Some(resample(&values, mask_size, size)));
+    }
+    match dictionary.get(&PdfName::Mask) {
+        Some(PdfObject::Reference(reference)) => {
+            let reference = *reference;
+            let mask_dictionary = match document.resolve(PdfObject::Reference(reference))? {
+                PdfObject::Dictionary(mask_dictionary) => mask_dictionary,
+                _ => return Ok(None),
+            };
+            let data = document.stream_data(reference)?;
+            let (width, height, painted) = stencil(document, &mask_dictionary, &data)?;
+            let values: Vec<u8> = painted.into_iter().map(|p| p as u8 * 255).collect();
+            Ok(Some(resample(&values, (width, height), size)))
+        }
+        // color key masking, ranges of samples which aren't painted
+        Some(PdfObject::Array(ranges)) if ranges.len() == 2 * components => {
+            let ranges: Vec<f64> = ranges.iter().filter_map(|range| range.as_f64()).collect();
+            if ranges.len() != 2 * components {
+                return Ok(None);
+            }
+            let values = samples
+                .chunks(components)
+                .map(|pixel| {
+                    let masked = pixel.iter().enumerate().all(|(i, &sample)| {
+                        let sample = sample as f64;
+                        sample >= ranges[2 * i] && sample <= ranges[2 * i + 1]
+                    });
+                    if masked {
+                        0
+                    } else {
+                        255
+                    }
+                })
+                .collect();
+            Ok(Some(values))
+        }
+        _ => Ok(None),
+    }
+}
+
+// decodes image data, the masks of a soft mask are ignored
+fn decode(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+    data: Vec<u8>,
+    color_space: Option<&ColorSpace>,
+    is_mask: bool,
+) -> crate::Result<DecodedImage> {
+    let image_mask = matches!(
+        entry(document, dictionary, PdfName::ImageMask)?,
+        Some(PdfObject::Boolean(true))
+    );
+    // stencil masks are decoded as black, transparent where they aren't painted
+    if image_mask {
+        let (width, height, painted) = stencil(document, dictionary, &data)?;
+        return Ok(DecodedImage {
+            width: width as u32,
+            height: height as u32,
+            format: PixelFormat::Rgba,
+            pixels: painted
+                .into_iter()
+                .flat_map(|painted| [0, 0, 0, painted as u8 * 255])
+                .collect(),
+        });
+    }
+    let (width, height) = dimensions(document, dictionary)?;
+    let bits = match number(entry(document, dictionary, PdfName::BitsPerComponent)?) {
+        Some(bits) if [1.0, 2.0, 4.0, 8.0, 16.0].contains(&bits) => bits as u32,
+        Some(_) => return Err(PdfError::InvalidPdf("invalid BitsPerComponent")),
+        None => return Err(PdfError::Unsupported("images without BitsPerComponent")),
+    };
+    let converter = match color_space {
+        Some(space) => Converter::new(document, space)?,
+        None => return Err(PdfError::InvalidPdf("ColorSpace missing from image")),
+    };
+    let components = converter.components();
+    let height = covered_rows(&data, width, height, components, bits)?;
+    let decode = decode_array(document, dictionary, converter.default_decode(bits))?;
+    let samples = unpack(&data, width, height, components, bits);
+    let alpha = match is_mask {
+        true => None,
+        false => alpha(document, dictionary, (width, height), &samples, components)?,
+    };
+    let max = ((1u32 << bits) - 1) as f64;
+    let format = match (&alpha, converter.is_gray()) {
+        (Some(_), _) => PixelFormat::Rgba,
+        (None, true) => PixelFormat::Gray,
+        (None, false) => PixelFormat::Rgb,
+    };
+    let mut pixels = Vec::with_capacity(width * height * format.channels());
+    let mut cache: HashMap<&[u16], [u8; 3]> = HashMap::new();
+    let mut values = vec![0.0; components];
+    for (i, pixel) in samples.chunks(components.max(1)).enumerate() {
+        let rgb = match cache.get(pixel) {
+            Some(rgb) => *rgb,
+            None => {
+                for (j, (value, &sample)) in values.iter_mut().zip(pixel.iter()).enumerate() {
+                    *value =
+                        decode[2 * j] + sample as f64 * (decode[2 * j + 1] - decode[2 * j]) / max;
+                }
+                let rgb = converter.to_rgb(&values).map(to_u8);
+                if converter.is_slow() {
+                    cache.insert(pixel, rgb);
+                }
+                rgb
+            }
+        };
+        match format {
+            PixelFormat::Gray => pixels.push(rgb[0]),
+            PixelFormat::Rgb => pixels.extend(rgb),
+            PixelFormat::Rgba => {
+                pixels.extend(rgb);
+                pixels.push(alpha.as_ref().map_or(255, |alpha| alpha[i]));
+            }
+        }
+    }
+    Ok(DecodedImage {
+        width: width as u32,
+        height: height as u32,
+        format,
+        pixels,
+    })
+}
+
+// decodes an image found by page_images to 8-bit pixels, applying its /Decode array, color
+// space, and /SMask, /Mask or color key mask
+//
+// Images with an alpha channel are decoded as RGBA, others as gray or RGB depending on their
+// color space. Image masks are decoded as black where they paint and transparent elsewhere.
+// The image data must use filters which decode_stream supports.
+pub fn decode_image(document: &mut PdfDocument, image: &PageImage) -> crate::Result<DecodedImage> {
+    match (&image.inline, image.reference) {
+        (Some(inline), _) => {
+            let data = decode_stream(inline.data.clone(), inline.dictionary.clone())?;
+            decode(
+                document,
+                &inline.dictionary,
+                data,
+                image.color_space.as_ref(),
+                false,
+            )
+        }
+        (None, Some(reference)) => {
+            let dictionary = match document.resolve(PdfObject::Reference(reference))? {
+                PdfObject::Dictionary(dictionary) => dictionary,
+                _ => return Err(PdfError::InvalidPdf("image stream expected")),
+            };
+            let data = document.stream_data(reference)?;
+            decode(
+                document,
+                &dictionary,
+                data,
+                image.color_space.as_ref(),
+                false,
+            )
+        }
+        (None, None) => Err(PdfError::InternalError("image without data")),
+    }
+}
+
+// decodes an image XObject, such as a soft mask, see decode_image
+pub fn decode_image_xobject(
+    document: &mut PdfDocument,
+    reference: Reference,
+) -> crate::Result<DecodedImage> {
+    decode_xobject(document, reference, false)
+}
+
+fn decode_xobject(
+    document: &mut PdfDocument,
+    reference: Reference,
+    is_mask: bool,
+) -> crate::Result<DecodedImage> {
+    let dictionary = match document.resolve(PdfObject::Reference(reference))? {
+        PdfObject::Dictionary(dictionary) => dictionary,
+        _ => return Err(PdfError::InvalidPdf("image stream expected")),
+    };
+    let color_space = dictionary
+        .get(&PdfName::ColorSpace)
+        .map(|space| ColorSpace::from_object(space.clone()));
+    let data = document.stream_data(reference)?;
+    decode(document, &dictionary, data, color_space.as_ref(), is_mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::page_images;
+    use crate::matrix::Matrix;
+    use crate::rect::Rect;
+    use crate::test_files;
+
+    fn integer(value: i64) -> PdfObject {
+        PdfObject::Number(PdfNumber::Integer(value))
+    }
+
+    fn array(objects: Vec<PdfObject>) -> PdfObject {
+        PdfObject::Array(Box::new(objects))
+    }
+
+    // decodes an inline image without filters
+    fn decode_inline(
+        size: (i64, i64),
+        bits: Option<i64>,
+        color_space: Option<PdfObject>,
+        entries: Vec<(PdfName, PdfObject)>,
+        data: &[u8],
+    ) -> DecodedImage {
+        try_decode_inline(size, bits, color_space, entries, data).unwrap()
+    }
+
+    fn try_decode_inline(
+        size: (i64, i64),
+        bits: Option<i64>,
+        color_space: Option<PdfObject>,
+        entries: Vec<(PdfName, PdfObject)>,
+        data: &[u8],
+    ) -> crate::Result<DecodedImage> {
+        let mut dictionary: Dictionary = Box::default();
+        dictionary.insert(PdfName::Width, integer(size.0));
+        dictionary.insert(PdfName::Height, integer(size.1));
+        if let Some(bits) = bits {
+            dictionary.insert(PdfName::BitsPerComponent, integer(bits));
+        }
+        dictionary.extend(entries);
+        let image = PageImage {
+            name: None,
+            reference: None,
+            width: size.0 as u32,
+            height: size.1 as u32,
+            bits_per_component: bits.map(|bits| bits as u32),
+            color_space: color_space.map(ColorSpace::from_object),
+            image_mask: false,
+            smask: None,
+            filters: vec![],
+            ctm: Matrix::identity(),
+            page_bbox: Rect::default(),
+            forms: vec![],
+            inline: Some(Box::new(InlineImage {
+                dictionary,
+                data: data.to_vec(),
+            })),
+        };
+        decode_image(&mut test_files::open("minimal.pdf"), &image)
+    }
+
+    #[test]
+    fn device_color_spaces() {
+        let gray = Some(PdfObject::Name(PdfName::DeviceGray));
+        // 1-bit samples inverted by /Decode, rows start on a byte boundary
+        let decode = vec![(PdfName::Decode, array(vec![integer(1), integer(0)]))];
+        let image = decode_inline((3, 2), Some(1), gray.clone(), decode, &[0b1010_0000, 0xff]);
+        assert_eq!(image.format, PixelFormat::Gray);
+        assert_eq!(image.pixels, vec![0, 255, 0, 0, 0, 0]);
+        let image = decode_inline((2, 1), Some(4), gray, vec![], &[0xf8]);
+        assert_eq!(image.pixels, vec![255, 136]);
+        let rgb = Some(PdfObject::Name(PdfName::DeviceRGB));
+        let image = decode_inline(
+            (1, 1),
+            Some(16),
+            rgb,
+            vec![],
+            &[0xff, 0xff, 0x80, 0x00, 0, 0],
+        );
+        assert_eq!(image.format, PixelFormat::Rgb);
+        assert_eq!(image.pixels, vec![255, 128, 0]);
+        let cmyk = Some(PdfObject::Name(PdfName::DeviceCMYK));
+        let image = decode_inline((2, 1), Some(8), cmyk, vec![], &[255, 0, 0, 0, 0, 0, 0, 255]);
+        assert_eq!(image.pixels, vec![0, 255, 255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn special_color_spaces() {
+        let lookup = PdfObject::String(vec![255, 0, 0, 0, 0, 255]);
+        let indexed = array(vec![
+            PdfObject::Name(PdfName::Indexed),
+            PdfObject::Name(PdfName::DeviceRGB),
+            integer(1),
+            lookup,
+        ]);
+        let image = decode_inline((3, 1), Some(2), Some(indexed), vec![], &[0b0001_1100]);
+        assert_eq!(image.pixels, vec![255, 0, 0, 0, 0, 255, 0, 0, 255]);
+
+        // a spot color which is red at full tint
+        let mut tint: Dictionary = Box::default();
+        tint.insert(PdfName::FunctionType, integer(2));
+        tint.insert(PdfName::Domain, array(vec![integer(0), integer(1)]));
+        tint.insert(PdfName::C0, array(vec![integer(1), integer(1), integer(1)]));
+        tint.insert(PdfName::C1, array(vec![integer(1), integer(0), integer(0)]));
+        tint.insert(PdfName::N, integer(1));
+        let separation = array(vec![
+            PdfObject::Name(PdfName::Separation),
+            PdfObject::Name(PdfName::Other(b"Spot".to_vec())),
+            PdfObject::Name(PdfName::DeviceRGB),
+            PdfObject::Dictionary(tint),
+        ]);
+        let image = decode_inline((2, 1), Some(8), Some(separation), vec![], &[0, 255]);
+        assert_eq!(image.pixels, vec![255, 255, 255, 255, 0, 0]);
+
+        let lab = array(vec![
+            PdfObject::Name(PdfName::Lab),
+            PdfObject::Dictionary(Box::default()),
+        ]);
+        let data = [255, 128, 128, 0, 128, 128];
+        let image = decode_inline((2, 1), Some(8), Some(lab), vec![], &data);
+        assert!(image.pixels[3..].iter().all(|&value| value < 5));
+        assert!(image.pixels[..3].iter().all(|&value| value > 250));
+    }
+
+    #[test]
+    fn masks() {
+        let gray = Some(PdfObject::Name(PdfName::DeviceGray));
+        let key = vec![(PdfName::Mask, array(vec![integer(0), integer(10)]))];
+        let image = decode_inline((3, 1), Some(8), gray, key, &[5, 11, 0]);
+        assert_eq!(image.format, PixelFormat::Rgba);
+        assert_eq!(image.pixels, vec![5, 5, 5, 0, 11, 11, 11, 255, 0, 0, 0, 0]);
+        let mask = vec![(PdfName::ImageMask, PdfObject::Boolean(true))];
+        let image = decode_inline((2, 1), None, None, mask, &[0b0100_0000]);
+        assert_eq!(image.pixels, vec![0, 0, 0, 255, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bogus_dimensions() {
+        let gray = Some(PdfObject::Name(PdfName::DeviceGray));
+        // the rows are limited to those the data covers
+        let image = decode_inline((2, 1 << 40), Some(8), gray.clone(), vec![], &[1, 2, 3]);
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, vec![1, 2, 3, 0]);
+        let size = (1 << 40, 1 << 40);
+        assert!(try_decode_inline(size, Some(8), gray.clone(), vec![], &[1, 2, 3]).is_err());
+        assert!(try_decode_inline((i64::MAX, 1), Some(16), gray, vec![], &[1, 2]).is_err());
+        let mask = vec![(PdfName::ImageMask, PdfObject::Boolean(true))];
+        assert!(try_decode_inline(size, None, None, mask, &[0]).is_err());
+    }
+
+    #[test]
+    fn self_masked_image() {
+        // the soft mask's own /SMask isn't applied, which would recurse forever
+        let mut document = test_files::document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+            "<< /Type /XObject /Subtype /Image /Width 2 /Height 1 /ColorSpace /DeviceGray \
+             /BitsPerComponent 8 /SMask 3 0 R /Length 2 >>\nstream\nAB\nendstream",
+        ]);
+        let image = decode_image_xobject(&mut document, Reference::new(3, 0)).unwrap();
+        assert_eq!(image.format, PixelFormat::Rgba);
+        assert_eq!(image.pixels, vec![65, 65, 65, 65, 66, 66, 66, 66]);
+    }
+
+    #[test]
+    fn tracemonkey_pdf_soft_mask() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let image = &page_images(&mut document, 1).unwrap()[0];
+        let decoded = decode_image(&mut document, image).unwrap();
+        assert_eq!((decoded.width, decoded.height), (118, 57));
+        assert_eq!(decoded.format, PixelFormat::Rgba);
+        assert_eq!(decoded.pixels.len(), 118 * 57 * 4);
+        let alpha: Vec<u8> = decoded.pixels.iter().skip(3).step_by(4).cloned().collect();
+        let (min, max) = (alpha.iter().min().unwrap(), alpha.iter().max().unwrap());
+        assert!(*min == 0 && *max > 128);
+        let mask = decode_image_xobject(&mut document, image.smask.unwrap()).unwrap();
+        assert_eq!(mask.format, PixelFormat::Gray);
+        assert_eq!(alpha, mask.pixels);
+    }
+}
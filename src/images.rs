@@ -0,0 +1,337 @@
+use crate::dictionary::Access;
+use crate::graphics_state::{ColorSpace, GraphicsState};
+use crate::interpreter::{Interpreter, Visitor};
+use crate::matrix::Matrix;
+use crate::page_contents::PageContents;
+use crate::pdf_document::PdfDocument;
+use crate::pdf_types::*;
+use crate::rect::Rect;
+use crate::resources::{self, Resources};
+
+// an image painted on a page, by Do or as an inline image
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageImage {
+    // the /XObject resource name, None for inline images
+    pub name: Option<PdfName>,
+    // the image XObject stream, None for inline images
+    pub reference: Option<Reference>,
+    pub width: u32,
+    pub height: u32,
+    // missing for JPXDecode images, which carry it in their data
+    pub bits_per_component: Option<u32>,
+    // missing for image masks and JPXDecode images
+    pub color_space: Option<ColorSpace>,
+    pub image_mask: bool,
+    // the soft mask image XObject stream
+    pub smask: Option<Reference>,
+    pub filters: Vec<PdfName>,
+    // maps the unit square of image space to default user space, the CTM when the image was
+    // painted, including the /Matrix of any form XObjects it was painted through
+    pub ctm: Matrix,
+    // the unit square in page space, see PdfDocument::page_space
+    pub page_bbox: Rect,
+    // the form XObjects it was painted through, outermost first
+    pub forms: Vec<PdfName>,
+    // the dictionary and data of an inline image
+    pub inline: Option<Box<InlineImage>>,
+}
+
+// where Do and inline images were used in a content stream, with the resource scope they were
+// used in
+enum Placement {
+    XObject(PdfName, Matrix, usize),
+    Inline(Box<InlineImage>, Matrix, usize),
+}
+
+struct PlacementCollector {
+    placements: Vec<Placement>,
+    // the resources of the page and of each form interpreted, in the order they were entered
+    scopes: Vec<Resources>,
+    // the forms being interpreted and the index of their resources in scopes, outermost first
+    forms: Vec<(PdfName, usize)>,
+    // the forms each placement was made through
+    paths: Vec<Vec<PdfName>>,
+}
+
+impl PlacementCollector {
+    fn new(resources: Resources) -> PlacementCollector {
+        PlacementCollector {
+            placements: vec![],
+            scopes: vec![resources],
+            forms: vec![],
+            paths: vec![],
+        }
+    }
+
+    // the resource scope of a placement being made, noting the forms it's made through
+    fn scope(&mut self) -> usize {
+        self.paths
+            .push(self.forms.iter().map(|(name, _)| name.clone()).collect());
+        self.forms.last().map_or(0, |(_, scope)| *scope)
+    }
+}
+
+impl Visitor for PlacementCollector {
+    fn xobject(&mut self, name: &PdfName, state: &GraphicsState) {
+        let scope = self.scope();
+        self.placements
+            .push(Placement::XObject(name.clone(), state.ctm, scope));
+    }
+
+    fn inline_image(&mut self, image: &InlineImage, state: &GraphicsState) {
+        let scope = self.scope();
+        self.placements
+            .push(Placement::Inline(Box::new(image.clone()), state.ctm, scope));
+    }
+
+    fn begin_form(
+        &mut self,
+        name: &PdfName,
+        _form: Reference,
+        _bbox: Option<Rect>,
+        resources: &Resources,
+        _state: &GraphicsState,
+    ) {
+        self.forms.push((name.clone(), self.scopes.len()));
+        self.scopes.push(resources.clone());
+    }
+
+    fn end_form(&mut self, _name: &PdfName, _state: &GraphicsState) {
+        self.forms.pop();
+    }
+}
+
+struct ImageWalker<'a> {
+    document: &'a mut PdfDocument,
+    page_space: Matrix,
+    images: Vec<PageImage>,
+}
+
+impl<'a> ImageWalker<'a> {
+    // finds the images painted by a content stream, and the form XObjects it paints
+    fn walk(&mut self, contents: PageContents, resources: Dictionary) -> crate::Result<()> {
+        let mut interpreter = Interpreter::new(self.document, resources);
+        let mut collector = PlacementCollector::new(interpreter.resources().clone());
+        interpreter.recurse_forms(true);
+        interpreter.run(contents, &mut collector)?;
+        let PlacementCollector {
+            placements,
+            scopes,
+            paths,
+            ..
+        } = collector;
+        // images which fail to load are skipped, like forms which fail to load
+        for (placement, forms) in placements.into_iter().zip(paths) {
+            match placement {
+                Placement::Inline(image, ctm, scope) => {
+                    let dictionary = &image.dictionary;
+                    if let Ok(mut page_image) =
+                        self.image(None, None, dictionary, &scopes[scope], ctm, forms)
+                    {
+                        page_image.inline = Some(image);
+                        self.images.push(page_image);
+                    }
+                }
+                Placement::XObject(name, ctm, scope) => {
+                    let _ = self.xobject(name, &scopes[scope], ctm, forms);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn xobject(
+        &mut self,
+        name: PdfName,
+        resources: &Resources,
+        ctm: Matrix,
+        forms: Vec<PdfName>,
+    ) -> crate::Result<()> {
+        // forms have been interpreted already
+        if let Some(xobject) = resources.xobject(self.document, &name)? {
+            if xobject.subtype() == Some(PdfName::Image) {
+                let image = self.image(
+                    Some(name),
+                    xobject.reference,
+                    &xobject.dictionary,
+                    resources,
+                    ctm,
+                    forms,
+                )?;
+                self.images.push(image);
+            }
+        }
+        Ok(())
+    }
+
+    fn image(
+        &mut self,
+        name: Option<PdfName>,
+        reference: Option<Reference>,
+        dictionary: &Dictionary,
+        resources: &Resources,
+        ctm: Matrix,
+        forms: Vec<PdfName>,
+    ) -> crate::Result<PageImage> {
+        let document = &mut *self.document;
+        let number = |object: Option<PdfObject>| object.and_then(|object| object.as_f64());
+        let width = number(entry(document, dictionary, PdfName::Width)?).unwrap_or(0.0);
+        let height = number(entry(document, dictionary, PdfName::Height)?).unwrap_or(0.0);
+        let bits_per_component = number(entry(document, dictionary, PdfName::BitsPerComponent)?);
+        let image_mask = matches!(
+            entry(document, dictionary, PdfName::ImageMask)?,
+            Some(PdfObject::Boolean(true))
+        );
+        let color_space = match entry(document, dictionary, PdfName::ColorSpace)? {
+            Some(space) if !image_mask => Some(color_space(document, resources, space)?),
+            _ => None,
+        };
+        let filters = match entry(document, dictionary, PdfName::Filter)? {
+            Some(PdfObject::Name(filter)) => vec![filter],
+            Some(PdfObject::Array(filters)) => filters
+                .iter()
+                .filter_map(|filter| filter.as_name())
+                .collect(),
+            _ => vec![],
+        };
+        Ok(PageImage {
+            name,
+            reference,
+            width: width as u32,
+            height: height as u32,
+            bits_per_component: bits_per_component.map(|bits| bits as u32),
+            color_space,
+            image_mask,
+            smask: dictionary.get_reference(PdfName::SMask),
+            filters,
+            ctm,
+            page_bbox: Rect::new(0.0, 0.0, 1.0, 1.0).transform(&ctm.multiply(&self.page_space)),
+            forms,
+            inline: None,
+        })
+    }
+}
+
+// a dictionary entry, with references resolved
+pub(crate) fn entry(
+    document: &mut PdfDocument,
+    dictionary: &Dictionary,
+    key: PdfName,
+) -> crate::Result<Option<PdfObject>> {
+    match dictionary.get(&key) {
+        Some(object) => Ok(Some(document.resolve(object.clone())?)),
+        None => Ok(None),
+    }
+}
+
+// an image's color space, which inline images can give as a /ColorSpace resource name or
+// abbreviate
+fn color_space(
+    document: &mut PdfDocument,
+    resources: &Resources,
+    space: PdfObject,
+) -> crate::Result<ColorSpace> {
+    if let Some(name) = space.as_name() {
+        if let Some(space) = resources.color_space(document, &name)? {
+            return Ok(space);
+        }
+    }
+    resources::color_space(document, space)
+}
+
+// the images painted on a page, in the order they're painted, including those in form
+// XObjects and inline images
+//
+// Forms are interpreted as described for Interpreter. Images whose dictionary or color space
+// fails to load are left out.
+pub fn page_images(document: &mut PdfDocument, pageno: u32) -> crate::Result<Vec<PageImage>> {
+    let contents = document.page_contents(pageno)?;
+    let resources = document.page_resources(pageno)?;
+    let page_space = document.page_space(pageno)?;
+    let mut walker = ImageWalker {
+        document,
+        page_space,
+        images: vec![],
+    };
+    walker.walk(contents, resources)?;
+    Ok(walker.images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files;
+
+    #[test]
+    fn tracemonkey_pdf_images() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        assert!(page_images(&mut document, 0).unwrap().is_empty());
+        // the images are all in form XObjects placed with Do
+        let images = page_images(&mut document, 1).unwrap();
+        assert_eq!(images.len(), 10);
+        let image = &images[0];
+        let im1 = PdfName::Other(b"Im1".to_vec());
+        assert_eq!(image.name, Some(im1.clone()));
+        assert_eq!(image.forms, vec![im1]);
+        assert_eq!(image.reference, Some(Reference::new(52, 0)));
+        assert_eq!((image.width, image.height), (118, 57));
+        assert_eq!(image.bits_per_component, Some(8));
+        assert_eq!(image.color_space.as_ref().unwrap().components(), 3);
+        assert!(!image.image_mask);
+        assert_eq!(image.smask, Some(Reference::new(72, 0)));
+        assert_eq!(image.filters, vec![PdfName::FlateDecode]);
+        assert_eq!(
+            image.ctm.transform(0.0, 0.0),
+            (image.page_bbox.x0, image.page_bbox.y0)
+        );
+        assert!(image.page_bbox.width() > 50.0 && image.page_bbox.y1 < 600.0);
+        assert_eq!(page_images(&mut document, 10).unwrap().len(), 78);
+    }
+
+    #[test]
+    fn inline_images() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let contents =
+            b"1 0 0 1 100 0 cm q 10 0 0 20 5 5 cm BI /W 2 /H 1 /CS /RGB /BPC 8 /F /AHx ID 00ff00ff0000> EI Q \
+            2 0 0 2 0 0 cm BI /W 8 /H 1 /IM true ID \xaa EI";
+        let mut walker = ImageWalker {
+            document: &mut document,
+            page_space: Matrix::identity(),
+            images: vec![],
+        };
+        walker
+            .walk(PageContents::new(contents.to_vec()), Dictionary::default())
+            .unwrap();
+        let images = walker.images;
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].name, None);
+        assert_eq!((images[0].width, images[0].height), (2, 1));
+        assert_eq!(images[0].color_space, Some(ColorSpace::DeviceRGB));
+        assert_eq!(images[0].filters, vec![PdfName::ASCIIHexDecode]);
+        assert_eq!(images[0].page_bbox, Rect::new(105.0, 5.0, 115.0, 25.0));
+        assert!(images[1].image_mask);
+        assert_eq!(images[1].bits_per_component, None);
+        assert_eq!(images[1].color_space, None);
+        assert_eq!(images[1].page_bbox, Rect::new(100.0, 0.0, 102.0, 2.0));
+    }
+
+    #[test]
+    fn broken_images() {
+        let mut document = test_files::document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 300 144] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R \
+                /Resources << /XObject << /Im1 9 0 R /Im2 5 0 R /Im3 6 0 R >> \
+                    /ColorSpace << /CS0 9 0 R >> >> >>",
+            "<< /Length 60 >> stream\n/Im1 Do /Im2 Do /Im3 Do BI /W 1 /H 1 /CS /CS0 /BPC 8 ID x EI\nendstream",
+            "<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /BitsPerComponent 8 \
+                /ColorSpace 9 0 R /Length 1 >> stream\nx\nendstream",
+            "<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /BitsPerComponent 8 \
+                /ColorSpace /DeviceGray /Length 1 >> stream\nx\nendstream",
+        ]);
+        // a missing image and images whose color space is missing are skipped
+        let images = page_images(&mut document, 0).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].name, Some(PdfName::Other(b"Im3".to_vec())));
+    }
+}
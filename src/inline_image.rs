@@ -0,0 +1,318 @@
+use std::io::{Read, SeekFrom};
+
+use crate::dictionary::Access;
+use crate::next_object::next_object;
+use crate::pdf_source::Source;
+use crate::pdf_types::*;
+use crate::PdfError;
+
+// number of bytes examined after a candidate EI to make sure it is followed by content operators
+const LOOKAHEAD: usize = 32;
+
+// reads an inline image, the source must be positioned just past the BI operator
+pub fn inline_image(source: &mut Box<dyn Source>) -> crate::Result<InlineImage> {
+    let mut dictionary: Dictionary = Box::default();
+    loop {
+        match next_object(source)? {
+            Some(PdfObject::Keyword(PdfKeyword::ID)) => break,
+            Some(PdfObject::Name(key)) => {
+                let key = expand_key(key);
+                let value = match next_object(source)? {
+                    Some(value) => expand_value(&key, value),
+                    None => return Err(PdfError::InvalidPdf("inline image value expected")),
+                };
+                dictionary.insert(key, value);
+            }
+            Some(PdfObject::Symbol(symbol)) => {
+                let value = match next_object(source)? {
+                    Some(value) => value,
+                    None => return Err(PdfError::InvalidPdf("inline image value expected")),
+                };
+                dictionary.insert(PdfName::Other(symbol), value);
+            }
+            Some(_) => return Err(PdfError::InvalidPdf("malformed inline image dictionary")),
+            None => return Err(PdfError::EndOfFile),
+        }
+    }
+    // ID is followed by a single white-space character
+    if source.getch()?.is_none() {
+        return Err(PdfError::EndOfFile);
+    }
+    let data = match data_length(&dictionary) {
+        Some(length) => match read_counted(source, length)? {
+            Some(data) => data,
+            None => read_to_end_marker(source)?,
+        },
+        None => read_to_end_marker(source)?,
+    };
+    Ok(InlineImage { dictionary, data })
+}
+
+fn expand_key(key: PdfName) -> PdfName {
+    match key {
+        PdfName::BPC => PdfName::BitsPerComponent,
+        PdfName::CS => PdfName::ColorSpace,
+        PdfName::D => PdfName::Decode,
+        PdfName::DP => PdfName::DecodeParms,
+        PdfName::F => PdfName::Filter,
+        PdfName::H => PdfName::Height,
+        PdfName::IM => PdfName::ImageMask,
+        PdfName::I => PdfName::Interpolate,
+        PdfName::L => PdfName::Length,
+        PdfName::W => PdfName::Width,
+        key => key,
+    }
+}
+
+fn expand_name(name: PdfName) -> PdfName {
+    match name {
+        PdfName::G => PdfName::DeviceGray,
+        PdfName::RGB => PdfName::DeviceRGB,
+        PdfName::CMYK => PdfName::DeviceCMYK,
+        PdfName::I => PdfName::Indexed,
+        PdfName::AHx => PdfName::ASCIIHexDecode,
+        PdfName::A85 => PdfName::ASCII85Decode,
+        PdfName::LZW => PdfName::LZWDecode,
+        PdfName::Fl => PdfName::FlateDecode,
+        PdfName::RL => PdfName::RunLengthDecode,
+        PdfName::CCF => PdfName::CCITTFaxDecode,
+        PdfName::DCT => PdfName::DCTDecode,
+        name => name,
+    }
+}
+
+fn expand_value(key: &PdfName, value: PdfObject) -> PdfObject {
+    match (key, value) {
+        (PdfName::ColorSpace, PdfObject::Name(name)) | (PdfName::Filter, PdfObject::Name(name)) => {
+            PdfObject::Name(expand_name(name))
+        }
+        (PdfName::ColorSpace, PdfObject::Array(array))
+        | (PdfName::Filter, PdfObject::Array(array)) => PdfObject::Array(Box::new(
+            array
+                .into_iter()
+                .map(|item| match item {
+                    PdfObject::Name(name) => PdfObject::Name(expand_name(name)),
+                    item => item,
+                })
+                .collect(),
+        )),
+        (_, value) => value,
+    }
+}
+
+fn components(color_space: &PdfObject) -> Option<usize> {
+    match color_space {
+        PdfObject::Name(PdfName::DeviceGray) | PdfObject::Name(PdfName::Indexed) => Some(1),
+        PdfObject::Name(PdfName::DeviceRGB) => Some(3),
+        PdfObject::Name(PdfName::DeviceCMYK) => Some(4),
+        PdfObject::Array(array) => match array.first() {
+            Some(PdfObject::Name(PdfName::Indexed)) => Some(1),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// the number of bytes of image data, if it can be determined from the dictionary
+fn data_length(dictionary: &Dictionary) -> Option<usize> {
+    if let Some(length) = dictionary.get_u32(PdfName::Length) {
+        return Some(length as usize);
+    }
+    if dictionary.contains_key(&PdfName::Filter) {
+        return None;
+    }
+    let width = dictionary.get_u32(PdfName::Width)? as usize;
+    let height = dictionary.get_u32(PdfName::Height)? as usize;
+    let (components, bpc) = match dictionary.get(&PdfName::ImageMask) {
+        Some(PdfObject::Boolean(true)) => (1, 1),
+        _ => (
+            components(dictionary.get(&PdfName::ColorSpace)?)?,
+            dictionary.get_u32(PdfName::BitsPerComponent)? as usize,
+        ),
+    };
+    let row = width
+        .checked_mul(components)?
+        .checked_mul(bpc)?
+        .checked_add(7)?
+        / 8;
+    row.checked_mul(height)
+}
+
+// reads exactly length bytes, returns None (leaving the source untouched) if they aren't followed by EI
+//
+// When the length is wrong what follows is usually more image data, which needn't be valid
+// syntax, so a lexer error counts as not being followed by EI.
+fn read_counted(source: &mut Box<dyn Source>, length: usize) -> crate::Result<Option<Vec<u8>>> {
+    let position = source.seek(SeekFrom::Current(0))?;
+    let mut data = vec![];
+    let nread = source.by_ref().take(length as u64).read_to_end(&mut data)?;
+    if nread == length
+        && matches!(
+            next_object(source),
+            Ok(Some(PdfObject::Keyword(PdfKeyword::EI)))
+        )
+    {
+        return Ok(Some(data));
+    }
+    source.seek(SeekFrom::Start(position))?;
+    Ok(None)
+}
+
+// reads up to a white-space delimited EI which is followed by what looks like content stream syntax
+fn read_to_end_marker(source: &mut Box<dyn Source>) -> crate::Result<Vec<u8>> {
+    let mut data = vec![];
+    loop {
+        match source.getch()? {
+            Some(ch) => data.push(ch as u8),
+            None => return Err(PdfError::EndOfFile),
+        }
+        let n = data.len();
+        if n >= 3
+            && is_whitespace(data[n - 3])
+            && &data[n - 2..] == b"EI"
+            && followed_by_content(source)?
+        {
+            data.truncate(n - 3);
+            return Ok(data);
+        }
+    }
+}
+
+fn followed_by_content(source: &mut Box<dyn Source>) -> crate::Result<bool> {
+    let position = source.seek(SeekFrom::Current(0))?;
+    let mut lookahead = vec![];
+    let _ = source
+        .by_ref()
+        .take(LOOKAHEAD as u64)
+        .read_to_end(&mut lookahead)?;
+    source.seek(SeekFrom::Start(position))?;
+    Ok(match lookahead.first() {
+        None => true,
+        Some(&ch) if is_whitespace(ch) => lookahead
+            .iter()
+            .all(|&ch| is_whitespace(ch) || (0x20..0x7f).contains(&ch)),
+        Some(_) => false,
+    })
+}
+
+fn is_whitespace(ch: u8) -> bool {
+    matches!(ch, b' ' | b'\t' | b'\n' | b'\r' | b'\x0c' | b'\0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_contents::PageContents;
+
+    fn image(contents: &[u8]) -> InlineImage {
+        let mut pc = PageContents::new(contents.to_vec());
+        loop {
+            match pc.next_object().unwrap() {
+                Some(PdfObject::InlineImage(image)) => return *image,
+                Some(_) => {}
+                None => panic!("no inline image found"),
+            }
+        }
+    }
+
+    #[test]
+    fn abbreviations() {
+        let image = image(b"q BI /W 2 /H 2 /BPC 8 /CS /G /I true ID \x01\x02\x03\x04 EI Q");
+        assert_eq!(image.dictionary.get_u32(PdfName::Width), Some(2));
+        assert_eq!(image.dictionary.get_u32(PdfName::Height), Some(2));
+        assert_eq!(image.dictionary.get_u32(PdfName::BitsPerComponent), Some(8));
+        assert_eq!(
+            image.dictionary.get_name(PdfName::ColorSpace),
+            Some(PdfName::DeviceGray)
+        );
+        assert_eq!(
+            image.dictionary.get(&PdfName::Interpolate),
+            Some(&PdfObject::Boolean(true))
+        );
+        assert_eq!(image.data, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn counted_data_containing_ei() {
+        let mut pc = PageContents::new(
+            b"BI /W 4 /H 1 /BPC 8 /CS /RGB ID  EI EI\xff\xfe\xfd\xfc\xfb\xfa EI\nQ".to_vec(),
+        );
+        match pc.next_object().unwrap() {
+            Some(PdfObject::InlineImage(image)) => {
+                assert_eq!(
+                    image.dictionary.get_name(PdfName::ColorSpace),
+                    Some(PdfName::DeviceRGB)
+                );
+                assert_eq!(image.data, b" EI EI\xff\xfe\xfd\xfc\xfb\xfa".to_vec());
+            }
+            obj => panic!("unexpected object: {:?}", obj),
+        }
+        assert_eq!(
+            pc.next_object().unwrap(),
+            Some(PdfObject::Keyword(PdfKeyword::Q))
+        );
+        assert_eq!(pc.next_object().unwrap(), None);
+    }
+
+    #[test]
+    fn wrong_counted_length() {
+        // two bytes are expected, the rest can't be lexed as PDF syntax
+        for data in [&b"ab)c"[..], b"ab>cd", b"ab(cd"] {
+            let contents = [&b"BI /W 2 /H 1 /BPC 8 /CS /G ID "[..], data, b" EI Q"].concat();
+            let mut pc = PageContents::new(contents);
+            match pc.next_object().unwrap() {
+                Some(PdfObject::InlineImage(image)) => assert_eq!(image.data, data.to_vec()),
+                obj => panic!("unexpected object: {:?}", obj),
+            }
+            assert_eq!(
+                pc.next_object().unwrap(),
+                Some(PdfObject::Keyword(PdfKeyword::Q))
+            );
+        }
+    }
+
+    #[test]
+    fn filtered_data() {
+        let mut pc = PageContents::new(
+            b"BI /W 8 /H 8 /IM true /F [/AHx /Fl] ID 78 EI \x9c\x01 EI Q".to_vec(),
+        );
+        match pc.next_object().unwrap() {
+            Some(PdfObject::InlineImage(image)) => {
+                assert_eq!(
+                    image.dictionary.get_array(PdfName::Filter),
+                    Some(Box::new(vec![
+                        PdfObject::Name(PdfName::ASCIIHexDecode),
+                        PdfObject::Name(PdfName::FlateDecode)
+                    ]))
+                );
+                assert_eq!(image.data, b"78 EI \x9c\x01".to_vec());
+            }
+            obj => panic!("unexpected object: {:?}", obj),
+        }
+        assert_eq!(
+            pc.next_object().unwrap(),
+            Some(PdfObject::Keyword(PdfKeyword::Q))
+        );
+    }
+
+    #[test]
+    fn indexed_color_space() {
+        let image = image(b"BI /W 3 /H 1 /BPC 4 /CS [/I /RGB 1 <ff000000ff00>] ID \x01\x20 EI");
+        assert_eq!(
+            image.dictionary.get_array(PdfName::ColorSpace),
+            Some(Box::new(vec![
+                PdfObject::Name(PdfName::Indexed),
+                PdfObject::Name(PdfName::DeviceRGB),
+                PdfObject::Number(PdfNumber::Integer(1)),
+                PdfObject::String(vec![0xff, 0, 0, 0, 0xff, 0]),
+            ]))
+        );
+        assert_eq!(image.data, vec![0x01, 0x20]);
+    }
+
+    #[test]
+    fn unterminated() {
+        let mut pc = PageContents::new(b"BI /W 1 /H 1 /F /Fl ID xyzzy".to_vec());
+        assert!(pc.next_object().is_err());
+    }
+}
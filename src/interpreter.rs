@@ -0,0 +1,845 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::dictionary::Access;
+use crate::font::Font;
+use crate::graphics_state::{Color, ColorSpace, GraphicsState};
+use crate::matrix::Matrix;
+use crate::operations::Operation;
+use crate::page_contents::PageContents;
+use crate::pdf_document::PdfDocument;
+use crate::pdf_types::*;
+use crate::rect::Rect;
+use crate::resources::Resources;
+use crate::PdfError;
+
+// how deeply form XObjects can be nested inside each other
+const MAX_FORM_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillRule {
+    NonZeroWinding,
+    EvenOdd,
+}
+
+// path construction in user space coordinates, v and y curves are expanded to c
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    MoveTo(f64, f64),
+    LineTo(f64, f64),
+    CurveTo(f64, f64, f64, f64, f64, f64),
+    Rectangle(f64, f64, f64, f64),
+    ClosePath,
+}
+
+// how a path is painted, n paints nothing and only clips, if requested by W or W*
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Paint {
+    pub stroke: bool,
+    pub fill: Option<FillRule>,
+    pub clip: Option<FillRule>,
+}
+
+// callbacks made by the interpreter, all of them default to doing nothing
+//
+// The state passed to each callback is the one in effect for the operation. Text strings are
+// passed undecoded, show_text is called before the text matrix is advanced past the string.
+pub trait Visitor {
+    // every operation, after it has been applied to the graphics state
+    fn operation(&mut self, _operation: &Operation, _state: &GraphicsState) {}
+    fn begin_text(&mut self, _state: &GraphicsState) {}
+    fn end_text(&mut self, _state: &GraphicsState) {}
+    // a string shown by Tj, ', " or TJ, the font can be used to decode it
+    fn show_text(&mut self, _text: &PdfString, _state: &GraphicsState) {}
+    // a TJ adjustment in thousandths of a unit of text space, before it moves the text matrix
+    fn adjust_text(&mut self, _adjustment: f64, _state: &GraphicsState) {}
+    fn paint_path(&mut self, _path: &[PathSegment], _paint: Paint, _state: &GraphicsState) {}
+    // an external object painted by Do, forms included
+    fn xobject(&mut self, _name: &PdfName, _state: &GraphicsState) {}
+    // the start of a form XObject's content when forms are interpreted, the state's CTM
+    // includes the form's /Matrix and resources are the ones its content uses, the bounding
+    // box is the form's /BBox in form space, None if it's missing or invalid
+    fn begin_form(
+        &mut self,
+        _name: &PdfName,
+        _form: Reference,
+        _bbox: Option<Rect>,
+        _resources: &Resources,
+        _state: &GraphicsState,
+    ) {
+    }
+    fn end_form(&mut self, _name: &PdfName, _state: &GraphicsState) {}
+    fn inline_image(&mut self, _image: &InlineImage, _state: &GraphicsState) {}
+}
+
+// a form XObject painted by Do, loaded before its content is interpreted
+struct Form {
+    reference: Reference,
+    matrix: Option<Matrix>,
+    bbox: Option<Rect>,
+    resources: Resources,
+    contents: PageContents,
+}
+
+// executes content stream operations, tracking the graphics state
+//
+// Operations with operands of the wrong type, or naming resources which don't exist, are
+// skipped. Operations with the wrong number of operands are skipped too, any other error
+// in the content stream stops the interpreter.
+//
+// Form XObjects are only interpreted if recurse_forms is set. Forms which paint themselves,
+// directly or through other forms, are only interpreted once on each path, and forms nested
+// more than 16 deep are skipped. Forms which fail to load are skipped too, and an error in a
+// form's content only stops that form.
+pub struct Interpreter<'a> {
+    document: &'a mut PdfDocument,
+    resources: Resources,
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+    path: Vec<PathSegment>,
+    current_point: Option<(f64, f64)>,
+    clip: Option<FillRule>,
+    // fonts loaded so far, None for fonts which aren't supported
+    fonts: HashMap<PdfName, Option<Rc<Font>>>,
+    recurse_forms: bool,
+    // the form XObjects being interpreted, outermost first
+    forms: Vec<Reference>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(document: &'a mut PdfDocument, resources: Dictionary) -> Interpreter<'a> {
+        Interpreter {
+            document,
+            resources: Resources::new(resources),
+            state: GraphicsState::default(),
+            stack: vec![],
+            path: vec![],
+            current_point: None,
+            clip: None,
+            fonts: HashMap::new(),
+            recurse_forms: false,
+            forms: vec![],
+        }
+    }
+
+    pub fn for_page(document: &'a mut PdfDocument, pageno: u32) -> crate::Result<Interpreter<'a>> {
+        let resources = document.page_resources(pageno)?;
+        Ok(Interpreter::new(document, resources))
+    }
+
+    // whether Do interprets the content of form XObjects
+    pub fn recurse_forms(&mut self, recurse: bool) {
+        self.recurse_forms = recurse;
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub fn state(&self) -> &GraphicsState {
+        &self.state
+    }
+
+    pub fn run(&mut self, contents: PageContents, visitor: &mut dyn Visitor) -> crate::Result<()> {
+        for operation in contents.operations() {
+            match operation {
+                Ok(operation) => {
+                    self.execute(&operation, visitor)?;
+                    visitor.operation(&operation, &self.state);
+                }
+                Err(PdfError::InvalidOperandCount { .. }) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, operation: &Operation, visitor: &mut dyn Visitor) -> crate::Result<()> {
+        use PdfKeyword::*;
+        let operands = &operation.operands[..];
+        let numbers: Option<Vec<f64>> = operands.iter().map(|operand| operand.as_f64()).collect();
+        let state = &mut self.state;
+        match (&operation.operator, numbers.as_deref()) {
+            // special graphics state
+            (q, _) => self.stack.push(state.clone()),
+            (Q, _) => {
+                if let Some(saved) = self.stack.pop() {
+                    self.state = saved;
+                }
+            }
+            (cm, _) => {
+                if let Some(matrix) = Matrix::from_objects(operands) {
+                    state.ctm = matrix.multiply(&state.ctm);
+                }
+            }
+
+            // general graphics state
+            (w, Some(&[width])) => state.line_width = width,
+            (J, Some(&[cap])) => state.line_cap = cap as u32,
+            (j, Some(&[join])) => state.line_join = join as u32,
+            (M, Some(&[limit])) => state.miter_limit = limit,
+            (d, _) => state.set_dash(&operands[0], &operands[1]),
+            (ri, _) => {
+                if let PdfObject::Name(intent) = &operands[0] {
+                    state.rendering_intent = intent.clone();
+                }
+            }
+            (i, Some(&[flatness])) => state.flatness = flatness,
+            (gs, _) => {
+                if let Some(name) = operands[0].as_name() {
+                    if let Some(parameters) = self.resources.ext_gstate(self.document, &name)? {
+                        self.set_parameters(&parameters)?;
+                    }
+                }
+            }
+
+            // color
+            (CS, _) | (cs, _) => {
+                let space = match operands[0].as_name() {
+                    Some(name) => self.resources.color_space(self.document, &name)?,
+                    None => None,
+                };
+                if let Some(space) = space {
+                    let color = Color::new(space);
+                    match operation.operator {
+                        CS => self.state.stroke_color = color,
+                        _ => self.state.fill_color = color,
+                    }
+                }
+            }
+            (SC, _) | (SCN, _) => set_color(&mut state.stroke_color, operands),
+            (sc, _) | (scn, _) => set_color(&mut state.fill_color, operands),
+            (G, Some(components)) => state.stroke_color = device_color(components),
+            (g, Some(components)) => state.fill_color = device_color(components),
+            (RG, Some(components)) => state.stroke_color = device_color(components),
+            (rg, Some(components)) => state.fill_color = device_color(components),
+            (K, Some(components)) => state.stroke_color = device_color(components),
+            (k, Some(components)) => state.fill_color = device_color(components),
+
+            // text state
+            (Tc, Some(&[spacing])) => state.text.char_spacing = spacing,
+            (Tw, Some(&[spacing])) => state.text.word_spacing = spacing,
+            (Tz, Some(&[scale])) => state.text.horizontal_scaling = scale / 100.0,
+            (TL, Some(&[leading])) => state.text.leading = leading,
+            (Tf, _) => {
+                if let (Some(name), Some(size)) = (operands[0].as_name(), operands[1].as_f64()) {
+                    let font = self.font(name.clone())?;
+                    let text = &mut self.state.text;
+                    text.font_name = Some(name);
+                    text.font = font;
+                    text.font_size = size;
+                }
+            }
+            (Tr, Some(&[mode])) => state.text.render_mode = mode as u32,
+            (Ts, Some(&[rise])) => state.text.rise = rise,
+
+            // text objects and positioning
+            (BT, _) => {
+                state.text.matrix = Matrix::identity();
+                state.text.line_matrix = Matrix::identity();
+                visitor.begin_text(state);
+            }
+            (ET, _) => visitor.end_text(state),
+            (Td, Some(&[tx, ty])) => state.text.next_line(tx, ty),
+            (TD, Some(&[tx, ty])) => {
+                state.text.leading = -ty;
+                state.text.next_line(tx, ty);
+            }
+            (Tm, _) => {
+                if let Some(matrix) = Matrix::from_objects(operands) {
+                    state.text.matrix = matrix;
+                    state.text.line_matrix = matrix;
+                }
+            }
+            (Tstar, _) => state.text.next_line(0.0, -state.text.leading),
+
+            // text showing
+            (Tj, _) => {
+                if let PdfObject::String(text) = &operands[0] {
+                    visitor.show_text(text, state);
+                    state.text.advance(text);
+                }
+            }
+            (apos, _) => {
+                if let PdfObject::String(text) = &operands[0] {
+                    state.text.next_line(0.0, -state.text.leading);
+                    visitor.show_text(text, state);
+                    state.text.advance(text);
+                }
+            }
+            (quote, _) => {
+                if let (Some(aw), Some(ac), PdfObject::String(text)) =
+                    (operands[0].as_f64(), operands[1].as_f64(), &operands[2])
+                {
+                    state.text.word_spacing = aw;
+                    state.text.char_spacing = ac;
+                    state.text.next_line(0.0, -state.text.leading);
+                    visitor.show_text(text, state);
+                    state.text.advance(text);
+                }
+            }
+            (TJ, _) => {
+                if let PdfObject::Array(array) = &operands[0] {
+                    for item in array.iter() {
+                        match item {
+                            PdfObject::String(text) => {
+                                visitor.show_text(text, state);
+                                state.text.advance(text);
+                            }
+                            PdfObject::Number(adjustment) => {
+                                let adjustment = adjustment.as_f64();
+                                visitor.adjust_text(adjustment, state);
+                                state.text.adjust(adjustment);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            // path construction
+            (m, Some(&[x0, y0])) => {
+                self.path.push(PathSegment::MoveTo(x0, y0));
+                self.current_point = Some((x0, y0));
+            }
+            (l, Some(&[x0, y0])) => {
+                self.path.push(PathSegment::LineTo(x0, y0));
+                self.current_point = Some((x0, y0));
+            }
+            (c, Some(&[x1, y1, x2, y2, x3, y3])) => {
+                self.path.push(PathSegment::CurveTo(x1, y1, x2, y2, x3, y3));
+                self.current_point = Some((x3, y3));
+            }
+            (v, Some(&[x2, y2, x3, y3])) => {
+                if let Some((x1, y1)) = self.current_point {
+                    self.path.push(PathSegment::CurveTo(x1, y1, x2, y2, x3, y3));
+                    self.current_point = Some((x3, y3));
+                }
+            }
+            (y, Some(&[x1, y1, x3, y3])) => {
+                self.path.push(PathSegment::CurveTo(x1, y1, x3, y3, x3, y3));
+                self.current_point = Some((x3, y3));
+            }
+            (h, _) => {
+                self.path.push(PathSegment::ClosePath);
+            }
+            (re, Some(&[x0, y0, width, height])) => {
+                self.path
+                    .push(PathSegment::Rectangle(x0, y0, width, height));
+                self.current_point = Some((x0, y0));
+            }
+
+            // clipping and path painting
+            (W, _) => self.clip = Some(FillRule::NonZeroWinding),
+            (Wstar, _) => self.clip = Some(FillRule::EvenOdd),
+            (S, _) => self.paint(visitor, false, true, None),
+            (s, _) => self.paint(visitor, true, true, None),
+            (f, _) | (F, _) => self.paint(visitor, false, false, Some(FillRule::NonZeroWinding)),
+            (fstar, _) => self.paint(visitor, false, false, Some(FillRule::EvenOdd)),
+            (B, _) => self.paint(visitor, false, true, Some(FillRule::NonZeroWinding)),
+            (Bstar, _) => self.paint(visitor, false, true, Some(FillRule::EvenOdd)),
+            (b, _) => self.paint(visitor, true, true, Some(FillRule::NonZeroWinding)),
+            (bstar, _) => self.paint(visitor, true, true, Some(FillRule::EvenOdd)),
+            (n, _) => self.paint(visitor, false, false, None),
+
+            // external objects and inline images
+            (Do, _) => {
+                if let Some(name) = operands[0].as_name() {
+                    visitor.xobject(&name, state);
+                    if self.recurse_forms {
+                        self.form(name, visitor);
+                    }
+                }
+            }
+            (BI, _) => {
+                if let PdfObject::InlineImage(image) = &operands[0] {
+                    visitor.inline_image(image, state);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn paint(
+        &mut self,
+        visitor: &mut dyn Visitor,
+        close: bool,
+        stroke: bool,
+        fill: Option<FillRule>,
+    ) {
+        if close {
+            self.path.push(PathSegment::ClosePath);
+        }
+        let paint = Paint {
+            stroke,
+            fill,
+            clip: self.clip.take(),
+        };
+        visitor.paint_path(&self.path, paint, &self.state);
+        self.path.clear();
+        self.current_point = None;
+    }
+
+    // interprets a form XObject's content with its own resources and matrix, leaving the
+    // graphics state as it was
+    fn form(&mut self, name: PdfName, visitor: &mut dyn Visitor) {
+        // forms which fail to load are skipped, like those which don't exist
+        let form = match self.load_form(&name) {
+            Ok(Some(form)) => form,
+            _ => return,
+        };
+        let saved = self.state.clone();
+        self.state.ctm = form.matrix.unwrap_or_default().multiply(&self.state.ctm);
+        let resources = std::mem::replace(&mut self.resources, form.resources);
+        // font names are only meaningful within the form's resources, and an unbalanced Q
+        // in the form can't restore a state saved outside it
+        let fonts = std::mem::take(&mut self.fonts);
+        let stack = std::mem::take(&mut self.stack);
+        self.forms.push(form.reference);
+        visitor.begin_form(
+            &name,
+            form.reference,
+            form.bbox,
+            &self.resources,
+            &self.state,
+        );
+        // an error in the form's content only stops the form
+        let _ = self.run(form.contents, visitor);
+        visitor.end_form(&name, &self.state);
+        self.forms.pop();
+        self.stack = stack;
+        self.fonts = fonts;
+        self.resources = resources;
+        self.state = saved;
+    }
+
+    // the form XObject painted by Do, None if it isn't a form or is already being interpreted
+    fn load_form(&mut self, name: &PdfName) -> crate::Result<Option<Form>> {
+        let (reference, dictionary) = match self.resources.xobject(self.document, name)? {
+            Some(xobject) if xobject.subtype() == Some(PdfName::Form) => match xobject.reference {
+                Some(reference) => (reference, xobject.dictionary),
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        if self.forms.len() >= MAX_FORM_DEPTH || self.forms.contains(&reference) {
+            return Ok(None);
+        }
+        let matrix = match dictionary.get(&PdfName::Matrix) {
+            Some(matrix) => match self.document.resolve(matrix.clone())? {
+                PdfObject::Array(array) => Matrix::from_objects(&array),
+                _ => None,
+            },
+            None => None,
+        };
+        // forms without resources use those of the content which painted them
+        let resources = match dictionary.get(&PdfName::Resources) {
+            Some(resources) => match self.document.resolve(resources.clone())? {
+                PdfObject::Dictionary(resources) => Resources::new(resources),
+                _ => self.resources.clone(),
+            },
+            None => self.resources.clone(),
+        };
+        let bbox = match dictionary.get(&PdfName::BBox) {
+            Some(bbox) => match self.document.resolve(bbox.clone())? {
+                PdfObject::Array(array) => Rect::from_objects(&array),
+                _ => None,
+            },
+            None => None,
+        };
+        let contents = self.document.stream_contents(reference)?;
+        Ok(Some(Form {
+            reference,
+            matrix,
+            bbox,
+            resources,
+            contents,
+        }))
+    }
+
+    fn font(&mut self, name: PdfName) -> crate::Result<Option<Rc<Font>>> {
+        if let Some(font) = self.fonts.get(&name) {
+            return Ok(font.clone());
+        }
+        // text in a font which fails to load is still positioned, with zero widths
+        let font = self
+            .resources
+            .font(self.document, &name)
+            .ok()
+            .flatten()
+            .map(Rc::new);
+        self.fonts.insert(name, font.clone());
+        Ok(font)
+    }
+
+    // applies the entries of a graphics state parameter dictionary
+    fn set_parameters(&mut self, parameters: &Dictionary) -> crate::Result<()> {
+        for (key, value) in parameters.iter() {
+            let number = value.as_f64();
+            let state = &mut self.state;
+            match (key, number) {
+                (PdfName::LW, Some(width)) => state.line_width = width,
+                (PdfName::LC, Some(cap)) => state.line_cap = cap as u32,
+                (PdfName::LJ, Some(join)) => state.line_join = join as u32,
+                (PdfName::ML, Some(limit)) => state.miter_limit = limit,
+                (PdfName::FL, Some(flatness)) => state.flatness = flatness,
+                (PdfName::CA, Some(alpha)) => state.stroke_alpha = alpha,
+                (PdfName::ca, Some(alpha)) => state.fill_alpha = alpha,
+                (PdfName::RI, _) => {
+                    if let Some(intent) = parameters.get_name(PdfName::RI) {
+                        state.rendering_intent = intent;
+                    }
+                }
+                (PdfName::D, _) => {
+                    if let PdfObject::Array(dash) = value {
+                        if dash.len() == 2 {
+                            state.set_dash(&dash[0], &dash[1]);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// the color set by G, g, RG, rg, K or k, the color space follows from the number of operands
+fn device_color(components: &[f64]) -> Color {
+    let space = match components.len() {
+        1 => ColorSpace::DeviceGray,
+        3 => ColorSpace::DeviceRGB,
+        _ => ColorSpace::DeviceCMYK,
+    };
+    Color {
+        space,
+        components: components.to_vec(),
+        pattern: None,
+    }
+}
+
+// SC, SCN, sc and scn operands: color components, followed by a pattern name for scn and SCN
+fn set_color(color: &mut Color, operands: &[PdfObject]) {
+    let (pattern, operands) = match operands.split_last() {
+        Some((last, rest)) => match last.as_name() {
+            Some(name) => (Some(name), rest),
+            None => (None, operands),
+        },
+        None => return,
+    };
+    let components: Option<Vec<f64>> = operands.iter().map(|operand| operand.as_f64()).collect();
+    if let Some(components) = components {
+        if pattern.is_some() || !components.is_empty() {
+            color.components = components;
+            color.pattern = pattern;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files;
+
+    #[derive(Default)]
+    struct Recorder {
+        texts: Vec<(PdfString, Matrix, f64)>,
+        paths: Vec<(Vec<PathSegment>, Paint, Color)>,
+        xobjects: Vec<(PdfName, Matrix)>,
+        forms: Vec<(PdfName, Reference, Option<Rect>, Matrix)>,
+        operations: usize,
+    }
+
+    impl Visitor for Recorder {
+        fn operation(&mut self, _operation: &Operation, _state: &GraphicsState) {
+            self.operations += 1;
+        }
+
+        fn show_text(&mut self, text: &PdfString, state: &GraphicsState) {
+            self.texts
+                .push((text.clone(), state.text.matrix, state.text.font_size));
+        }
+
+        fn paint_path(&mut self, path: &[PathSegment], paint: Paint, state: &GraphicsState) {
+            self.paths
+                .push((path.to_vec(), paint, state.fill_color.clone()));
+        }
+
+        fn xobject(&mut self, name: &PdfName, state: &GraphicsState) {
+            self.xobjects.push((name.clone(), state.ctm));
+        }
+
+        fn begin_form(
+            &mut self,
+            name: &PdfName,
+            form: Reference,
+            bbox: Option<Rect>,
+            _resources: &Resources,
+            state: &GraphicsState,
+        ) {
+            self.forms.push((name.clone(), form, bbox, state.ctm));
+        }
+    }
+
+    fn interpret(contents: &[u8]) -> (Recorder, GraphicsState) {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let mut interpreter = Interpreter::new(&mut document, Box::default());
+        let mut recorder = Recorder::default();
+        interpreter
+            .run(PageContents::new(contents.to_vec()), &mut recorder)
+            .unwrap();
+        let state = interpreter.state().clone();
+        (recorder, state)
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let (recorder, state) =
+            interpret(b"2 w 1 0 0 1 10 20 cm q 3 w 2 0 0 2 0 0 cm /X1 Do Q /X2 Do 4 J");
+        assert_eq!(
+            recorder.xobjects,
+            vec![
+                (
+                    PdfName::Other(b"X1".to_vec()),
+                    Matrix::new(2.0, 0.0, 0.0, 2.0, 10.0, 20.0)
+                ),
+                (
+                    PdfName::Other(b"X2".to_vec()),
+                    Matrix::translate(10.0, 20.0)
+                ),
+            ]
+        );
+        assert_eq!(state.line_width, 2.0);
+        assert_eq!(state.line_cap, 4);
+        assert_eq!(recorder.operations, 9);
+    }
+
+    #[test]
+    fn colors() {
+        let (recorder, state) = interpret(
+            b"0.5 g 0 0 10 10 re f 1 0 0 rg 0 0 m 10 10 l h S /DeviceCMYK CS 0.1 0.2 0.3 0.4 SC \
+              /Pattern cs /P1 scn",
+        );
+        assert_eq!(recorder.paths[0].2, device_color(&[0.5]));
+        assert_eq!(recorder.paths[1].2, device_color(&[1.0, 0.0, 0.0]));
+        assert_eq!(state.stroke_color, device_color(&[0.1, 0.2, 0.3, 0.4]));
+        assert_eq!(state.fill_color.space, ColorSpace::Pattern);
+        assert_eq!(
+            state.fill_color.pattern,
+            Some(PdfName::Other(b"P1".to_vec()))
+        );
+    }
+
+    #[test]
+    fn paths() {
+        let (recorder, _) = interpret(b"1 2 m 3 4 5 6 v 7 8 l W* n 0 0 1 1 re b*");
+        assert_eq!(
+            recorder.paths,
+            vec![
+                (
+                    vec![
+                        PathSegment::MoveTo(1.0, 2.0),
+                        PathSegment::CurveTo(1.0, 2.0, 3.0, 4.0, 5.0, 6.0),
+                        PathSegment::LineTo(7.0, 8.0),
+                    ],
+                    Paint {
+                        stroke: false,
+                        fill: None,
+                        clip: Some(FillRule::EvenOdd)
+                    },
+                    Color::default()
+                ),
+                (
+                    vec![
+                        PathSegment::Rectangle(0.0, 0.0, 1.0, 1.0),
+                        PathSegment::ClosePath
+                    ],
+                    Paint {
+                        stroke: true,
+                        fill: Some(FillRule::EvenOdd),
+                        clip: None
+                    },
+                    Color::default()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_positioning() {
+        let (recorder, state) = interpret(
+            b"BT /F1 12 Tf 14 TL 72 720 Td (a) Tj T* (b) Tj 0 -20 TD [(c) -500 (d)] TJ \
+              1 2 (e) \" ET",
+        );
+        let texts: Vec<_> = recorder
+            .texts
+            .iter()
+            .map(|(text, matrix, size)| (text.clone(), matrix.e, matrix.f, *size))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (b"a".to_vec(), 72.0, 720.0, 12.0),
+                (b"b".to_vec(), 72.0, 706.0, 12.0),
+                (b"c".to_vec(), 72.0, 686.0, 12.0),
+                (b"d".to_vec(), 78.0, 686.0, 12.0),
+                (b"e".to_vec(), 72.0, 666.0, 12.0),
+            ]
+        );
+        assert_eq!(state.text.font_name, Some(PdfName::Other(b"F1".to_vec())));
+        assert_eq!(state.text.leading, 20.0);
+        assert_eq!(state.text.word_spacing, 1.0);
+        assert_eq!(state.text.char_spacing, 2.0);
+    }
+
+    #[test]
+    fn forms() {
+        let page = b"1 0 0 1 5 5 cm /Fm1 Do 3 w";
+        let form = b"Q BT /F2 10 Tf (in) Tj ET 7 w /Fm1 Do";
+        let mut document = test_files::document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 300 144] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R \
+             /Resources << /XObject << /Fm1 5 0 R >> >> >>",
+            &format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                page.len(),
+                String::from_utf8_lossy(page)
+            ),
+            &format!(
+                "<< /Type /XObject /Subtype /Form /BBox [0 0 10 10] /Matrix [2 0 0 2 0 0] \
+                 /Resources << /XObject << /Fm1 5 0 R >> \
+                 /Font << /F2 << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> >> >> \
+                 /Length {} >>\nstream\n{}\nendstream",
+                form.len(),
+                String::from_utf8_lossy(form)
+            ),
+        ]);
+        let contents = document.page_contents(0).unwrap();
+        let mut interpreter = Interpreter::for_page(&mut document, 0).unwrap();
+        let mut recorder = Recorder::default();
+        interpreter.run(contents, &mut recorder).unwrap();
+        assert!(recorder.forms.is_empty() && recorder.texts.is_empty());
+
+        let contents = document.page_contents(0).unwrap();
+        let mut interpreter = Interpreter::for_page(&mut document, 0).unwrap();
+        interpreter.recurse_forms(true);
+        let mut recorder = Recorder::default();
+        interpreter.run(contents, &mut recorder).unwrap();
+        let fm1 = PdfName::Other(b"Fm1".to_vec());
+        let form_matrix = Matrix::new(2.0, 0.0, 0.0, 2.0, 5.0, 5.0);
+        // the form paints itself, which isn't interpreted again
+        assert_eq!(
+            recorder.forms,
+            vec![(
+                fm1.clone(),
+                Reference::new(5, 0),
+                Some(Rect::new(0.0, 0.0, 10.0, 10.0)),
+                form_matrix
+            )]
+        );
+        assert_eq!(
+            recorder.xobjects,
+            vec![
+                (fm1.clone(), Matrix::translate(5.0, 5.0)),
+                (fm1, form_matrix)
+            ]
+        );
+        assert_eq!(
+            recorder.texts,
+            vec![(b"in".to_vec(), Matrix::identity(), 10.0)]
+        );
+        // the form's Q and w don't affect the page
+        let state = interpreter.state();
+        assert_eq!(state.ctm, Matrix::translate(5.0, 5.0));
+        assert_eq!(state.line_width, 3.0);
+        assert_eq!(state.text.font_name, None);
+    }
+
+    #[test]
+    fn broken_forms() {
+        let page = b"/Fm1 Do /Fm2 Do /Fm3 Do 2 w /Fm4 Do 3 w";
+        let form = b"BT /F1 10 Tf (in) Tj ET";
+        let mut document = test_files::document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 300 144] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R \
+             /Resources << /XObject << /Fm1 5 0 R /Fm2 9 0 R /Fm3 6 0 R /Fm4 7 0 R >> \
+             /Font << /F1 << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> >> >> >>",
+            &format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                page.len(),
+                String::from_utf8_lossy(page)
+            ),
+            // an LZW encoded form, which isn't supported
+            "<< /Type /XObject /Subtype /Form /BBox [0 0 10 10] /Filter /LZWDecode \
+             /Length 5 >>\nstream\n@B\x07!!\nendstream",
+            // a form whose content stops at an unterminated dictionary
+            "<< /Type /XObject /Subtype /Form /BBox [0 0 10 10] /Length 14 >>\n\
+             stream\n4 w << /A 1 Do\nendstream",
+            &format!(
+                "<< /Type /XObject /Subtype /Form /Length {} >>\nstream\n{}\nendstream",
+                form.len(),
+                String::from_utf8_lossy(form)
+            ),
+        ]);
+        let contents = document.page_contents(0).unwrap();
+        let mut interpreter = Interpreter::for_page(&mut document, 0).unwrap();
+        interpreter.recurse_forms(true);
+        let mut recorder = Recorder::default();
+        interpreter.run(contents, &mut recorder).unwrap();
+        let names: Vec<_> = recorder.forms.iter().map(|form| form.0.clone()).collect();
+        assert_eq!(
+            names,
+            vec![
+                PdfName::Other(b"Fm3".to_vec()),
+                PdfName::Other(b"Fm4".to_vec())
+            ]
+        );
+        // a form without a /BBox
+        assert_eq!(recorder.forms[1].2, None);
+        assert_eq!(
+            recorder.texts,
+            vec![(b"in".to_vec(), Matrix::identity(), 10.0)]
+        );
+        assert_eq!(interpreter.state().line_width, 3.0);
+    }
+
+    #[test]
+    fn broken_fonts() {
+        let page = b"BT /F1 10 Tf (a) Tj /F2 10 Tf (b) Tj /F1 12 Tf ET";
+        let mut document = test_files::document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 300 144] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R \
+             /Resources << /Font << /F1 << /Type /Font /Subtype /Unknown >> \
+             /F2 << /Type /Font /Subtype /Type0 /Encoding /Identity-H \
+             /DescendantFonts 9 0 R >> >> >> >>",
+            &format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                page.len(),
+                String::from_utf8_lossy(page)
+            ),
+        ]);
+        let contents = document.page_contents(0).unwrap();
+        let mut interpreter = Interpreter::for_page(&mut document, 0).unwrap();
+        let mut recorder = Recorder::default();
+        interpreter.run(contents, &mut recorder).unwrap();
+        assert_eq!(recorder.texts.len(), 2);
+        assert!(interpreter.state().text.font.is_none());
+        assert_eq!(interpreter.fonts.len(), 2);
+    }
+
+    #[test]
+    fn tracemonkey_pdf_interpreter() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        for pageno in 0..document.page_count() {
+            let contents = document.page_contents(pageno).unwrap();
+            let mut interpreter = Interpreter::for_page(&mut document, pageno).unwrap();
+            let mut recorder = Recorder::default();
+            interpreter.run(contents, &mut recorder).unwrap();
+            assert!(!recorder.texts.is_empty());
+        }
+    }
+}
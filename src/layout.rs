@@ -0,0 +1,617 @@
+use crate::matrix::Matrix;
+use crate::pdf_document::PdfDocument;
+use crate::rect::Rect;
+use crate::text_runs::{text_runs, Glyph, TextRun};
+
+// the fewest characters a line can have for its width to set the grid of layout_text
+const MIN_PITCH_CHARS: usize = 8;
+
+// thresholds for grouping glyphs into words, lines and blocks, as fractions of the font size
+// or line height
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutParams {
+    // glyphs are on the same line when their heights overlap by at least this fraction of the
+    // smaller glyph, which keeps superscripts and subscripts on their line
+    pub line_overlap: f64,
+    // the largest gap between glyphs on the same line
+    pub char_margin: f64,
+    // gaps wider than this start a new word
+    pub word_margin: f64,
+    // the largest gap between lines in the same block
+    pub line_margin: f64,
+    // lines whose font sizes differ by more than this fraction are in different blocks
+    pub size_tolerance: f64,
+    // a line indented by more than this, which reaches the right edge of the block above it,
+    // starts a new paragraph, as does a line outdented by more than this from the line above,
+    // unless that is the first line of its block
+    pub indent: f64,
+}
+
+impl Default for LayoutParams {
+    fn default() -> LayoutParams {
+        LayoutParams {
+            line_overlap: 0.5,
+            char_margin: 2.0,
+            word_margin: 0.1,
+            line_margin: 0.5,
+            size_tolerance: 0.2,
+            indent: 0.5,
+        }
+    }
+}
+
+// bounding boxes are in page space, see PdfDocument::page_space
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub text: String,
+    pub bbox: Rect,
+    // the font and size of its first glyph, see TextRun
+    pub base_font: String,
+    pub size: f64,
+    pub glyphs: Vec<Glyph>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub words: Vec<Word>,
+    pub bbox: Rect,
+}
+
+impl Line {
+    pub fn text(&self) -> String {
+        let words: Vec<&str> = self.words.iter().map(|word| word.text.as_str()).collect();
+        words.join(" ")
+    }
+}
+
+// a group of lines close together with similar font sizes, usually a paragraph
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub lines: Vec<Line>,
+    pub bbox: Rect,
+    // the direction of its text, see TextRun::angle
+    pub angle: f64,
+}
+
+impl Block {
+    pub fn text(&self) -> String {
+        let lines: Vec<String> = self.lines.iter().map(|line| line.text()).collect();
+        lines.join("\n")
+    }
+}
+
+// the blocks of text on a page, in reading order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PageLayout {
+    pub blocks: Vec<Block>,
+}
+
+impl PageLayout {
+    // blocks are separated by blank lines
+    pub fn text(&self) -> String {
+        let blocks: Vec<String> = self.blocks.iter().map(|block| block.text()).collect();
+        blocks.join("\n\n")
+    }
+}
+
+// a glyph with its bounding box in a frame where its text advances along the x axis
+#[derive(Debug, Clone)]
+struct Item {
+    glyph: Glyph,
+    frame: Rect,
+    base_font: String,
+    size: f64,
+}
+
+// glyphs on the same baseline, in frame coordinates
+#[derive(Debug, Clone)]
+struct FrameLine {
+    items: Vec<Item>,
+    frame: Rect,
+    size: f64,
+}
+
+#[derive(Debug)]
+struct FrameBlock {
+    lines: Vec<FrameLine>,
+    frame: Rect,
+}
+
+// groups the glyphs of text runs into words, lines and blocks and puts the blocks in reading
+// order
+//
+// Text in each direction is analyzed separately, upright text first. Within a direction,
+// glyphs are joined into lines in content stream order, and lines into blocks with the
+// nearest line above them. Blocks are ordered by recursively cutting the page into columns
+// where no block crosses a vertical gap, or otherwise into bands at the widest horizontal gap.
+pub fn analyze(runs: &[TextRun], params: &LayoutParams) -> PageLayout {
+    let mut angles: Vec<i64> = runs.iter().map(|run| run.angle.round() as i64).collect();
+    angles.sort_by_key(|&angle| (angle.rem_euclid(360), angle));
+    angles.dedup();
+    let mut blocks = vec![];
+    for angle in angles.into_iter() {
+        let radians = (angle as f64).to_radians();
+        let (sin, cos) = radians.sin_cos();
+        // rotates the text in page space so that it advances along the x axis
+        let frame = Matrix::new(cos, -sin, sin, cos, 0.0, 0.0);
+        let items: Vec<Item> = runs
+            .iter()
+            .filter(|run| run.angle.round() as i64 == angle)
+            .flat_map(|run| {
+                run.glyphs.iter().map(move |glyph| Item {
+                    glyph: glyph.clone(),
+                    frame: glyph.page_bbox.transform(&frame),
+                    base_font: run.base_font.clone(),
+                    size: run.size,
+                })
+            })
+            .collect();
+        let lines = find_lines(items, params);
+        let frame_blocks = reading_order(find_blocks(lines, params));
+        blocks.extend(
+            frame_blocks
+                .into_iter()
+                .map(|block| to_block(block, angle as f64, params)),
+        );
+    }
+    PageLayout { blocks }
+}
+
+// the layout of the text on a page, see analyze
+pub fn page_layout(
+    document: &mut PdfDocument,
+    pageno: u32,
+    params: &LayoutParams,
+) -> crate::Result<PageLayout> {
+    Ok(analyze(&text_runs(document, pageno)?, params))
+}
+
+// the upright text of a page laid out on a grid of characters, approximating where it appears
+// on the page, like pdftotext -layout
+//
+// Each line of the grid is a row of glyphs sharing a baseline, even when they're in different
+// columns, and words are placed at the column nearest their left edge, at least one space
+// after the previous word. Grid columns are as wide as the narrowest character pitch of any
+// line, and blank lines stand for vertical gaps wider than the usual line spacing. Text which
+// isn't upright is left out.
+pub fn layout_text(runs: &[TextRun], params: &LayoutParams) -> String {
+    let items: Vec<Item> = runs
+        .iter()
+        .filter(|run| run.angle.round() == 0.0)
+        .flat_map(|run| {
+            run.glyphs.iter().map(move |glyph| Item {
+                glyph: glyph.clone(),
+                frame: glyph.page_bbox,
+                base_font: run.base_font.clone(),
+                size: run.size,
+            })
+        })
+        .collect();
+    let lines = find_lines(items, params);
+    // the narrowest character pitch of any line, so that no line overflows its columns,
+    // ignoring short lines unless there are no others
+    let pitches: Vec<(usize, f64)> = lines
+        .iter()
+        .map(|line| {
+            let chars = to_line(line.clone(), params).text().chars().count();
+            (chars, line.frame.width() / chars.max(1) as f64)
+        })
+        .collect();
+    let narrowest = |min_chars: usize| {
+        pitches
+            .iter()
+            .filter(|(chars, _)| *chars >= min_chars)
+            .map(|(_, pitch)| *pitch)
+            .fold(f64::INFINITY, f64::min)
+    };
+    let cell = match narrowest(MIN_PITCH_CHARS) {
+        cell if cell.is_finite() => cell,
+        _ => narrowest(1),
+    };
+    if !cell.is_finite() || cell <= 0.0 {
+        return String::new();
+    }
+    let rows = find_rows(lines, params);
+    let left = rows
+        .iter()
+        .map(|row| row.frame.x0)
+        .fold(f64::INFINITY, f64::min);
+    // the usual distance between the baselines of consecutive rows
+    let mut pitches: Vec<f64> = rows
+        .windows(2)
+        .map(|pair| pair[0].frame.y0 - pair[1].frame.y0)
+        .filter(|&pitch| pitch > 0.0)
+        .collect();
+    pitches.sort_by(f64::total_cmp);
+    let pitch = match pitches.len() {
+        0 => 1.0,
+        len => pitches[(len - 1) / 2],
+    };
+
+    let mut text = String::new();
+    let mut above: Option<f64> = None;
+    for row in rows.into_iter() {
+        if let Some(above) = above {
+            let blank = ((above - row.frame.y0) / pitch).round() as usize;
+            text.push_str(&"\n".repeat(blank.max(1)));
+        }
+        above = Some(row.frame.y0);
+        let mut words: Vec<Word> = row
+            .lines
+            .into_iter()
+            .flat_map(|line| to_line(line, params).words)
+            .collect();
+        words.sort_by(|a, b| a.bbox.x0.total_cmp(&b.bbox.x0));
+        let mut line = String::new();
+        let mut columns = 0;
+        for word in words.into_iter() {
+            let column = ((word.bbox.x0 - left) / cell).round() as usize;
+            let column = if columns == 0 {
+                column
+            } else {
+                column.max(columns + 1)
+            };
+            line.push_str(&" ".repeat(column - columns));
+            line.push_str(&word.text);
+            columns = column + word.text.chars().count();
+        }
+        text.push_str(&line);
+    }
+    text.push('\n');
+    text
+}
+
+// the text of a page, see layout_text
+pub fn page_layout_text(
+    document: &mut PdfDocument,
+    pageno: u32,
+    params: &LayoutParams,
+) -> crate::Result<String> {
+    Ok(layout_text(&text_runs(document, pageno)?, params))
+}
+
+// lines of glyphs whose heights overlap, from top to bottom
+#[derive(Debug)]
+struct Row {
+    lines: Vec<FrameLine>,
+    frame: Rect,
+}
+
+fn find_rows(mut lines: Vec<FrameLine>, params: &LayoutParams) -> Vec<Row> {
+    lines.sort_by(|a, b| b.frame.y1.total_cmp(&a.frame.y1));
+    let mut rows: Vec<Row> = vec![];
+    for line in lines.into_iter() {
+        if let Some(row) = rows.last_mut() {
+            let height = row.frame.height().min(line.frame.height());
+            if vertical_overlap(&row.frame, &line.frame) >= params.line_overlap * height {
+                row.frame = row.frame.union(&line.frame);
+                row.lines.push(line);
+                continue;
+            }
+        }
+        rows.push(Row {
+            frame: line.frame,
+            lines: vec![line],
+        });
+    }
+    rows
+}
+
+fn vertical_overlap(a: &Rect, b: &Rect) -> f64 {
+    a.y1.min(b.y1) - a.y0.max(b.y0)
+}
+
+fn horizontal_overlap(a: &Rect, b: &Rect) -> f64 {
+    a.x1.min(b.x1) - a.x0.max(b.x0)
+}
+
+fn find_lines(items: Vec<Item>, params: &LayoutParams) -> Vec<FrameLine> {
+    let mut lines: Vec<FrameLine> = vec![];
+    for item in items.into_iter() {
+        if let Some(line) = lines.last_mut() {
+            let last = &line.items[line.items.len() - 1];
+            let height = item.frame.height().min(last.frame.height());
+            let size = item.size.max(last.size);
+            let gap = item.frame.x0 - last.frame.x1;
+            if vertical_overlap(&item.frame, &last.frame) >= params.line_overlap * height
+                && gap <= params.char_margin * size
+                && gap >= -size
+            {
+                line.frame = line.frame.union(&item.frame);
+                line.size = line.size.max(item.size);
+                line.items.push(item);
+                continue;
+            }
+        }
+        lines.push(FrameLine {
+            frame: item.frame,
+            size: item.size,
+            items: vec![item],
+        });
+    }
+    // whitespace glyphs only separate words, so lines of nothing else are dropped
+    lines.retain(|line| {
+        line.items
+            .iter()
+            .any(|item| !item.glyph.text.trim().is_empty())
+    });
+    lines
+}
+
+fn find_blocks(mut lines: Vec<FrameLine>, params: &LayoutParams) -> Vec<FrameBlock> {
+    lines.sort_by(|a, b| b.frame.y1.total_cmp(&a.frame.y1));
+    let mut blocks: Vec<FrameBlock> = vec![];
+    for line in lines.into_iter() {
+        let mut nearest: Option<(usize, f64)> = None;
+        for (index, block) in blocks.iter().enumerate() {
+            let last = &block.lines[block.lines.len() - 1];
+            let gap = last.frame.y0 - line.frame.y1;
+            let height = last.frame.height().max(line.frame.height());
+            let width = last.frame.width().min(line.frame.width());
+            let size = last.size.max(line.size);
+            let indent = line.frame.x0 - last.frame.x0;
+            let paragraph = block.lines.len() > 1
+                && (indent < -params.indent * size
+                    || indent > params.indent * size
+                        && (line.frame.x1 - block.frame.x1).abs() < size);
+            if gap <= params.line_margin * height
+                && !paragraph
+                && vertical_overlap(&last.frame, &line.frame) < params.line_overlap * height
+                && horizontal_overlap(&last.frame, &line.frame) >= 0.5 * width
+                && (last.size - line.size).abs() <= params.size_tolerance * size
+                && nearest.is_none_or(|(_, nearest)| gap < nearest)
+            {
+                nearest = Some((index, gap));
+            }
+        }
+        match nearest {
+            Some((index, _)) => {
+                let block = &mut blocks[index];
+                block.frame = block.frame.union(&line.frame);
+                block.lines.push(line);
+            }
+            None => blocks.push(FrameBlock {
+                frame: line.frame,
+                lines: vec![line],
+            }),
+        }
+    }
+    blocks
+}
+
+// splits blocks wherever no block crosses a gap in their projection onto one axis
+fn cut(mut blocks: Vec<FrameBlock>, vertical: bool) -> Vec<Vec<FrameBlock>> {
+    let span = |block: &FrameBlock| {
+        if vertical {
+            (block.frame.x0, block.frame.x1)
+        } else {
+            (-block.frame.y1, -block.frame.y0)
+        }
+    };
+    blocks.sort_by(|a, b| span(a).0.total_cmp(&span(b).0));
+    let mut parts: Vec<Vec<FrameBlock>> = vec![];
+    let mut end = f64::NEG_INFINITY;
+    for block in blocks.into_iter() {
+        let (start, finish) = span(&block);
+        match parts.last_mut() {
+            Some(part) if start <= end => part.push(block),
+            _ => parts.push(vec![block]),
+        }
+        end = end.max(finish);
+    }
+    parts
+}
+
+fn reading_order(blocks: Vec<FrameBlock>) -> Vec<FrameBlock> {
+    if blocks.len() <= 1 {
+        return blocks;
+    }
+    let columns = cut(blocks, true);
+    if columns.len() > 1 {
+        return columns.into_iter().flat_map(reading_order).collect();
+    }
+    let blocks = columns.into_iter().flatten().collect();
+    let mut bands = cut(blocks, false);
+    if bands.len() == 1 {
+        // nothing separates the blocks, so they are read from top to bottom
+        let mut blocks = bands.remove(0);
+        blocks.sort_by(|a, b| b.frame.y1.total_cmp(&a.frame.y1));
+        return blocks;
+    }
+    // only the widest gap is cut, so gaps which happen to line up between columns are not
+    let widest = (1..bands.len())
+        .max_by(|&a, &b| band_gap(&bands, a).total_cmp(&band_gap(&bands, b)))
+        .unwrap_or(1);
+    let lower: Vec<FrameBlock> = bands.split_off(widest).into_iter().flatten().collect();
+    let upper: Vec<FrameBlock> = bands.into_iter().flatten().collect();
+    let mut ordered = reading_order(upper);
+    ordered.extend(reading_order(lower));
+    ordered
+}
+
+// the gap between a band and the one above it
+fn band_gap(bands: &[Vec<FrameBlock>], index: usize) -> f64 {
+    let frame = |band: &[FrameBlock]| union(band.iter().map(|block| block.frame));
+    frame(&bands[index - 1]).y0 - frame(&bands[index]).y1
+}
+
+fn to_block(block: FrameBlock, angle: f64, params: &LayoutParams) -> Block {
+    let lines: Vec<Line> = block
+        .lines
+        .into_iter()
+        .map(|line| to_line(line, params))
+        .collect();
+    Block {
+        bbox: union(lines.iter().map(|line| line.bbox)),
+        lines,
+        angle,
+    }
+}
+
+fn to_line(line: FrameLine, params: &LayoutParams) -> Line {
+    let mut words: Vec<Vec<Item>> = vec![];
+    let mut last: Option<Rect> = None;
+    for item in line.items.into_iter() {
+        let gap = last.map_or(0.0, |last| item.frame.x0 - last.x1);
+        last = Some(item.frame);
+        if item.glyph.text.trim().is_empty() {
+            words.push(vec![]);
+            continue;
+        }
+        match words.last_mut() {
+            Some(word) if gap <= params.word_margin * item.size => word.push(item),
+            _ => words.push(vec![item]),
+        }
+    }
+    let words: Vec<Word> = words
+        .into_iter()
+        .filter(|word| !word.is_empty())
+        .map(|items| Word {
+            text: items.iter().map(|item| item.glyph.text.as_str()).collect(),
+            bbox: union(items.iter().map(|item| item.glyph.page_bbox)),
+            base_font: items[0].base_font.clone(),
+            size: items[0].size,
+            glyphs: items.into_iter().map(|item| item.glyph).collect(),
+        })
+        .collect();
+    Line {
+        bbox: union(words.iter().map(|word| word.bbox)),
+        words,
+    }
+}
+
+fn union(mut rects: impl Iterator<Item = Rect>) -> Rect {
+    match rects.next() {
+        Some(first) => rects.fold(first, |bbox, rect| bbox.union(&rect)),
+        None => Rect::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics_state::Color;
+    use crate::test_files;
+
+    // a run of glyphs with size 10 and an advance of 6, starting from the box at origin
+    fn run(text: &str, origin: Rect, angle: f64) -> TextRun {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let glyphs: Vec<Glyph> = text
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| {
+                let (dx, dy) = (
+                    (cos * 6.0 * i as f64).round(),
+                    (sin * 6.0 * i as f64).round(),
+                );
+                let bbox = Rect::new(
+                    origin.x0 + dx,
+                    origin.y0 + dy,
+                    origin.x1 + dx,
+                    origin.y1 + dy,
+                );
+                Glyph {
+                    text: ch.to_string(),
+                    bbox,
+                    page_bbox: bbox,
+                }
+            })
+            .collect();
+        TextRun {
+            text: text.to_owned(),
+            base_font: "Helvetica".to_owned(),
+            font_size: 10.0,
+            size: 10.0,
+            fill_color: Color::default(),
+            render_mode: 0,
+            angle,
+            bbox: union(glyphs.iter().map(|glyph| glyph.bbox)),
+            page_bbox: union(glyphs.iter().map(|glyph| glyph.page_bbox)),
+            glyphs,
+        }
+    }
+
+    #[test]
+    fn superscripts_and_rotated_text() {
+        let mut superscript = run("2", Rect::new(112.0, 103.0, 116.0, 109.0), 0.0);
+        superscript.size = 6.0;
+        let runs = vec![
+            run("x", Rect::new(106.0, 98.0, 112.0, 106.0), 0.0),
+            superscript,
+            run("y", Rect::new(120.0, 98.0, 126.0, 106.0), 0.0),
+            // reading upwards, with the second line to the right of the first
+            run("AB", Rect::new(22.0, 100.0, 30.0, 106.0), 90.0),
+            run("CD", Rect::new(34.0, 100.0, 42.0, 106.0), 90.0),
+        ];
+        let layout = analyze(&runs, &LayoutParams::default());
+        assert_eq!(layout.text(), "x2 y\n\nAB\nCD");
+        assert_eq!(layout.blocks[1].angle, 90.0);
+        assert_eq!(layout.blocks[1].bbox, Rect::new(22.0, 100.0, 42.0, 112.0));
+        let words = &layout.blocks[0].lines[0].words;
+        assert_eq!(words[0].bbox, Rect::new(106.0, 98.0, 116.0, 109.0));
+    }
+
+    #[test]
+    fn table_layout_text() {
+        let runs = vec![
+            run("Name", Rect::new(100.0, 700.0, 106.0, 710.0), 0.0),
+            run("Total", Rect::new(160.0, 700.0, 166.0, 710.0), 0.0),
+            run("apples", Rect::new(100.0, 688.0, 106.0, 698.0), 0.0),
+            run("12", Rect::new(160.0, 688.0, 166.0, 698.0), 0.0),
+            run("pears", Rect::new(100.0, 652.0, 106.0, 662.0), 0.0),
+            run("7", Rect::new(160.0, 652.0, 166.0, 662.0), 0.0),
+        ];
+        assert_eq!(
+            layout_text(&runs, &LayoutParams::default()),
+            "Name      Total\napples    12\n\n\npears     7\n"
+        );
+    }
+
+    #[test]
+    fn tracemonkey_pdf_layout_text() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let text = page_layout_text(&mut document, 0, &LayoutParams::default()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        let row = lines
+            .iter()
+            .position(|line| line.starts_with("Abstract"))
+            .unwrap();
+        // both columns share each row, and the right one starts at the same column throughout
+        let words = |row: usize| lines[row].split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(
+            words(row),
+            "Abstract and is used for the application logic of browser-based productivity"
+        );
+        assert!(words(row + 1).starts_with("Dynamic languages "));
+        let column = |row: usize, word: &str| {
+            let line: &str = lines[row];
+            line[..line.find(word).unwrap()].chars().count()
+        };
+        assert_eq!(column(row + 1, "applications"), column(row, " and ") + 1);
+        assert_eq!(column(row + 2, "laboration"), column(row, " and ") + 1);
+        assert!(lines[..row].iter().any(|line| line.trim() == "Languages"));
+    }
+
+    #[test]
+    fn tracemonkey_pdf_layout() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let layout = page_layout(&mut document, 0, &LayoutParams::default()).unwrap();
+        let blocks: Vec<String> = layout.blocks.iter().map(|block| block.text()).collect();
+        assert_eq!(
+            blocks[0],
+            "Trace-based Just-in-Time Type Specialization for Dynamic\nLanguages"
+        );
+        // superscripts stay on their line
+        assert!(blocks[1].starts_with("Andreas Gal\u{2217}+, Brendan Eich\u{2217}, "));
+        let find = |start: &str| blocks.iter().position(|block| block.starts_with(start));
+        let abstract_ = find("Abstract").unwrap();
+        let left = find("Dynamic languages such as JavaScript are more").unwrap();
+        let footnote = find("Permission to make digital").unwrap();
+        let right = find("and is used for the application logic").unwrap();
+        assert!(abstract_ < left && left < footnote && footnote < right);
+        assert!(blocks[left].contains(" dif\u{fb01}cult to com-\npile than "));
+        // a paragraph starts at each indented line
+        assert!(find("Compilers for statically typed languages").unwrap() > right);
+        assert_eq!(layout.blocks[left].bbox.x0.round(), 54.0);
+    }
+}
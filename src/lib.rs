@@ -1,15 +1,72 @@
+mod cid_tables;
+mod cmap;
 mod dictionary;
+mod encoding;
 mod errors;
+mod export;
+mod font;
+mod function;
+mod graphics_state;
+mod image_decoder;
+mod images;
+mod inline_image;
+mod interpreter;
+mod layout;
+mod matrix;
 mod next_object;
 mod next_token;
+mod normalize;
+mod operations;
 mod page_contents;
 mod pdf_document;
 mod pdf_source;
 mod pdf_types;
+mod rect;
+mod resources;
+mod search;
+mod standard_fonts;
 mod streams;
+#[cfg(test)]
+mod test_files;
+mod text;
+mod text_runs;
+mod validation;
+mod warnings;
 
 pub type Result<T> = std::result::Result<T, errors::PdfError>;
 
-pub use crate::errors::PdfError;
-pub use crate::pdf_document::PdfDocument;
+pub use crate::cmap::{CMap, Code, Codes};
+pub use crate::dictionary::Access;
+pub use crate::encoding::{decode_text_string, glyph_to_unicode, Encoding};
+pub use crate::errors::{Location, PdfError};
+pub use crate::export::{alto, hocr, ExportOptions};
+pub use crate::font::{CidFont, CidToGid, Font, FontDescriptor};
+pub use crate::function::Function;
+pub use crate::graphics_state::{Color, ColorSpace, DashPattern, GraphicsState, TextState};
+pub use crate::image_decoder::{decode_image, decode_image_xobject, DecodedImage, PixelFormat};
+pub use crate::images::{page_images, PageImage};
+pub use crate::interpreter::{FillRule, Interpreter, Paint, PathSegment, Visitor};
+pub use crate::layout::{
+    analyze, layout_text, page_layout, page_layout_text, Block, LayoutParams, Line, PageLayout,
+    Word,
+};
+pub use crate::matrix::Matrix;
+pub use crate::normalize::{
+    dehyphenate, normalize_runs, normalize_text, normalized_text, NormalizationForm,
+    NormalizeOptions,
+};
+pub use crate::operations::{arity, Arity, Operation, Operations};
+pub use crate::page_contents::PageContents;
+pub use crate::pdf_document::{ParseMode, PdfDocument};
 pub use crate::pdf_source::{ByteSliceSource, ByteSource, PdfSource, Source};
+pub use crate::pdf_types::{
+    Array, Dictionary, InlineImage, PdfKeyword, PdfName, PdfNumber, PdfObject, PdfString, Reference,
+};
+pub use crate::rect::Rect;
+pub use crate::resources::{Resource, Resources};
+pub use crate::search::{search, search_page, Hit, SearchOptions};
+pub use crate::standard_fonts::StandardFont;
+pub use crate::text::{extract_text, TextExtractor};
+pub use crate::text_runs::{text_runs, Glyph, TextRun, TextRunCollector};
+pub use crate::validation::{validate, Diagnostic, DiagnosticKind};
+pub use crate::warnings::{Warning, WarningKind};
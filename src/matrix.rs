@@ -0,0 +1,94 @@
+use crate::pdf_types::*;
+
+// a PDF transformation matrix [a b c d e f], mapping (x, y) to (ax + cy + e, bx + dy + f)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Matrix {
+    fn default() -> Matrix {
+        Matrix::identity()
+    }
+}
+
+impl Matrix {
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Matrix {
+        Matrix { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Matrix {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Matrix {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Matrix {
+        Matrix::new(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    // six numbers, as found in cm and Tm operands or a /Matrix array
+    pub fn from_objects(objects: &[PdfObject]) -> Option<Matrix> {
+        if objects.len() != 6 {
+            return None;
+        }
+        let mut values = [0.0; 6];
+        for (value, object) in values.iter_mut().zip(objects.iter()) {
+            *value = object.as_f64()?;
+        }
+        let [a, b, c, d, e, f] = values;
+        Some(Matrix::new(a, b, c, d, e, f))
+    }
+
+    // the transformation which applies self and then other
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix::new(
+            self.a * other.a + self.b * other.c,
+            self.a * other.b + self.b * other.d,
+            self.c * other.a + self.d * other.c,
+            self.c * other.b + self.d * other.d,
+            self.e * other.a + self.f * other.c + other.e,
+            self.e * other.b + self.f * other.d + other.f,
+        )
+    }
+
+    pub fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiply() {
+        let m = Matrix::scale(2.0, 3.0).multiply(&Matrix::translate(10.0, 20.0));
+        assert_eq!(m.transform(1.0, 1.0), (12.0, 23.0));
+        let m = Matrix::translate(10.0, 20.0).multiply(&Matrix::scale(2.0, 3.0));
+        assert_eq!(m.transform(1.0, 1.0), (22.0, 63.0));
+    }
+
+    #[test]
+    fn from_objects() {
+        let objects: Vec<_> = [1, 0, 0, 1, 72, 720]
+            .iter()
+            .map(|&n| PdfObject::Number(PdfNumber::Integer(n)))
+            .collect();
+        assert_eq!(
+            Matrix::from_objects(&objects),
+            Some(Matrix::translate(72.0, 720.0))
+        );
+        assert_eq!(Matrix::from_objects(&objects[1..]), None);
+    }
+}
@@ -1,11 +1,20 @@
-use std::collections::HashMap;
+use std::io::SeekFrom;
 
 use crate::next_token::next_token;
 use crate::pdf_source::Source;
 use crate::pdf_types::*;
+use crate::warnings::{Warning, WarningKind};
 use crate::PdfError;
 
 pub fn next_object(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfObject>> {
+    next_object_with_warnings(source, &mut vec![])
+}
+
+// like next_object, but records any recoverable problems found in the object
+pub fn next_object_with_warnings(
+    source: &mut Box<dyn Source>,
+    warnings: &mut Vec<Warning>,
+) -> crate::Result<Option<PdfObject>> {
     match next_token(source)? {
         Some(PdfToken::Keyword(PdfKeyword::null)) => Ok(Some(PdfObject::Null)),
         Some(PdfToken::Keyword(PdfKeyword::r#true)) => Ok(Some(PdfObject::Boolean(true))),
@@ -16,9 +25,12 @@ pub fn next_object(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfObje
         Some(PdfToken::Name(name)) => Ok(Some(PdfObject::Name(name))),
         Some(PdfToken::Symbol(symbol)) => Ok(Some(PdfObject::Symbol(symbol))),
         Some(PdfToken::Str(s)) => Ok(Some(PdfObject::String(s))),
-        Some(PdfToken::BeginArray) => array(source),
-        Some(PdfToken::BeginDictionary) => dictionary(source),
-        Some(PdfToken::EndArray) | Some(PdfToken::EndDictionary) => Ok(None),
+        Some(PdfToken::BeginArray) => array(source, warnings),
+        Some(PdfToken::BeginDictionary) => dictionary(source, warnings),
+        Some(PdfToken::BeginProcedure) => procedure(source, warnings),
+        Some(PdfToken::EndArray) | Some(PdfToken::EndDictionary) | Some(PdfToken::EndProcedure) => {
+            Ok(None)
+        }
         None => Ok(None),
     }
 }
@@ -37,17 +49,23 @@ pub fn need_u32(source: &mut Box<dyn Source>, value: u32) -> crate::Result<()> {
     }
 }
 
-pub fn need_dictionary(source: &mut Box<dyn Source>) -> crate::Result<Dictionary> {
-    match next_object(source)? {
+pub fn need_dictionary(
+    source: &mut Box<dyn Source>,
+    warnings: &mut Vec<Warning>,
+) -> crate::Result<Dictionary> {
+    match next_object_with_warnings(source, warnings)? {
         Some(PdfObject::Dictionary(d)) => Ok(d),
         _ => Err(PdfError::InvalidPdf("dictionary expected")),
     }
 }
 
-fn array(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfObject>> {
+fn array(
+    source: &mut Box<dyn Source>,
+    warnings: &mut Vec<Warning>,
+) -> crate::Result<Option<PdfObject>> {
     let mut array = Box::new(vec![]);
     loop {
-        match next_object(source)? {
+        match next_object_with_warnings(source, warnings)? {
             Some(PdfObject::Keyword(PdfKeyword::R)) => reference(&mut array)?,
             Some(obj) => array.push(obj),
             None => return Ok(Some(PdfObject::Array(array))),
@@ -55,25 +73,45 @@ fn array(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfObject>> {
     }
 }
 
-fn dictionary(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfObject>> {
+fn procedure(
+    source: &mut Box<dyn Source>,
+    warnings: &mut Vec<Warning>,
+) -> crate::Result<Option<PdfObject>> {
+    let mut procedure = Box::new(vec![]);
+    while let Some(obj) = next_object_with_warnings(source, warnings)? {
+        procedure.push(obj);
+    }
+    Ok(Some(PdfObject::Procedure(procedure)))
+}
+
+fn dictionary(
+    source: &mut Box<dyn Source>,
+    warnings: &mut Vec<Warning>,
+) -> crate::Result<Option<PdfObject>> {
     let mut array = vec![];
     loop {
-        match next_object(source)? {
+        match next_object_with_warnings(source, warnings)? {
             Some(PdfObject::Keyword(PdfKeyword::R)) => reference(&mut array)?,
             Some(obj) => array.push(obj),
             None => {
                 if array.len() % 2 != 0 {
+                    warnings.push(Warning {
+                        kind: WarningKind::OddLengthDictionary,
+                        offset: source.seek(SeekFrom::Current(0))?,
+                        reference: None,
+                    });
                     array.push(PdfObject::Null);
                 }
-                let mut dict: Dictionary = Box::new(HashMap::new());
-                while array.len() != 0 {
-                    let value = array.pop().unwrap();
+                let mut dict: Dictionary = Box::default();
+                while let Some(value) = array.pop() {
                     let name = array.pop().unwrap();
                     match name {
                         PdfObject::Name(name) => {
                             dict.insert(name, value);
                         }
-                        PdfObject::Symbol(_) => {}
+                        PdfObject::Symbol(symbol) => {
+                            dict.insert(PdfName::Other(symbol), value);
+                        }
                         _ => return Err(PdfError::InvalidPdf("malformed dictionary")),
                     }
                 }
@@ -105,6 +143,7 @@ fn reference(array: &mut Vec<PdfObject>) -> crate::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dictionary::Access;
     use crate::pdf_source::ByteSliceSource;
 
     fn next(source: &mut Box<dyn Source>) -> PdfObject {
@@ -264,4 +303,72 @@ mod tests {
         let n2 = next(&mut source2);
         assert_eq!(n1, n2);
     }
+
+    #[test]
+    fn compact_dictionary() {
+        let mut source1: Box<dyn Source> = Box::new(ByteSliceSource::new(
+            b"<</Type/Page/Parent 3 0 R/MediaBox[0 0 612 792]/Contents 4 0 R>>",
+        ));
+        let n1 = next(&mut source1);
+        let mut source2: Box<dyn Source> = Box::new(ByteSliceSource::new(
+            b"<< /Type /Page /Parent 3 0 R /MediaBox [ 0 0 612 792 ] /Contents 4 0 R >>",
+        ));
+        let n2 = next(&mut source2);
+        assert_eq!(n1, n2);
+    }
+
+    #[test]
+    fn unrecognized_keys() {
+        let mut source: Box<dyn Source> =
+            Box::new(ByteSliceSource::new(b"<< /F1 6 0 R /Type /Font >>"));
+        match next(&mut source) {
+            PdfObject::Dictionary(dict) => {
+                assert_eq!(
+                    dict.get_reference(PdfName::Other(b"F1".to_vec())),
+                    Some(Reference::new(6, 0))
+                );
+                assert_eq!(dict.get_name(PdfName::Type), Some(PdfName::Font));
+            }
+            obj => panic!("unexpected object: {:?}", obj),
+        }
+    }
+
+    #[test]
+    fn procedure() {
+        let mut source: Box<dyn Source> =
+            Box::new(ByteSliceSource::new(b"{ 2 copy gt { exch } if pop }"));
+        let n = next(&mut source);
+        assert_eq!(
+            n,
+            PdfObject::Procedure(Box::new(vec![
+                PdfObject::Number(PdfNumber::Integer(2)),
+                PdfObject::Keyword(PdfKeyword::copy),
+                PdfObject::Keyword(PdfKeyword::gt),
+                PdfObject::Procedure(Box::new(vec![PdfObject::Keyword(PdfKeyword::exch)])),
+                PdfObject::Keyword(PdfKeyword::r#if),
+                PdfObject::Keyword(PdfKeyword::pop),
+            ]))
+        );
+    }
+
+    #[test]
+    fn odd_length_dictionary() {
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"<</Size 1 /Root>>"));
+        let mut warnings = vec![];
+        let n = next_object_with_warnings(&mut source, &mut warnings)
+            .unwrap()
+            .unwrap();
+        let mut expected: Dictionary = Box::default();
+        expected.insert(PdfName::Size, PdfObject::Number(PdfNumber::Integer(1)));
+        expected.insert(PdfName::Root, PdfObject::Null);
+        assert_eq!(n, PdfObject::Dictionary(expected));
+        assert_eq!(
+            warnings,
+            vec![Warning {
+                kind: WarningKind::OddLengthDictionary,
+                offset: 17,
+                reference: None
+            }]
+        );
+    }
 }
@@ -17,13 +17,14 @@ pub fn next_token(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfToken
     let syntax_error = Err(PdfError::InvalidPdf("syntax error"));
     skip_whitespace(source)?;
     match source.getch()? {
-        Some(ch @ 'A'..='Z') | Some(ch @ 'a'..='z') => keyword(source, ch),
         Some(ch @ '+') | Some(ch @ '-') | Some(ch @ '.') | Some(ch @ '0'..='9') => {
             number(source, ch)
         }
         Some('/') => name_or_symbol(source),
         Some('[') => Ok(Some(PdfToken::BeginArray)),
         Some(']') => Ok(Some(PdfToken::EndArray)),
+        Some('{') => Ok(Some(PdfToken::BeginProcedure)),
+        Some('}') => Ok(Some(PdfToken::EndProcedure)),
         Some('(') => string(source),
         Some('<') => match source.getch()? {
             Some('<') => Ok(Some(PdfToken::BeginDictionary)),
@@ -37,33 +38,49 @@ pub fn next_token(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfToken
             Some('>') => Ok(Some(PdfToken::EndDictionary)),
             None | Some(_) => syntax_error,
         },
+        Some(ch) if is_keyword_start(ch) => keyword(source, ch),
         Some(_) => syntax_error,
         None => Ok(None),
     }
 }
 
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, ' ' | '\t' | '\n' | '\r' | '\x0c' | '\0')
+}
+
+fn is_delimiter(ch: char) -> bool {
+    matches!(
+        ch,
+        '(' | ')' | '<' | '>' | '[' | ']' | '{' | '}' | '/' | '%'
+    )
+}
+
+fn is_regular(ch: char) -> bool {
+    !is_whitespace(ch) && !is_delimiter(ch)
+}
+
+// operators start with a letter, except for the ' and " text operators
+fn is_keyword_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '\'' || ch == '"'
+}
+
 fn skip_whitespace(source: &mut Box<dyn Source>) -> crate::Result<()> {
-    let whitespace = [' ', '\t', '\n', '\r', '\x0c'];
     let mut in_comment = false;
     loop {
         let ch = source.getch()?;
         if in_comment {
-            if ch == Some('\n') {
+            if ch == Some('\n') || ch == Some('\r') {
                 in_comment = false;
             }
-        } else {
-            if ch == Some('%') {
-                in_comment = true;
-            } else {
-                if let Some(ch) = ch {
-                    if !whitespace.contains(&ch) {
-                        source.backup();
-                        return Ok(());
-                    }
-                } else {
-                    return Ok(());
-                }
+        } else if ch == Some('%') {
+            in_comment = true;
+        } else if let Some(ch) = ch {
+            if !is_whitespace(ch) {
+                source.backup();
+                return Ok(());
             }
+        } else {
+            return Ok(());
         }
     }
 }
@@ -72,7 +89,7 @@ fn keyword(source: &mut Box<dyn Source>, first: char) -> crate::Result<Option<Pd
     let mut keyword = first.to_string();
     loop {
         match source.getch()? {
-            Some(ch @ 'A'..='Z') | Some(ch @ 'a'..='z') => keyword.push(ch),
+            Some(ch) if is_regular(ch) && ch.is_ascii_graphic() => keyword.push(ch),
             Some(_) => {
                 source.backup();
                 return Ok(Some(PdfToken::Keyword(pdf_keyword(&keyword))));
@@ -92,8 +109,10 @@ fn number(source: &mut Box<dyn Source>, first: char) -> crate::Result<Option<Pdf
                 number.push('.');
                 decimal = true;
             }
-            None | Some(_) => {
-                source.backup();
+            ch => {
+                if ch.is_some() {
+                    source.backup();
+                }
                 if decimal {
                     return Ok(Some(PdfToken::Real(number.parse()?)));
                 } else {
@@ -104,87 +123,105 @@ fn number(source: &mut Box<dyn Source>, first: char) -> crate::Result<Option<Pdf
     }
 }
 
-fn nybble(ch: Option<char>) -> crate::Result<u8> {
+fn nybble(ch: Option<u8>) -> crate::Result<u8> {
     match ch {
-        Some(ch @ '0'..='9') => Ok(ch as u8 - b'0'),
-        Some(ch @ 'A'..='F') => Ok(10 + (ch as u8 - b'A')),
-        Some(ch @ 'a'..='f') => Ok(10 + (ch as u8 - b'a')),
+        Some(ch @ b'0'..=b'9') => Ok(ch - b'0'),
+        Some(ch @ b'A'..=b'F') => Ok(10 + (ch - b'A')),
+        Some(ch @ b'a'..=b'f') => Ok(10 + (ch - b'a')),
         None => Err(PdfError::EndOfFile),
         _ => Err(PdfError::InvalidPdf("invalid hex character")),
     }
 }
 
 fn name_or_symbol(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfToken>> {
-    let mut name = "".to_owned();
+    let mut name = vec![];
     loop {
-        match source.getch()? {
-            None | Some(' ') | Some('\t') | Some('\n') | Some('\r') | Some('\x0c') => {
+        match source.getbyte()? {
+            Some(ch) if !is_regular(ch as char) => {
                 source.backup();
-                return match pdf_name(&name) {
-                    Some(name) => Ok(Some(PdfToken::Name(name))),
-                    None => Ok(Some(PdfToken::Symbol(name.as_bytes().to_vec()))),
-                };
+                return Ok(Some(name_token(name)));
             }
-            Some('#') => {
-                let hi = nybble(source.getch()?)?;
-                let lo = nybble(source.getch()?)?;
-                name.push((hi << 4 | lo) as char);
+            None => return Ok(Some(name_token(name))),
+            Some(b'#') => {
+                let hi = nybble(source.getbyte()?)?;
+                let lo = nybble(source.getbyte()?)?;
+                name.push(hi << 4 | lo);
             }
-            Some(ch @ _) => name.push(ch),
+            Some(ch) => name.push(ch),
         }
     }
 }
 
+fn name_token(name: Vec<u8>) -> PdfToken {
+    match std::str::from_utf8(&name).ok().and_then(pdf_name) {
+        Some(name) => PdfToken::Name(name),
+        None => PdfToken::Symbol(name),
+    }
+}
+
 fn string(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfToken>> {
     let mut nesting = 0;
     let mut string = vec![];
     loop {
-        match source.getch()? {
-            Some('(') => {
+        match source.getbyte()? {
+            Some(b'(') => {
                 string.push(b'(');
                 nesting += 1;
             }
-            None | Some(')') => {
+            Some(b')') => {
                 if nesting == 0 {
                     return Ok(Some(PdfToken::Str(string)));
                 }
                 string.push(b')');
                 nesting -= 1;
             }
-            Some('\\') => match source.getch()? {
-                Some('n') => string.push(b'\n'),
-                Some('r') => string.push(b'\r'),
-                Some('t') => string.push(b'\t'),
-                Some('b') => string.push(0x08),
-                Some('f') => string.push(0x0c),
-                Some('(') => string.push(b'('),
-                Some(')') => string.push(b')'),
-                Some(ch @ '0'..='7') => string.push(octal_escape(source, ch)?),
-                None | Some(_) => {}
+            Some(b'\\') => match source.getbyte()? {
+                Some(b'n') => string.push(b'\n'),
+                Some(b'r') => string.push(b'\r'),
+                Some(b't') => string.push(b'\t'),
+                Some(b'b') => string.push(0x08),
+                Some(b'f') => string.push(0x0c),
+                Some(ch @ b'0'..=b'7') => string.push(octal_escape(source, ch)?),
+                // a backslash at the end of a line continues the string on the next line
+                Some(b'\r') => skip_linefeed(source)?,
+                Some(b'\n') => {}
+                // the backslash is ignored in any other escape sequence
+                Some(ch) => string.push(ch),
+                None => return Err(PdfError::EndOfFile),
             },
-            Some(ch @ _) => string.push(ch as u8),
+            // an end-of-line marker in a string is treated as a single line feed
+            Some(b'\r') => {
+                skip_linefeed(source)?;
+                string.push(b'\n');
+            }
+            Some(ch) => string.push(ch),
+            None => return Err(PdfError::EndOfFile),
         }
     }
 }
 
-fn octal_escape(source: &mut Box<dyn Source>, first: char) -> crate::Result<u8> {
-    let mut octal = first as u8 - b'0';
-    let mut digits = 1;
-    loop {
-        match source.getch()? {
-            Some(ch @ '0'..='7') => {
-                octal = (octal << 3) | (ch as u8 - b'0');
-                digits += 1;
-                if digits == 3 {
-                    return Ok(octal);
-                }
-            }
-            _ => {
+fn skip_linefeed(source: &mut Box<dyn Source>) -> crate::Result<()> {
+    match source.getbyte()? {
+        Some(b'\n') | None => {}
+        Some(_) => source.backup(),
+    }
+    Ok(())
+}
+
+fn octal_escape(source: &mut Box<dyn Source>, first: u8) -> crate::Result<u8> {
+    // high-order overflow is ignored
+    let mut octal = (first - b'0') as u32;
+    for _ in 1..3 {
+        match source.getbyte()? {
+            Some(ch @ b'0'..=b'7') => octal = (octal << 3) | (ch - b'0') as u32,
+            Some(_) => {
                 source.backup();
-                return Ok(octal);
+                break;
             }
+            None => break,
         }
     }
+    Ok(octal as u8)
 }
 
 fn hex_string(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfToken>> {
@@ -193,15 +230,15 @@ fn hex_string(source: &mut Box<dyn Source>) -> crate::Result<Option<PdfToken>> {
     let mut first = false;
     let mut string = vec![];
     loop {
-        match source.getch()? {
-            Some(' ') | Some('\t') | Some('\n') | Some('\r') | Some('\x0c') => {}
-            Some('>') => {
+        match source.getbyte()? {
+            Some(ch) if is_whitespace(ch as char) => continue,
+            Some(b'>') => {
                 if first {
                     string.push(hex << 4);
                 }
                 return Ok(Some(PdfToken::Str(string)));
             }
-            ch @ _ => {
+            ch => {
                 hex = nybble(ch)?;
                 first = !first;
             }
@@ -263,6 +300,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn reals() {
         let mut source: Box<dyn Source> =
             Box::new(ByteSliceSource::new(b"0.0 2030.0 3.1415926 -32. .5"));
@@ -371,8 +409,8 @@ special characters ( * ! & } ^ % and so on ).)
             tok,
             PdfToken::Str(vec![
                 83, 116, 114, 105, 110, 103, 32, 119, 105, 116, 104, 32, 101, 115, 99, 97, 112,
-                101, 115, 58, 32, 10, 32, 13, 32, 9, 32, 8, 32, 12, 32, 40, 32, 41, 32, 32, 0, 32,
-                8, 32, 64, 32, 83, 52
+                101, 115, 58, 32, 10, 32, 13, 32, 9, 32, 8, 32, 12, 32, 40, 32, 41, 32, 92, 32, 0,
+                32, 8, 32, 64, 32, 83, 52
             ])
         );
     }
@@ -394,6 +432,71 @@ special characters ( * ! & } ^ % and so on ).)
         assert_eq!(tok, PdfToken::Str(vec![0xca, 0xfe, 0xba, 0xbe]));
     }
 
+    #[test]
+    fn string_conformance() {
+        let cases: &[(&[u8], &[u8])] = &[
+            (b"(abc)", b"abc"),
+            (b"(a(b)c)", b"a(b)c"),
+            (b"(a\\(b)", b"a(b"),
+            (b"(a\\)b)", b"a)b"),
+            (b"(a\\\\b)", b"a\\b"),
+            (b"(\\q\\/\\%)", b"q/%"),
+            (b"(line\\\ncontinued)", b"linecontinued"),
+            (b"(line\\\rcontinued)", b"linecontinued"),
+            (b"(line\\\r\ncontinued)", b"linecontinued"),
+            (b"(a\rb)", b"a\nb"),
+            (b"(a\r\nb)", b"a\nb"),
+            (b"(a\nb)", b"a\nb"),
+            (b"(a\n\rb)", b"a\n\nb"),
+            (b"(a\r\rb)", b"a\n\nb"),
+            (b"(\\0053)", b"\x053"),
+            (b"(\\53)", b"+"),
+            (b"(\\053)", b"+"),
+            (b"(\\7a)", b"\x07a"),
+            (b"(\\777)", b"\xff"),
+            (b"(\xe9\x80\xff\x00)", b"\xe9\x80\xff\x00"),
+            (b"(\\\xe9)", b"\xe9"),
+            (b"(%not a comment)", b"%not a comment"),
+        ];
+        for (input, expected) in cases.iter() {
+            let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(input));
+            assert_eq!(next(&mut source), PdfToken::Str(expected.to_vec()));
+            assert_eq!(next_token(&mut source).unwrap(), None);
+        }
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"(unterminated"));
+        assert!(next_token(&mut source).is_err());
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"(escape\\"));
+        assert!(next_token(&mut source).is_err());
+    }
+
+    #[test]
+    fn hex_string_conformance() {
+        let cases: &[(&[u8], &[u8])] = &[
+            (b"<>", b""),
+            (b"<901FA3>", b"\x90\x1f\xa3"),
+            (b"<901FA>", b"\x90\x1f\xa0"),
+            (b"<90 1F\r\nA3>", b"\x90\x1f\xa3"),
+            (b"<9 0 1 F a 3>", b"\x90\x1f\xa3"),
+            (b"<\x00ff\t00\x0c>", b"\xff\x00"),
+        ];
+        for (input, expected) in cases.iter() {
+            let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(input));
+            assert_eq!(next(&mut source), PdfToken::Str(expected.to_vec()));
+            assert_eq!(next_token(&mut source).unwrap(), None);
+        }
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"<12xz>"));
+        assert!(next_token(&mut source).is_err());
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"<1234"));
+        assert!(next_token(&mut source).is_err());
+    }
+
+    #[test]
+    fn names_with_high_bytes() {
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"/Caf#e9 /Fran\xe7ais"));
+        assert_eq!(next(&mut source), PdfToken::Symbol(b"Caf\xe9".to_vec()));
+        assert_eq!(next(&mut source), PdfToken::Symbol(b"Fran\xe7ais".to_vec()));
+    }
+
     #[test]
     fn structures() {
         let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"<< >> [ ]"));
@@ -406,4 +509,82 @@ special characters ( * ! & } ^ % and so on ).)
         let tok = next(&mut source);
         assert_eq!(tok, PdfToken::EndArray);
     }
+
+    #[test]
+    fn delimiters() {
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(
+            b"/Type/Page/F1[/Name(x)/A<</B<ab>>>]{1 add}12/Xyz 3.5(y)",
+        ));
+        assert_eq!(next(&mut source), PdfToken::Name(PdfName::Type));
+        assert_eq!(next(&mut source), PdfToken::Name(PdfName::Page));
+        assert_eq!(next(&mut source), PdfToken::Symbol(b"F1".to_vec()));
+        assert_eq!(next(&mut source), PdfToken::BeginArray);
+        assert_eq!(next(&mut source), PdfToken::Symbol(b"Name".to_vec()));
+        assert_eq!(next(&mut source), PdfToken::Str(b"x".to_vec()));
+        assert_eq!(next(&mut source), PdfToken::Symbol(b"A".to_vec()));
+        assert_eq!(next(&mut source), PdfToken::BeginDictionary);
+        assert_eq!(next(&mut source), PdfToken::Name(PdfName::B));
+        assert_eq!(next(&mut source), PdfToken::Str(vec![0xab]));
+        assert_eq!(next(&mut source), PdfToken::EndDictionary);
+        assert_eq!(next(&mut source), PdfToken::EndArray);
+        assert_eq!(next(&mut source), PdfToken::BeginProcedure);
+        assert_eq!(next(&mut source), PdfToken::Integer(1));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::add));
+        assert_eq!(next(&mut source), PdfToken::EndProcedure);
+        assert_eq!(next(&mut source), PdfToken::Integer(12));
+        assert_eq!(next(&mut source), PdfToken::Symbol(b"Xyz".to_vec()));
+        assert_eq!(next(&mut source), PdfToken::Real(3.5));
+        assert_eq!(next(&mut source), PdfToken::Str(b"y".to_vec()));
+        assert_eq!(next_token(&mut source).unwrap(), None);
+    }
+
+    #[test]
+    fn operator_keywords() {
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(
+            b"0 0 1 rg d0 T* ' \" W*(z)Tj%comment\rf*",
+        ));
+        assert_eq!(next(&mut source), PdfToken::Integer(0));
+        assert_eq!(next(&mut source), PdfToken::Integer(0));
+        assert_eq!(next(&mut source), PdfToken::Integer(1));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::rg));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::d0));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::Tstar));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::apos));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::quote));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::Wstar));
+        assert_eq!(next(&mut source), PdfToken::Str(b"z".to_vec()));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::Tj));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::fstar));
+        assert_eq!(next_token(&mut source).unwrap(), None);
+    }
+
+    #[test]
+    fn tokens_at_end_of_stream() {
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"/Root 12 ET"));
+        assert_eq!(next(&mut source), PdfToken::Name(PdfName::Root));
+        assert_eq!(next(&mut source), PdfToken::Integer(12));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::ET));
+        assert_eq!(next_token(&mut source).unwrap(), None);
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"12"));
+        assert_eq!(next(&mut source), PdfToken::Integer(12));
+        assert_eq!(next_token(&mut source).unwrap(), None);
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"/Size"));
+        assert_eq!(next(&mut source), PdfToken::Name(PdfName::Size));
+        assert_eq!(next_token(&mut source).unwrap(), None);
+    }
+
+    #[test]
+    fn garbage_bytes() {
+        for garbage in [&b")"[..], b"\x80", b"\x01", b"@", b"1 0 obj ~"] {
+            let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(garbage));
+            let mut result = next_token(&mut source);
+            while let Ok(Some(_)) = result {
+                result = next_token(&mut source);
+            }
+            assert!(result.is_err());
+        }
+        let mut source: Box<dyn Source> = Box::new(ByteSliceSource::new(b"Tj\xff"));
+        assert_eq!(next(&mut source), PdfToken::Keyword(PdfKeyword::Tj));
+        assert!(next_token(&mut source).is_err());
+    }
 }
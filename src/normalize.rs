@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::layout::{analyze, LayoutParams};
+use crate::pdf_document::PdfDocument;
+use crate::rect::Rect;
+use crate::text_runs::{text_runs, TextRun};
+
+// how far apart, as a fraction of its height, a glyph can be drawn over an earlier copy of
+// itself and still count as an overprint
+const OVERPRINT_TOLERANCE: f64 = 0.15;
+
+const SOFT_HYPHEN: char = '\u{ad}';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalizationForm {
+    Nfc,
+    Nfkc,
+}
+
+// the clean-up steps applied to extracted text, each of which can be turned off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizeOptions {
+    // replaces the ligatures U+FB00 to U+FB06 with their letters
+    pub expand_ligatures: bool,
+    pub remove_soft_hyphens: bool,
+    // joins words hyphenated at the end of a line, see dehyphenate
+    pub dehyphenate: bool,
+    // drops glyphs drawn again over themselves, which some producers do to fake bold text
+    pub remove_overprint: bool,
+    // drops U+FFFD, which stands for glyphs that couldn't be decoded, and private use
+    // characters, which fonts map glyphs without a Unicode value to
+    pub remove_unmapped: bool,
+    pub form: Option<NormalizationForm>,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> NormalizeOptions {
+        NormalizeOptions {
+            expand_ligatures: true,
+            remove_soft_hyphens: true,
+            dehyphenate: true,
+            remove_overprint: true,
+            remove_unmapped: true,
+            form: Some(NormalizationForm::Nfc),
+        }
+    }
+}
+
+fn ligature(ch: char) -> Option<&'static str> {
+    match ch {
+        '\u{fb00}' => Some("ff"),
+        '\u{fb01}' => Some("fi"),
+        '\u{fb02}' => Some("fl"),
+        '\u{fb03}' => Some("ffi"),
+        '\u{fb04}' => Some("ffl"),
+        '\u{fb05}' | '\u{fb06}' => Some("st"),
+        _ => None,
+    }
+}
+
+fn is_unmapped(ch: char) -> bool {
+    matches!(ch,
+        '\u{fffd}' | '\u{e000}'..='\u{f8ff}' | '\u{f0000}'..='\u{ffffd}' | '\u{100000}'..='\u{10fffd}')
+}
+
+// applies the steps which work on single glyphs
+fn normalize_glyph(text: &str, options: &NormalizeOptions) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ligature(ch) {
+            Some(letters) if options.expand_ligatures => normalized.push_str(letters),
+            _ if options.remove_unmapped && is_unmapped(ch) => {}
+            _ => normalized.push(ch),
+        }
+    }
+    normalized
+}
+
+fn is_overprint(a: &Rect, b: &Rect) -> bool {
+    let tolerance = OVERPRINT_TOLERANCE * a.height().min(b.height());
+    (a.x0 - b.x0).abs() <= tolerance && (a.y0 - b.y0).abs() <= tolerance
+}
+
+// cleans up the glyphs of text runs before layout analysis, so words and search hits aren't
+// broken up by them
+//
+// Overprinted and unmapped glyphs are removed, along with runs left empty, and ligatures are
+// expanded in place, keeping their bounding boxes. The other steps need whole lines and are
+// left to normalize_text.
+pub fn normalize_runs(runs: &mut Vec<TextRun>, options: &NormalizeOptions) {
+    let mut shown: HashMap<String, Vec<Rect>> = HashMap::new();
+    for run in runs.iter_mut() {
+        run.glyphs.retain_mut(|glyph| {
+            glyph.text = normalize_glyph(&glyph.text, options);
+            if glyph.text.is_empty() {
+                return false;
+            }
+            if !options.remove_overprint || glyph.text.trim().is_empty() {
+                return true;
+            }
+            let boxes = shown.entry(glyph.text.clone()).or_default();
+            if boxes
+                .iter()
+                .any(|bbox| is_overprint(bbox, &glyph.page_bbox))
+            {
+                return false;
+            }
+            boxes.push(glyph.page_bbox);
+            true
+        });
+        run.text = run.glyphs.iter().map(|glyph| glyph.text.as_str()).collect();
+        if let Some((first, rest)) = run.glyphs.split_first() {
+            run.bbox = rest
+                .iter()
+                .fold(first.bbox, |bbox, glyph| bbox.union(&glyph.bbox));
+            run.page_bbox = rest
+                .iter()
+                .fold(first.page_bbox, |bbox, glyph| bbox.union(&glyph.page_bbox));
+        }
+    }
+    runs.retain(|run| !run.glyphs.is_empty());
+}
+
+// joins words split by a hyphen or soft hyphen at the end of a line, when the line continues
+// with a lowercase letter, moving the rest of the word up to the end of the line and dropping
+// the next line if that was all of it
+//
+// Hyphens in compound words broken at a line end are removed too, since they can't be told
+// apart from hyphenation.
+pub fn dehyphenate(text: &str) -> String {
+    let mut lines: Vec<String> = text.split('\n').map(|line| line.to_owned()).collect();
+    let mut emptied = vec![false; lines.len()];
+    for i in 1..lines.len() {
+        let (before, after) = lines.split_at_mut(i);
+        let line = &mut before[i - 1];
+        let next = &mut after[0];
+        let mut chars = line.chars().rev();
+        let hyphenated = matches!(chars.next(), Some('-' | '\u{2010}' | SOFT_HYPHEN))
+            && chars.next().is_some_and(char::is_alphabetic)
+            && next.chars().next().is_some_and(char::is_lowercase);
+        if !hyphenated {
+            continue;
+        }
+        line.pop();
+        let end = next.find(char::is_whitespace).unwrap_or(next.len());
+        line.push_str(&next[..end]);
+        *next = next[end..].trim_start().to_owned();
+        emptied[i] = next.is_empty();
+    }
+    let lines: Vec<String> = lines
+        .into_iter()
+        .zip(emptied)
+        .filter(|(_, emptied)| !emptied)
+        .map(|(line, _)| line)
+        .collect();
+    lines.join("\n")
+}
+
+// applies the steps which work on text, in the order dehyphenation, soft hyphen removal,
+// ligature expansion, unmapped character removal and Unicode normalization
+pub fn normalize_text(text: &str, options: &NormalizeOptions) -> String {
+    let mut text = if options.dehyphenate {
+        dehyphenate(text)
+    } else {
+        text.to_owned()
+    };
+    if options.remove_soft_hyphens {
+        text.retain(|ch| ch != SOFT_HYPHEN);
+    }
+    text = normalize_glyph(&text, options);
+    match options.form {
+        Some(NormalizationForm::Nfc) => text.nfc().collect(),
+        Some(NormalizationForm::Nfkc) => text.nfkc().collect(),
+        None => text,
+    }
+}
+
+// the text of a page in reading order, see PageLayout::text, cleaned up by normalize_runs and
+// normalize_text
+pub fn normalized_text(
+    document: &mut PdfDocument,
+    pageno: u32,
+    params: &LayoutParams,
+    options: &NormalizeOptions,
+) -> crate::Result<String> {
+    let mut runs = text_runs(document, pageno)?;
+    normalize_runs(&mut runs, options);
+    Ok(normalize_text(&analyze(&runs, params).text(), options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics_state::Color;
+    use crate::test_files;
+    use crate::text_runs::Glyph;
+
+    fn run(text: &str, x: f64, y: f64) -> TextRun {
+        let glyphs: Vec<Glyph> = text
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| {
+                let x0 = x + 6.0 * i as f64;
+                let bbox = Rect::new(x0, y, x0 + 6.0, y + 10.0);
+                Glyph {
+                    text: ch.to_string(),
+                    bbox,
+                    page_bbox: bbox,
+                }
+            })
+            .collect();
+        TextRun {
+            text: text.to_owned(),
+            base_font: "Helvetica".to_owned(),
+            font_size: 10.0,
+            size: 10.0,
+            fill_color: Color::default(),
+            render_mode: 0,
+            angle: 0.0,
+            bbox: Rect::new(x, y, x + 6.0 * glyphs.len() as f64, y + 10.0),
+            page_bbox: Rect::new(x, y, x + 6.0 * glyphs.len() as f64, y + 10.0),
+            glyphs,
+        }
+    }
+
+    #[test]
+    fn overprinted_runs() {
+        let options = NormalizeOptions::default();
+        // bold faked by drawing the text again half a point to the right
+        let mut runs = vec![
+            run("Bold", 100.0, 700.0),
+            run("Bold", 100.5, 700.0),
+            run("ll\u{fb01}\u{e001}", 100.0, 680.0),
+        ];
+        normalize_runs(&mut runs, &options);
+        let texts: Vec<&str> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, vec!["Bold", "llfi"]);
+        assert_eq!(runs[1].glyphs[2].text, "fi");
+        assert_eq!(runs[1].page_bbox.x1, 118.0);
+        let options = NormalizeOptions {
+            remove_overprint: false,
+            ..NormalizeOptions::default()
+        };
+        let mut runs = vec![run("Bold", 100.0, 700.0), run("Bold", 100.5, 700.0)];
+        normalize_runs(&mut runs, &options);
+        assert_eq!(runs.len(), 2);
+    }
+
+    #[test]
+    fn text_steps() {
+        let options = NormalizeOptions::default();
+        let text =
+            "e\u{301}\u{fb03}cient com-\npile well\nsoft\u{ad}\nware\nFoo-\nBar\n\nnon-\n\nend-";
+        assert_eq!(
+            normalize_text(text, &options),
+            "\u{e9}fficient compile\nwell\nsoftware\nFoo-\nBar\n\nnon-\n\nend-"
+        );
+        let options = NormalizeOptions {
+            dehyphenate: false,
+            form: Some(NormalizationForm::Nfkc),
+            ..NormalizeOptions::default()
+        };
+        assert_eq!(
+            normalize_text("com-\npile x\u{b2} \u{fffd}", &options),
+            "com-\npile x2 "
+        );
+        let options = NormalizeOptions {
+            expand_ligatures: false,
+            remove_soft_hyphens: false,
+            dehyphenate: false,
+            remove_overprint: false,
+            remove_unmapped: false,
+            form: None,
+        };
+        let text = "\u{fb01}soft\u{ad}ware\u{e001}";
+        assert_eq!(normalize_text(text, &options), text);
+    }
+
+    #[test]
+    fn tracemonkey_pdf_normalized_text() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let params = LayoutParams::default();
+        let text =
+            normalized_text(&mut document, 0, &params, &NormalizeOptions::default()).unwrap();
+        assert!(text.contains("more difficult to compile\nthan"));
+        assert!(!text.contains('\u{fb01}'));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::page_contents::PageContents;
+use crate::pdf_types::*;
+use crate::PdfError;
+
+// a content stream operator together with its operands
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub operator: PdfKeyword,
+    pub operands: Vec<PdfObject>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exactly(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/codegen_arities.rs"));
+
+// iterates over the operations in a content stream
+//
+// Operators which aren't known content stream operators (PdfKeyword::Unknown, for example)
+// are returned without checking their operands. Iteration stops after a syntax error.
+pub struct Operations {
+    contents: PageContents,
+    done: bool,
+}
+
+impl Operations {
+    pub fn new(contents: PageContents) -> Operations {
+        Operations {
+            contents,
+            done: false,
+        }
+    }
+
+    fn next_operation(&mut self) -> crate::Result<Option<Operation>> {
+        let mut operands = vec![];
+        loop {
+            match self.contents.next_object()? {
+                Some(PdfObject::Keyword(operator)) => return operation(operator, operands),
+                Some(image @ PdfObject::InlineImage(_)) => {
+                    operands.push(image);
+                    return operation(PdfKeyword::BI, operands);
+                }
+                Some(operand) => operands.push(operand),
+                None if operands.is_empty() => return Ok(None),
+                None => return Err(PdfError::InvalidPdf("operands without an operator")),
+            }
+        }
+    }
+}
+
+fn operation(operator: PdfKeyword, operands: Vec<PdfObject>) -> crate::Result<Option<Operation>> {
+    match arity(&operator) {
+        Some(arity) if !arity.accepts(operands.len()) => Err(PdfError::InvalidOperandCount {
+            operator,
+            found: operands.len(),
+        }),
+        _ => Ok(Some(Operation { operator, operands })),
+    }
+}
+
+impl Iterator for Operations {
+    type Item = crate::Result<Operation>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_operation() {
+            Ok(Some(operation)) => Some(Ok(operation)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                // the operands were consumed, so a bad operand count doesn't stop the iteration
+                if !matches!(error, PdfError::InvalidOperandCount { .. }) {
+                    self.done = true;
+                }
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operations(contents: &[u8]) -> Operations {
+        PageContents::new(contents.to_vec()).operations()
+    }
+
+    fn number(n: i64) -> PdfObject {
+        PdfObject::Number(PdfNumber::Integer(n))
+    }
+
+    #[test]
+    fn simple_operations() {
+        let ops: Vec<_> = operations(b"q 1 0 0 1 10 20 cm BT /F1 12 Tf (Hi) Tj ET Q")
+            .map(|op| op.unwrap())
+            .collect();
+        assert_eq!(ops.len(), 7);
+        assert_eq!(
+            ops[1],
+            Operation {
+                operator: PdfKeyword::cm,
+                operands: vec![
+                    number(1),
+                    number(0),
+                    number(0),
+                    number(1),
+                    number(10),
+                    number(20)
+                ]
+            }
+        );
+        assert_eq!(
+            ops[3],
+            Operation {
+                operator: PdfKeyword::Tf,
+                operands: vec![PdfObject::Symbol(b"F1".to_vec()), number(12)]
+            }
+        );
+        let operators: Vec<_> = ops.iter().map(|op| op.operator.clone()).collect();
+        assert_eq!(
+            operators,
+            vec![
+                PdfKeyword::q,
+                PdfKeyword::cm,
+                PdfKeyword::BT,
+                PdfKeyword::Tf,
+                PdfKeyword::Tj,
+                PdfKeyword::ET,
+                PdfKeyword::Q
+            ]
+        );
+    }
+
+    #[test]
+    fn arities() {
+        assert_eq!(arity(&PdfKeyword::cm), Some(Arity::Exactly(6)));
+        assert_eq!(arity(&PdfKeyword::Tstar), Some(Arity::Exactly(0)));
+        assert_eq!(arity(&PdfKeyword::quote), Some(Arity::Exactly(3)));
+        assert_eq!(arity(&PdfKeyword::scn), Some(Arity::AtLeast(1)));
+        // ID and EI are part of an inline image, f is also an xref keyword
+        assert_eq!(arity(&PdfKeyword::ID), None);
+        assert_eq!(arity(&PdfKeyword::f), Some(Arity::Exactly(0)));
+        assert_eq!(arity(&PdfKeyword::add), None);
+        assert_eq!(arity(&PdfKeyword::Unknown), None);
+    }
+
+    #[test]
+    fn variable_arity() {
+        let ops: Vec<_> = operations(b"/P0 scn 0.5 SC 1 0 0 sc /Pattern cs 0.1 0.2 0.3 /P1 SCN")
+            .map(|op| op.unwrap().operands.len())
+            .collect();
+        assert_eq!(ops, vec![1, 1, 3, 1, 4]);
+    }
+
+    #[test]
+    fn wrong_arity() {
+        let mut ops = operations(b"1 2 re 10 w q 5 Q f");
+        assert!(matches!(
+            ops.next(),
+            Some(Err(PdfError::InvalidOperandCount {
+                operator: PdfKeyword::re,
+                found: 2
+            }))
+        ));
+        assert_eq!(ops.next().unwrap().unwrap().operator, PdfKeyword::w);
+        assert_eq!(ops.next().unwrap().unwrap().operator, PdfKeyword::q);
+        assert!(ops.next().unwrap().is_err());
+        assert_eq!(ops.next().unwrap().unwrap().operator, PdfKeyword::f);
+        assert!(ops.next().is_none());
+    }
+
+    #[test]
+    fn unknown_operators() {
+        let ops: Vec<_> = operations(b"BX 1 2 3 foo EX")
+            .map(|op| op.unwrap())
+            .collect();
+        assert_eq!(ops.len(), 3);
+        assert_eq!(
+            ops[1],
+            Operation {
+                operator: PdfKeyword::Unknown,
+                operands: vec![number(1), number(2), number(3)]
+            }
+        );
+    }
+
+    #[test]
+    fn inline_image() {
+        let ops: Vec<_> = operations(b"q BI /W 1 /H 1 /BPC 8 /CS /G ID \xff EI Q")
+            .map(|op| op.unwrap())
+            .collect();
+        assert_eq!(ops.len(), 3);
+        assert_eq!(ops[1].operator, PdfKeyword::BI);
+        assert!(matches!(ops[1].operands[0], PdfObject::InlineImage(_)));
+    }
+
+    #[test]
+    fn trailing_operands() {
+        let mut ops = operations(b"q 1 2");
+        assert!(ops.next().unwrap().is_ok());
+        assert!(ops.next().unwrap().is_err());
+        assert!(ops.next().is_none());
+    }
+}
@@ -1,19 +1,47 @@
+use crate::inline_image::inline_image;
 use crate::next_object::next_object;
+use crate::operations::Operations;
 use crate::pdf_source::{ByteSource, Source};
 use crate::pdf_types::*;
 
 pub struct PageContents {
     source: Box<dyn Source>,
+    reference: Option<Reference>,
 }
 
 impl PageContents {
     pub fn new(contents: Vec<u8>) -> PageContents {
         PageContents {
             source: Box::new(ByteSource::new(contents)),
+            reference: None,
+        }
+    }
+
+    // error locations are offsets into the decoded stream identified by reference
+    pub(crate) fn with_reference(contents: Vec<u8>, reference: Reference) -> PageContents {
+        PageContents {
+            source: Box::new(ByteSource::new(contents)),
+            reference: Some(reference),
         }
     }
 
     pub fn next_object(&mut self) -> crate::Result<Option<PdfObject>> {
-        next_object(&mut self.source)
+        let reference = self.reference;
+        self.read_object()
+            .map_err(|error| error.locate(&mut self.source, reference))
+    }
+
+    // groups the objects into operators and their operands
+    pub fn operations(self) -> Operations {
+        Operations::new(self)
+    }
+
+    fn read_object(&mut self) -> crate::Result<Option<PdfObject>> {
+        match next_object(&mut self.source)? {
+            Some(PdfObject::Keyword(PdfKeyword::BI)) => Ok(Some(PdfObject::InlineImage(Box::new(
+                inline_image(&mut self.source)?,
+            )))),
+            obj => Ok(obj),
+        }
     }
 }
@@ -1,14 +1,28 @@
 use std::io::{Read, SeekFrom};
 
 use crate::dictionary::Access;
-use crate::next_object::{need_dictionary, need_keyword, need_u32, next_object};
+use crate::matrix::Matrix;
+use crate::next_object::{
+    need_dictionary, need_keyword, need_u32, next_object, next_object_with_warnings,
+};
 use crate::page_contents::PageContents;
 use crate::pdf_source::Source;
 use crate::pdf_types::*;
+use crate::rect::Rect;
 use crate::streams::decode_stream;
+use crate::warnings::{Warning, WarningKind};
 use crate::PdfError;
 
 const FREE_GEN: u16 = 0xffff;
+const MAX_INDIRECTION: usize = 32;
+
+// page attributes which pages inherit from their ancestors in the page tree
+const INHERITABLE: [PdfName; 4] = [
+    PdfName::Resources,
+    PdfName::MediaBox,
+    PdfName::CropBox,
+    PdfName::Rotate,
+];
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct XRefEntry {
@@ -16,30 +30,48 @@ pub struct XRefEntry {
     position: u64,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseMode {
+    // any deviation from the PDF specification is an error, except a wrong stream /Length
+    Strict,
+    // recover from common spec violations, recording a warning for each
+    Lenient,
+}
+
 pub struct PdfDocument {
     source: Box<dyn Source>,
+    mode: ParseMode,
+    warnings: Vec<Warning>,
     xref: Vec<XRefEntry>,
     pages: Vec<Dictionary>,
 }
 
 impl PdfDocument {
-    pub fn new(mut source: Box<dyn Source>) -> crate::Result<PdfDocument> {
+    pub fn new(source: Box<dyn Source>) -> crate::Result<PdfDocument> {
+        PdfDocument::with_mode(source, ParseMode::Strict)
+    }
+
+    pub fn with_mode(mut source: Box<dyn Source>, mode: ParseMode) -> crate::Result<PdfDocument> {
         PdfDocument::validate_pdf(&mut source)?;
         let (position, buffer) = PdfDocument::read_tail(&mut source)?;
         let trailer_position = find_trailer(position, &buffer)?;
         let mut document = PdfDocument {
-            source: source,
+            source,
+            mode,
+            warnings: vec![],
             xref: vec![],
             pages: vec![],
         };
         document.source.seek(SeekFrom::Start(trailer_position))?;
-        let (trailer_dict, startxref) = PdfDocument::read_trailer(&mut document.source)?;
+        let trailer = PdfDocument::read_trailer(&mut document.source);
+        let (trailer_dict, startxref) = document.located(trailer, None)?;
         document.source.seek(SeekFrom::Start(startxref))?;
         let size = match trailer_dict.get_u32(PdfName::Size) {
             Some(s) => s,
             _ => return Err(PdfError::InvalidPdf("Size missing in trailer")),
         };
-        document.read_xref(size)?;
+        let xref = document.read_xref(size);
+        document.located(xref, None)?;
         let catalog_ref = match trailer_dict.get_reference(PdfName::Root) {
             Some(r) => r,
             _ => return Err(PdfError::InvalidPdf("Root missing from trailer")),
@@ -56,14 +88,125 @@ impl PdfDocument {
         Ok(document)
     }
 
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    // problems recovered from so far, only wrong stream lengths in strict mode
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
     pub fn page_count(&self) -> u32 {
         self.pages.len() as u32
     }
 
+    // follows references until a direct object is found, a stream resolves to its dictionary
+    pub fn resolve(&mut self, object: PdfObject) -> crate::Result<PdfObject> {
+        let mut object = object;
+        for _ in 0..MAX_INDIRECTION {
+            match object {
+                PdfObject::Reference(reference) => {
+                    self.seek_reference(reference)?;
+                    object = self.read_object(reference)?;
+                }
+                object => return Ok(object),
+            }
+        }
+        Err(PdfError::InvalidPdf("too many levels of indirection"))
+    }
+
+    // the page's resource dictionary, which may be inherited from the page tree
+    pub fn page_resources(&mut self, pageno: u32) -> crate::Result<Dictionary> {
+        let resources = match self.pages.get(pageno as usize) {
+            Some(page_dict) => page_dict.get(&PdfName::Resources).cloned(),
+            None => return Err(PdfError::InvalidPageNumber),
+        };
+        match resources {
+            Some(resources) => match self.resolve(resources)? {
+                PdfObject::Dictionary(dict) => Ok(dict),
+                _ => Err(PdfError::InvalidPdf("invalid page resources")),
+            },
+            None => Ok(Box::default()),
+        }
+    }
+
+    // the visible region of the page in default user space, its /CropBox or else its /MediaBox
+    pub fn page_box(&mut self, pageno: u32) -> crate::Result<Rect> {
+        let page_dict = match self.pages.get(pageno as usize) {
+            Some(page_dict) => page_dict.clone(),
+            None => return Err(PdfError::InvalidPageNumber),
+        };
+        for key in [PdfName::CropBox, PdfName::MediaBox].iter() {
+            if let Some(rect) = page_dict.get(key) {
+                if let PdfObject::Array(array) = self.resolve(rect.clone())? {
+                    if let Some(rect) = Rect::from_objects(&array) {
+                        return Ok(rect);
+                    }
+                }
+            }
+        }
+        // US Letter, when the required /MediaBox is missing
+        Ok(Rect::new(0.0, 0.0, 612.0, 792.0))
+    }
+
+    // the page's /Rotate entry, the number of degrees it is turned clockwise when displayed:
+    // 0, 90, 180 or 270
+    pub fn page_rotation(&mut self, pageno: u32) -> crate::Result<i32> {
+        let rotate = match self.pages.get(pageno as usize) {
+            Some(page_dict) => page_dict.get(&PdfName::Rotate).cloned(),
+            None => return Err(PdfError::InvalidPageNumber),
+        };
+        let rotate = match rotate {
+            Some(rotate) => self.resolve(rotate)?.as_f64().unwrap_or(0.0) as i32,
+            None => 0,
+        };
+        Ok(rotate.rem_euclid(360) / 90 * 90)
+    }
+
+    // the width and height of the page as displayed, in page space
+    pub fn page_size(&mut self, pageno: u32) -> crate::Result<(f64, f64)> {
+        let page_box = self.page_box(pageno)?;
+        Ok(match self.page_rotation(pageno)? {
+            90 | 270 => (page_box.height(), page_box.width()),
+            _ => (page_box.width(), page_box.height()),
+        })
+    }
+
+    // the transformation from default user space to page space, where the page is upright as
+    // displayed with the lower left corner of its visible region at the origin
+    pub fn page_space(&mut self, pageno: u32) -> crate::Result<Matrix> {
+        let page_box = self.page_box(pageno)?;
+        let (width, height) = (page_box.width(), page_box.height());
+        let rotation = match self.page_rotation(pageno)? {
+            90 => Matrix::new(0.0, -1.0, 1.0, 0.0, 0.0, width),
+            180 => Matrix::new(-1.0, 0.0, 0.0, -1.0, width, height),
+            270 => Matrix::new(0.0, 1.0, -1.0, 0.0, height, 0.0),
+            _ => Matrix::identity(),
+        };
+        Ok(Matrix::translate(-page_box.x0, -page_box.y0).multiply(&rotation))
+    }
+
+    // the decoded data of the stream object identified by reference
+    pub fn stream_data(&mut self, reference: Reference) -> crate::Result<Vec<u8>> {
+        self.read_stream(reference)
+    }
+
+    // the content of any content stream, e.g. a form XObject, an annotation's /AP /N appearance
+    // stream, a Type3 glyph procedure from /CharProcs or a tiling pattern cell
+    pub fn stream_contents(&mut self, reference: Reference) -> crate::Result<PageContents> {
+        let contents = self.read_stream(reference)?;
+        Ok(PageContents::with_reference(contents, reference))
+    }
+
     pub fn page_contents(&mut self, pageno: u32) -> crate::Result<PageContents> {
         if pageno < self.pages.len() as u32 {
             let page_dict = self.pages[pageno as usize].clone();
-            Ok(PageContents::new(self.contents(&page_dict)?))
+            let contents = self.contents(&page_dict)?;
+            Ok(match page_dict.get_reference(PdfName::Contents) {
+                Some(reference) => PageContents::with_reference(contents, reference),
+                None => PageContents::new(contents),
+            })
         } else {
             Err(PdfError::InvalidPageNumber)
         }
@@ -75,8 +218,7 @@ impl PdfDocument {
         source.seek(SeekFrom::Start(0))?;
         let expected_header = "%PDF-1.";
         let mut buffer = [0; 7];
-        source.read(&mut buffer)?;
-        if buffer != expected_header.as_bytes() {
+        if source.read_exact(&mut buffer).is_err() || buffer != expected_header.as_bytes() {
             return Err(PdfError::InvalidPdf("bad pdf header"));
         }
         Ok(())
@@ -161,6 +303,14 @@ impl PdfDocument {
 
     fn read_pages(&mut self, pages_node: &mut Dictionary) -> crate::Result<Vec<Dictionary>> {
         let mut pages = vec![];
+        let inherited: Vec<_> = INHERITABLE
+            .iter()
+            .filter_map(|key| {
+                pages_node
+                    .get(key)
+                    .map(|value| (key.clone(), value.clone()))
+            })
+            .collect();
         let kids = match pages_node.get_array(PdfName::Kids) {
             Some(a) => a,
             _ => return Err(PdfError::InvalidPdf("Kids missing from pages node")),
@@ -168,8 +318,11 @@ impl PdfDocument {
         for kid in kids.iter() {
             match kid {
                 PdfObject::Reference(r) => {
-                    self.seek_reference(r.clone())?;
-                    let mut dict = self.read_dictionary(r.clone())?;
+                    self.seek_reference(*r)?;
+                    let mut dict = self.read_dictionary(*r)?;
+                    for (key, value) in inherited.iter() {
+                        dict.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
                     match dict.get_name(PdfName::Type) {
                         Some(ref name) if *name == PdfName::Pages => {
                             pages.append(&mut self.read_pages(&mut dict)?);
@@ -186,30 +339,125 @@ impl PdfDocument {
         Ok(pages)
     }
 
-    fn read_object(&mut self, reference: Reference) -> crate::Result<PdfObject> {
+    // attaches the current source position and the object being read to a parse error
+    fn located<T>(
+        &mut self,
+        result: crate::Result<T>,
+        reference: Option<Reference>,
+    ) -> crate::Result<T> {
+        result.map_err(|error| error.locate(&mut self.source, reference))
+    }
+
+    fn lenient(&self) -> bool {
+        self.mode == ParseMode::Lenient
+    }
+
+    fn warn(&mut self, kind: WarningKind, offset: u64, reference: Option<Reference>) {
+        self.warnings.push(Warning {
+            kind,
+            offset,
+            reference,
+        });
+    }
+
+    // keeps warnings reported by the object parser, in lenient mode
+    fn add_warnings(&mut self, warnings: Vec<Warning>, reference: Option<Reference>) {
+        if self.lenient() {
+            for warning in warnings.into_iter() {
+                self.warn(
+                    warning.kind,
+                    warning.offset,
+                    warning.reference.or(reference),
+                );
+            }
+        }
+    }
+
+    // reads "<id> <gen> obj"
+    fn object_header(&mut self, reference: Reference) -> crate::Result<()> {
         need_u32(&mut self.source, reference.id)?;
-        need_u32(&mut self.source, reference.gen as u32)?;
-        need_keyword(&mut self.source, PdfKeyword::obj)?;
-        match next_object(&mut self.source)? {
+        if self.lenient() {
+            match next_object(&mut self.source)? {
+                Some(PdfObject::Number(PdfNumber::Integer(gen))) => {
+                    if gen != reference.gen as i64 {
+                        let offset = self.source.seek(SeekFrom::Current(0))?;
+                        let kind = WarningKind::GenerationMismatch {
+                            expected: reference.gen,
+                            found: gen,
+                        };
+                        self.warn(kind, offset, Some(reference));
+                    }
+                }
+                _ => return Err(PdfError::InvalidReferenceTarget),
+            }
+        } else {
+            need_u32(&mut self.source, reference.gen as u32)?;
+        }
+        need_keyword(&mut self.source, PdfKeyword::obj)
+    }
+
+    fn object_trailer(&mut self, reference: Reference) -> crate::Result<()> {
+        let offset = self.source.seek(SeekFrom::Current(0))?;
+        match need_keyword(&mut self.source, PdfKeyword::endobj) {
+            Err(PdfError::KeywordExpected(_)) if self.lenient() => {
+                self.source.seek(SeekFrom::Start(offset))?;
+                self.warn(WarningKind::MissingEndobj, offset, Some(reference));
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn read_object(&mut self, reference: Reference) -> crate::Result<PdfObject> {
+        let object = self.parse_object(reference);
+        self.located(object, Some(reference))
+    }
+
+    fn parse_object(&mut self, reference: Reference) -> crate::Result<PdfObject> {
+        self.object_header(reference)?;
+        let mut warnings = vec![];
+        let object = next_object_with_warnings(&mut self.source, &mut warnings)?;
+        self.add_warnings(warnings, Some(reference));
+        match object {
+            // only the dictionary of a stream object is returned
+            Some(PdfObject::Dictionary(dict)) if self.at_stream()? => {
+                Ok(PdfObject::Dictionary(dict))
+            }
             Some(obj) => {
-                need_keyword(&mut self.source, PdfKeyword::endobj)?;
+                self.object_trailer(reference)?;
                 Ok(obj)
             }
             None => Err(PdfError::InvalidPdf("pdf object expected")),
         }
     }
 
+    fn at_stream(&mut self) -> crate::Result<bool> {
+        let offset = self.source.seek(SeekFrom::Current(0))?;
+        let stream = matches!(
+            next_object(&mut self.source),
+            Ok(Some(PdfObject::Keyword(PdfKeyword::stream)))
+        );
+        self.source.seek(SeekFrom::Start(offset))?;
+        Ok(stream)
+    }
+
     fn read_prefix(&mut self, reference: Reference) -> crate::Result<Dictionary> {
-        need_u32(&mut self.source, reference.id)?;
-        need_u32(&mut self.source, reference.gen as u32)?;
-        need_keyword(&mut self.source, PdfKeyword::obj)?;
-        let dictionary = need_dictionary(&mut self.source)?;
+        let prefix = self.parse_prefix(reference);
+        self.located(prefix, Some(reference))
+    }
+
+    fn parse_prefix(&mut self, reference: Reference) -> crate::Result<Dictionary> {
+        self.object_header(reference)?;
+        let mut warnings = vec![];
+        let dictionary = need_dictionary(&mut self.source, &mut warnings)?;
+        self.add_warnings(warnings, Some(reference));
         Ok(dictionary)
     }
 
     fn read_dictionary(&mut self, reference: Reference) -> crate::Result<Dictionary> {
         let dictionary = self.read_prefix(reference)?;
-        need_keyword(&mut self.source, PdfKeyword::endobj)?;
+        let endobj = self.object_trailer(reference);
+        self.located(endobj, Some(reference))?;
         Ok(dictionary)
     }
 
@@ -244,7 +492,7 @@ impl PdfDocument {
                     .collect();
                 Ok(PdfObject::Dictionary(Box::new(d)))
             }
-            obj @ _ => Ok(obj),
+            obj => Ok(obj),
         }
     }
 
@@ -258,37 +506,137 @@ impl PdfDocument {
     }
 
     fn read_stream(&mut self, reference: Reference) -> crate::Result<Vec<u8>> {
+        let stream = self.parse_stream(reference);
+        self.located(stream, Some(reference))
+    }
+
+    fn parse_stream(&mut self, reference: Reference) -> crate::Result<Vec<u8>> {
         self.seek_reference(reference)?;
-        let stream_dict = self.read_prefix(reference)?;
+        let mut stream_dict = self.read_prefix(reference)?;
+        // only the entries needed to read the data are resolved, others such as an image's
+        // /SMask may refer back to the stream
+        stream_dict.retain(|key, _| {
+            matches!(
+                key,
+                PdfName::Length | PdfName::Filter | PdfName::DecodeParms
+            )
+        });
         need_keyword(&mut self.source, PdfKeyword::stream)?;
-        while match self.source.getch()? {
-            None => return Err(PdfError::EndOfFile),
-            Some('\n') => false,
-            _ => true,
-        } {}
+        self.stream_line_ending(reference)?;
         let pos = self.source.seek(SeekFrom::Current(0))?;
         let stream_dict = self.dereference_dictionary(stream_dict)?;
         let _ = self.source.seek(SeekFrom::Start(pos))?;
-        let length = match stream_dict.get_u32(PdfName::Length) {
-            Some(length) => length as usize,
-            None => {
-                return Err(PdfError::InvalidPdf(
-                    "Length missing from stream dictionary",
-                ))
-            }
+        // a wrong /Length is recovered from in either mode, the length used is reported
+        let buffer = match stream_dict.get_u32(PdfName::Length) {
+            Some(length) => match self.read_counted_stream(length as usize) {
+                Ok(buffer) => buffer,
+                Err(PdfError::IoError(error)) => return Err(error.into()),
+                Err(error) => {
+                    // if there's no end to be found the original error is the useful one
+                    let offset = self.source.seek(SeekFrom::Current(0))?;
+                    match self.recover_stream(pos, Some(length as u64), reference) {
+                        Ok(buffer) => buffer,
+                        Err(_) => {
+                            self.source.seek(SeekFrom::Start(offset))?;
+                            return Err(error);
+                        }
+                    }
+                }
+            },
+            None => self.recover_stream(pos, None, reference)?,
         };
-        let mut buffer = vec![];
-        buffer.resize(length, 0);
-        let nread = self.source.read(&mut buffer)?;
-        if nread != length {
-            return Err(PdfError::InternalError("failed to read stream"));
+        decode_stream(buffer, stream_dict)
+    }
+
+    fn read_counted_stream(&mut self, length: usize) -> crate::Result<Vec<u8>> {
+        // the length isn't trusted for the allocation, it can't exceed the rest of the file
+        let position = self.source.seek(SeekFrom::Current(0))?;
+        let end = self.source.seek(SeekFrom::End(0))?;
+        self.source.seek(SeekFrom::Start(position))?;
+        if length as u64 > end.saturating_sub(position) {
+            return Err(PdfError::InvalidPdf("stream length exceeds file"));
         }
+        let mut buffer = vec![0; length];
+        self.source.read_exact(&mut buffer)?;
         need_keyword(&mut self.source, PdfKeyword::endstream)?;
-        decode_stream(buffer, stream_dict)
+        Ok(buffer)
+    }
+
+    // finds the end of stream data starting at position by scanning for endstream (or endobj)
+    fn recover_stream(
+        &mut self,
+        position: u64,
+        declared: Option<u64>,
+        reference: Reference,
+    ) -> crate::Result<Vec<u8>> {
+        self.source.seek(SeekFrom::Start(position))?;
+        let mut buffer = vec![];
+        loop {
+            match self.source.getbyte()? {
+                Some(byte) => buffer.push(byte),
+                None => return Err(PdfError::KeywordExpected(PdfKeyword::endstream)),
+            }
+            if buffer.ends_with(b"endstream") {
+                buffer.truncate(buffer.len() - b"endstream".len());
+                break;
+            }
+            if buffer.ends_with(b"endobj") {
+                buffer.truncate(buffer.len() - b"endobj".len());
+                self.source
+                    .seek(SeekFrom::Current(-(b"endobj".len() as i64)))?;
+                break;
+            }
+        }
+        // the end-of-line marker preceding endstream isn't part of the data
+        if buffer.ends_with(b"\r\n") {
+            buffer.truncate(buffer.len() - 2);
+        } else if buffer.ends_with(b"\n") || buffer.ends_with(b"\r") {
+            buffer.truncate(buffer.len() - 1);
+        }
+        let kind = WarningKind::StreamLength {
+            declared,
+            actual: buffer.len() as u64,
+        };
+        self.warn(kind, position, Some(reference));
+        Ok(buffer)
+    }
+
+    // skips the end-of-line marker which follows the stream keyword
+    fn stream_line_ending(&mut self, reference: Reference) -> crate::Result<()> {
+        if !self.lenient() {
+            while match self.source.getch()? {
+                None => return Err(PdfError::EndOfFile),
+                Some('\n') => false,
+                _ => true,
+            } {}
+            return Ok(());
+        }
+        loop {
+            match self.source.getch()? {
+                None => return Err(PdfError::EndOfFile),
+                Some('\n') => return Ok(()),
+                Some('\r') => {
+                    match self.source.getch()? {
+                        Some('\n') => return Ok(()),
+                        Some(_) => self.source.backup(),
+                        None => {}
+                    }
+                    break;
+                }
+                Some(' ') | Some('\t') | Some('\x0c') => {}
+                Some(_) => {
+                    self.source.backup();
+                    break;
+                }
+            }
+        }
+        let offset = self.source.seek(SeekFrom::Current(0))?;
+        self.warn(WarningKind::StreamLineEnding, offset, Some(reference));
+        Ok(())
     }
 
     fn read_streams(&mut self, _streams: &Array) -> crate::Result<Vec<u8>> {
-        Err(PdfError::InternalError("read_streams not implemented"))
+        Err(PdfError::Unsupported("content stream arrays"))
     }
 
     fn contents(&mut self, page_dict: &Dictionary) -> crate::Result<Vec<u8>> {
@@ -313,7 +661,8 @@ fn find_trailer(position: u64, buffer: &[u8]) -> crate::Result<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pdf_source::{ByteSliceSource, PdfSource};
+    use crate::pdf_source::{ByteSliceSource, ByteSource, PdfSource};
+    use crate::resources::Resources;
     use std::fs::File;
 
     fn open_test_file(name: &str) -> Box<PdfSource<File>> {
@@ -322,6 +671,169 @@ mod tests {
         Box::new(PdfSource::new(File::open(&fullname).unwrap()))
     }
 
+    #[test]
+    fn located_error() {
+        let mut buffer = vec![];
+        let mut file = File::open(format!(
+            "{}/testing/minimal.pdf",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+        file.read_to_end(&mut buffer).unwrap();
+        // a wrong length can't be recovered from without an endstream or endobj to find
+        let bad_length = String::from_utf8_lossy(&buffer)
+            .replace("/Length 55", "/Length 50")
+            .replace("endstream\nendobj", "endstreax\nendobx");
+        let mut pdf = PdfDocument::new(Box::new(ByteSource::new(bad_length.into_bytes()))).unwrap();
+        let error = pdf.page_contents(0).err().unwrap();
+        assert!(matches!(
+            error.kind(),
+            PdfError::KeywordExpected(PdfKeyword::endstream)
+        ));
+        let location = error.location().unwrap();
+        assert_eq!(location.offset, 546);
+        assert_eq!(location.reference, Some(Reference::new(4, 0)));
+        assert_eq!(
+            location.excerpt,
+            b"o World) Tj\n  ET\nendstreax\nendob".to_vec()
+        );
+    }
+
+    fn read_test_file(name: &str) -> Vec<u8> {
+        let mut buffer = vec![];
+        let _ = open_test_file(name).read_to_end(&mut buffer).unwrap();
+        buffer
+    }
+
+    fn replace_once(buffer: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+        let index = buffer
+            .windows(from.len())
+            .position(|window| window == from)
+            .unwrap();
+        [&buffer[..index], to, &buffer[index + from.len()..]].concat()
+    }
+
+    #[test]
+    fn lenient_parsing() {
+        let buffer = read_test_file("minimal.pdf");
+        let buffer = replace_once(&buffer, b"3 0 obj", b"3 1 obj");
+        let buffer = replace_once(&buffer, b"endobj", b"      ");
+        let buffer = replace_once(&buffer, b"stream\n", b"stream\r");
+        assert!(PdfDocument::new(Box::new(ByteSource::new(buffer.clone()))).is_err());
+
+        let source = Box::new(ByteSource::new(buffer));
+        let mut pdf = PdfDocument::with_mode(source, ParseMode::Lenient).unwrap();
+        assert_eq!(pdf.page_count(), 1);
+        let mut pc = pdf.page_contents(0).unwrap();
+        assert_eq!(
+            pc.next_object().unwrap(),
+            Some(PdfObject::Keyword(PdfKeyword::BT))
+        );
+        let warnings: Vec<_> = pdf
+            .warnings()
+            .iter()
+            .map(|w| (w.kind.clone(), w.reference.unwrap()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (WarningKind::MissingEndobj, Reference::new(1, 0)),
+                (
+                    WarningKind::GenerationMismatch {
+                        expected: 0,
+                        found: 1
+                    },
+                    Reference::new(3, 0)
+                ),
+                (WarningKind::StreamLineEnding, Reference::new(4, 0)),
+            ]
+        );
+        assert_eq!(pdf.warnings()[0].offset, 68);
+    }
+
+    #[test]
+    fn invalid_generation_numbers() {
+        // the object keeps its offset, white space is taken from the dictionary
+        for (gen, found) in [(&b"3 -1 obj\n<<   "[..], -1), (b"3 65536 obj<< ", 65536)] {
+            let buffer = replace_once(&read_test_file("minimal.pdf"), b"3 0 obj\n  <<  ", gen);
+            let source = Box::new(ByteSource::new(buffer));
+            let pdf = PdfDocument::with_mode(source, ParseMode::Lenient).unwrap();
+            assert_eq!(
+                pdf.warnings()[0].kind,
+                WarningKind::GenerationMismatch { expected: 0, found }
+            );
+        }
+    }
+
+    #[test]
+    fn wrong_stream_length() {
+        let buffer = read_test_file("minimal.pdf");
+        let buffer = replace_once(&buffer, b"/Length 55", b"/Length 50");
+        for mode in [ParseMode::Strict, ParseMode::Lenient] {
+            let source = Box::new(ByteSource::new(buffer.clone()));
+            let mut pdf = PdfDocument::with_mode(source, mode).unwrap();
+            assert_eq!(
+                pdf.read_stream(Reference::new(4, 0)).unwrap(),
+                b"  BT\n    /F1 18 Tf\n    0 0 Td\n    (Hello World) Tj\n  ET".to_vec()
+            );
+            assert_eq!(
+                pdf.warnings(),
+                [Warning {
+                    kind: WarningKind::StreamLength {
+                        declared: Some(50),
+                        actual: 55
+                    },
+                    offset: 491,
+                    reference: Some(Reference::new(4, 0)),
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn huge_stream_length() {
+        let buffer = read_test_file("minimal.pdf");
+        let buffer = replace_once(&buffer, b"  << /Length 55 >>", b"<</Length 999999>>");
+        let mut pdf = PdfDocument::new(Box::new(ByteSource::new(buffer))).unwrap();
+        assert_eq!(pdf.read_stream(Reference::new(4, 0)).unwrap().len(), 55);
+        assert_eq!(
+            pdf.warnings()[0].kind,
+            WarningKind::StreamLength {
+                declared: Some(999999),
+                actual: 55
+            }
+        );
+    }
+
+    #[test]
+    fn unresolved_stream_length() {
+        let buffer = read_test_file("minimal.pdf");
+        let buffer = replace_once(&buffer, b"  << /Length 55 >>", b"<</Length 9 0 R>> ");
+        let buffer = replace_once(&buffer, b"  ET\nendstream", b"  ET\r\nendobj   ");
+        let mut pdf = PdfDocument::new(Box::new(ByteSource::new(buffer))).unwrap();
+        let mut pc = pdf.page_contents(0).unwrap();
+        let mut count = 0;
+        while pc.next_object().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 10);
+        assert_eq!(
+            pdf.warnings()[0].kind,
+            WarningKind::StreamLength {
+                declared: None,
+                actual: 55
+            }
+        );
+        assert_eq!(pdf.warnings()[0].reference, Some(Reference::new(4, 0)));
+    }
+
+    #[test]
+    fn strict_parsing_has_no_warnings() {
+        let pdf = PdfDocument::new(open_test_file("tracemonkey.pdf")).unwrap();
+        assert_eq!(pdf.mode(), ParseMode::Strict);
+        assert!(pdf.warnings().is_empty());
+    }
+
     #[test]
     fn bad_header() {
         let pdf = Box::new(ByteSliceSource::new(b"%PDx-1.3\n% bad pdf header\n"));
@@ -331,7 +843,7 @@ mod tests {
     #[test]
     fn find_trailer_middle() {
         let buffer = "blah blah blah trailer blah blah blah".as_bytes();
-        let position = find_trailer(0, &buffer);
+        let position = find_trailer(0, buffer);
         assert!(position.is_ok());
         assert_eq!(position.unwrap(), 22);
     }
@@ -339,7 +851,7 @@ mod tests {
     #[test]
     fn find_trailer_middle_offset() {
         let buffer = "blah blah blah trailer blah blah blah".as_bytes();
-        let position = find_trailer(1000, &buffer);
+        let position = find_trailer(1000, buffer);
         assert!(position.is_ok());
         assert_eq!(position.unwrap(), 1000 + 22);
     }
@@ -347,7 +859,7 @@ mod tests {
     #[test]
     fn find_trailer_end() {
         let buffer = "blah blah blah trailer".as_bytes();
-        let position = find_trailer(0, &buffer);
+        let position = find_trailer(0, buffer);
         assert!(position.is_ok());
         assert_eq!(position.unwrap(), 22);
     }
@@ -355,7 +867,7 @@ mod tests {
     #[test]
     fn find_trailer_start() {
         let buffer = "trailer blah blah blah".as_bytes();
-        let position = find_trailer(0, &buffer);
+        let position = find_trailer(0, buffer);
         assert!(position.is_ok());
         assert_eq!(position.unwrap(), 7);
     }
@@ -363,7 +875,7 @@ mod tests {
     #[test]
     fn no_trailer() {
         let buffer = "railer blah blah blah".as_bytes();
-        let position = find_trailer(0, &buffer);
+        let position = find_trailer(0, buffer);
         assert!(position.is_err());
     }
 
@@ -452,6 +964,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn tracemonkey_pdf_operations() {
+        let mut pdf = PdfDocument::new(open_test_file("tracemonkey.pdf")).unwrap();
+        for pageno in 0..pdf.page_count() {
+            for operation in pdf.page_contents(pageno).unwrap().operations() {
+                assert!(operation.unwrap().operator != PdfKeyword::Unknown);
+            }
+        }
+    }
+
+    #[test]
+    fn tracemonkey_pdf_validation() {
+        let mut pdf = PdfDocument::new(open_test_file("tracemonkey.pdf")).unwrap();
+        for pageno in 0..pdf.page_count() {
+            let contents = pdf.page_contents(pageno).unwrap();
+            assert_eq!(crate::validation::validate(contents), vec![]);
+        }
+    }
+
+    #[test]
+    fn minimal_pdf_page_space() {
+        let mut pdf = PdfDocument::new(open_test_file("minimal.pdf")).unwrap();
+        assert_eq!(pdf.page_box(0).unwrap(), Rect::new(0.0, 0.0, 300.0, 144.0));
+        assert_eq!(pdf.page_rotation(0).unwrap(), 0);
+        assert_eq!(pdf.page_space(0).unwrap(), Matrix::identity());
+        let corners = |pdf: &mut PdfDocument| {
+            let page_space = pdf.page_space(0).unwrap();
+            (
+                page_space.transform(0.0, 144.0),
+                page_space.transform(300.0, 0.0),
+            )
+        };
+        let rotate = |pdf: &mut PdfDocument, degrees| {
+            let degrees = PdfObject::Number(PdfNumber::Integer(degrees));
+            pdf.pages[0].insert(PdfName::Rotate, degrees);
+        };
+        assert_eq!(pdf.page_size(0).unwrap(), (300.0, 144.0));
+        rotate(&mut pdf, 90);
+        assert_eq!(corners(&mut pdf), ((144.0, 300.0), (0.0, 0.0)));
+        assert_eq!(pdf.page_size(0).unwrap(), (144.0, 300.0));
+        rotate(&mut pdf, -90);
+        assert_eq!(pdf.page_rotation(0).unwrap(), 270);
+        assert_eq!(corners(&mut pdf), ((0.0, 0.0), (144.0, 300.0)));
+        rotate(&mut pdf, 180);
+        assert_eq!(corners(&mut pdf), ((300.0, 0.0), (0.0, 144.0)));
+    }
+
     #[test]
     fn minimal_pdf_contents_iter() {
         let mut pdf = PdfDocument::new(open_test_file("minimal.pdf")).unwrap();
@@ -496,6 +1055,27 @@ mod tests {
             pc.next_object().unwrap().unwrap(),
             PdfObject::Keyword(PdfKeyword::ET)
         );
-        assert!(pc.next_object().unwrap() == None);
+        assert!(pc.next_object().unwrap().is_none());
+    }
+
+    #[test]
+    fn tracemonkey_pdf_stream_contents() {
+        let mut pdf = PdfDocument::new(open_test_file("tracemonkey.pdf")).unwrap();
+        let resources = Resources::for_page(&mut pdf, 1).unwrap();
+        let im1 = PdfName::Other(b"Im1".to_vec());
+        let form = resources.xobject(&mut pdf, &im1).unwrap().unwrap();
+        let operations: Vec<_> = pdf
+            .stream_contents(form.reference.unwrap())
+            .unwrap()
+            .operations()
+            .map(|operation| operation.unwrap())
+            .collect();
+        let paint = operations
+            .iter()
+            .find(|operation| operation.operator == PdfKeyword::Do)
+            .unwrap();
+        assert_eq!(paint.operands, vec![PdfObject::Symbol(b"Im1".to_vec())]);
+        // a resource dictionary isn't a stream
+        assert!(pdf.stream_contents(Reference::new(1, 0)).is_err());
     }
 }
@@ -6,6 +6,15 @@ pub trait Source: Read {
     fn seek(&mut self, pos: SeekFrom) -> StdResult<u64, Error>;
     fn getch(&mut self) -> crate::Result<Option<char>>;
     fn backup(&mut self);
+
+    // the next byte, unlike getch this doesn't need to be implemented
+    fn getbyte(&mut self) -> crate::Result<Option<u8>> {
+        let mut buffer = [0];
+        match self.read(&mut buffer)? {
+            0 => Ok(None),
+            _ => Ok(Some(buffer[0])),
+        }
+    }
 }
 
 pub struct PdfSource<T>
@@ -23,7 +32,7 @@ where
     where
         T: Read + Seek,
     {
-        PdfSource { source: source }
+        PdfSource { source }
     }
 }
 
@@ -124,3 +133,39 @@ fn readch(source: &mut dyn Read) -> crate::Result<Option<char>> {
         _ => Ok(Some(buffer[0] as char)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a source implemented outside the crate, which only provides the required methods
+    struct External(Cursor<Vec<u8>>);
+
+    impl Read for External {
+        fn read(&mut self, buf: &mut [u8]) -> StdResult<usize, Error> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Source for External {
+        fn seek(&mut self, pos: SeekFrom) -> StdResult<u64, Error> {
+            self.0.seek(pos)
+        }
+
+        fn getch(&mut self) -> crate::Result<Option<char>> {
+            readch(&mut self.0)
+        }
+
+        fn backup(&mut self) {
+            let _ = self.seek(SeekFrom::Current(-1));
+        }
+    }
+
+    #[test]
+    fn default_getbyte() {
+        let mut source: Box<dyn Source> = Box::new(External(Cursor::new(vec![b'a', 0xff])));
+        assert_eq!(source.getbyte().unwrap(), Some(b'a'));
+        assert_eq!(source.getbyte().unwrap(), Some(0xff));
+        assert_eq!(source.getbyte().unwrap(), None);
+    }
+}
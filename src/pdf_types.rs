@@ -15,7 +15,7 @@ pub struct Reference {
 
 impl Reference {
     pub fn new(id: u32, gen: u16) -> Reference {
-        Reference { id: id, gen: gen }
+        Reference { id, gen }
     }
 }
 
@@ -35,8 +35,53 @@ pub enum PdfObject {
     Name(PdfName),
     Symbol(PdfString), // a Symbol is an unrecognized Name
     Array(Array),
+    Procedure(Array), // a PostScript calculator procedure: { ... }
     Dictionary(Dictionary),
     Reference(Reference),
+    InlineImage(Box<InlineImage>), // BI ... ID <data> EI in a content stream
+}
+
+impl PdfName {
+    // the name as written in the file, without the leading slash
+    pub fn text(&self) -> String {
+        match self {
+            PdfName::Other(name) => String::from_utf8_lossy(name).into_owned(),
+            name => format!("{:?}", name),
+        }
+    }
+}
+
+impl PdfNumber {
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            PdfNumber::Integer(i) => i as f64,
+            PdfNumber::Real(r) => r,
+        }
+    }
+}
+
+impl PdfObject {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            PdfObject::Number(n) => Some(n.as_f64()),
+            _ => None,
+        }
+    }
+
+    // names and symbols as dictionary keys, e.g. a resource name operand such as /F1
+    pub fn as_name(&self) -> Option<PdfName> {
+        match self {
+            PdfObject::Name(name) => Some(name.clone()),
+            PdfObject::Symbol(symbol) => Some(PdfName::Other(symbol.clone())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct InlineImage {
+    pub dictionary: Dictionary,
+    pub data: Vec<u8>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -51,4 +96,6 @@ pub enum PdfToken {
     EndArray,
     BeginDictionary,
     EndDictionary,
+    BeginProcedure,
+    EndProcedure,
 }
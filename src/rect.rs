@@ -0,0 +1,87 @@
+use crate::matrix::Matrix;
+use crate::pdf_types::*;
+
+// an axis-aligned rectangle, with (x0, y0) at its lower left and (x1, y1) at its upper right
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+impl Rect {
+    // normalizes the corners, so any two opposite corners can be given
+    pub fn new(x0: f64, y0: f64, x1: f64, y1: f64) -> Rect {
+        Rect {
+            x0: x0.min(x1),
+            y0: y0.min(y1),
+            x1: x0.max(x1),
+            y1: y0.max(y1),
+        }
+    }
+
+    // a rectangle array, as found in /MediaBox or /BBox entries
+    pub fn from_objects(objects: &[PdfObject]) -> Option<Rect> {
+        match objects {
+            [x0, y0, x1, y1] => Some(Rect::new(
+                x0.as_f64()?,
+                y0.as_f64()?,
+                x1.as_f64()?,
+                y1.as_f64()?,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> f64 {
+        self.y1 - self.y0
+    }
+
+    // the smallest rectangle containing both rectangles
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+
+    // the bounding box of the rectangle after it has been transformed by a matrix
+    pub fn transform(&self, matrix: &Matrix) -> Rect {
+        let corners = [
+            matrix.transform(self.x0, self.y0),
+            matrix.transform(self.x1, self.y0),
+            matrix.transform(self.x0, self.y1),
+            matrix.transform(self.x1, self.y1),
+        ];
+        let (x, y) = corners[0];
+        corners[1..]
+            .iter()
+            .fold(Rect::new(x, y, x, y), |rect, &(x, y)| {
+                rect.union(&Rect::new(x, y, x, y))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform() {
+        let rect = Rect::new(10.0, 20.0, 0.0, 0.0);
+        assert_eq!(rect, Rect::new(0.0, 0.0, 10.0, 20.0));
+        let rotate = Matrix::new(0.0, 1.0, -1.0, 0.0, 0.0, 0.0);
+        assert_eq!(rect.transform(&rotate), Rect::new(-20.0, 0.0, 0.0, 10.0));
+        assert_eq!(
+            rect.union(&Rect::new(5.0, -5.0, 15.0, 5.0)),
+            Rect::new(0.0, -5.0, 15.0, 20.0)
+        );
+    }
+}
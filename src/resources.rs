@@ -0,0 +1,298 @@
+use crate::dictionary::Access;
+use crate::font::Font;
+use crate::graphics_state::ColorSpace;
+use crate::pdf_document::PdfDocument;
+use crate::pdf_types::*;
+
+// a resource which may be a stream, such as an XObject, a tiling pattern or a shading
+//
+// The reference is needed to read a stream's data with PdfDocument::stream_data, it's None for
+// resources given directly in the resource dictionary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resource {
+    pub reference: Option<Reference>,
+    pub dictionary: Dictionary,
+}
+
+impl Resource {
+    // the /Subtype of an XObject, e.g. /Image or /Form
+    pub fn subtype(&self) -> Option<PdfName> {
+        self.dictionary.get_name(PdfName::Subtype)
+    }
+}
+
+// the resource dictionary of a page or form XObject, with lookup by category and name
+//
+// Lookups resolve references through the document. Resource names are usually symbols, e.g.
+// /F1, which are looked up as PdfName::Other, see PdfObject::as_name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resources {
+    dictionary: Dictionary,
+}
+
+impl Resources {
+    pub fn new(dictionary: Dictionary) -> Resources {
+        Resources { dictionary }
+    }
+
+    pub fn for_page(document: &mut PdfDocument, pageno: u32) -> crate::Result<Resources> {
+        Ok(Resources::new(document.page_resources(pageno)?))
+    }
+
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dictionary
+    }
+
+    // a resource by category, e.g. /Font, and name, unresolved
+    pub fn lookup(
+        &self,
+        document: &mut PdfDocument,
+        category: PdfName,
+        name: &PdfName,
+    ) -> crate::Result<Option<PdfObject>> {
+        let category = match self.dictionary.get(&category) {
+            Some(category) => document.resolve(category.clone())?,
+            None => return Ok(None),
+        };
+        match category {
+            PdfObject::Dictionary(category) => Ok(category.get(name).cloned()),
+            _ => Ok(None),
+        }
+    }
+
+    // a resource by category and name, resolved
+    pub fn get(
+        &self,
+        document: &mut PdfDocument,
+        category: PdfName,
+        name: &PdfName,
+    ) -> crate::Result<Option<PdfObject>> {
+        match self.lookup(document, category, name)? {
+            Some(resource) => Ok(Some(document.resolve(resource)?)),
+            None => Ok(None),
+        }
+    }
+
+    // the font named by Tf, loaded
+    pub fn font(&self, document: &mut PdfDocument, name: &PdfName) -> crate::Result<Option<Font>> {
+        match self.get(document, PdfName::Font, name)? {
+            Some(PdfObject::Dictionary(dictionary)) => Ok(Some(Font::new(document, &dictionary)?)),
+            _ => Ok(None),
+        }
+    }
+
+    // the image or form XObject painted by Do
+    pub fn xobject(
+        &self,
+        document: &mut PdfDocument,
+        name: &PdfName,
+    ) -> crate::Result<Option<Resource>> {
+        self.stream(document, PdfName::XObject, name)
+    }
+
+    // the graphics state parameter dictionary set by gs
+    pub fn ext_gstate(
+        &self,
+        document: &mut PdfDocument,
+        name: &PdfName,
+    ) -> crate::Result<Option<Dictionary>> {
+        self.dictionary_resource(document, PdfName::ExtGState, name)
+    }
+
+    // the color space set by CS or cs, the device color spaces and /Pattern need no resource
+    pub fn color_space(
+        &self,
+        document: &mut PdfDocument,
+        name: &PdfName,
+    ) -> crate::Result<Option<ColorSpace>> {
+        let space = match name {
+            PdfName::DeviceGray | PdfName::DeviceRGB | PdfName::DeviceCMYK | PdfName::Pattern => {
+                PdfObject::Name(name.clone())
+            }
+            name => match self.get(document, PdfName::ColorSpace, name)? {
+                Some(space) => space,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(color_space(document, space)?))
+    }
+
+    // the pattern used by scn or SCN with the Pattern color space, a tiling pattern stream or
+    // a shading pattern dictionary
+    pub fn pattern(
+        &self,
+        document: &mut PdfDocument,
+        name: &PdfName,
+    ) -> crate::Result<Option<Resource>> {
+        self.stream(document, PdfName::Pattern, name)
+    }
+
+    // the shading painted by sh, a dictionary or, for mesh shadings, a stream
+    pub fn shading(
+        &self,
+        document: &mut PdfDocument,
+        name: &PdfName,
+    ) -> crate::Result<Option<Resource>> {
+        self.stream(document, PdfName::Shading, name)
+    }
+
+    // the property list of marked content named by BDC or DP
+    pub fn properties(
+        &self,
+        document: &mut PdfDocument,
+        name: &PdfName,
+    ) -> crate::Result<Option<Dictionary>> {
+        self.dictionary_resource(document, PdfName::Properties, name)
+    }
+
+    fn stream(
+        &self,
+        document: &mut PdfDocument,
+        category: PdfName,
+        name: &PdfName,
+    ) -> crate::Result<Option<Resource>> {
+        let (reference, resource) = match self.lookup(document, category, name)? {
+            Some(PdfObject::Reference(reference)) => (
+                Some(reference),
+                document.resolve(PdfObject::Reference(reference))?,
+            ),
+            Some(resource) => (None, document.resolve(resource)?),
+            None => return Ok(None),
+        };
+        match resource {
+            PdfObject::Dictionary(dictionary) => Ok(Some(Resource {
+                reference,
+                dictionary,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    fn dictionary_resource(
+        &self,
+        document: &mut PdfDocument,
+        category: PdfName,
+        name: &PdfName,
+    ) -> crate::Result<Option<Dictionary>> {
+        match self.get(document, category, name)? {
+            Some(PdfObject::Dictionary(dictionary)) => Ok(Some(dictionary)),
+            _ => Ok(None),
+        }
+    }
+}
+
+// a color space object, resolving the ICC profile stream which is needed to know the number of
+// components of an ICCBased space
+pub(crate) fn color_space(
+    document: &mut PdfDocument,
+    space: PdfObject,
+) -> crate::Result<ColorSpace> {
+    Ok(match space {
+        PdfObject::Array(mut array)
+            if array.first() == Some(&PdfObject::Name(PdfName::ICCBased)) && array.len() > 1 =>
+        {
+            array[1] = document.resolve(array[1].clone())?;
+            ColorSpace::from_object(PdfObject::Array(array))
+        }
+        space => ColorSpace::from_object(space),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::next_object::next_object;
+    use crate::pdf_source::{ByteSource, Source};
+    use crate::test_files;
+
+    // a resource name operand as it's read from a content stream
+    fn operand(name: &str) -> PdfName {
+        let mut source: Box<dyn Source> = Box::new(ByteSource::new(name.as_bytes().to_vec()));
+        next_object(&mut source)
+            .unwrap()
+            .unwrap()
+            .as_name()
+            .unwrap()
+    }
+
+    #[test]
+    fn minimal_pdf_resources() {
+        let mut document = test_files::open("minimal.pdf");
+        let resources = Resources::for_page(&mut document, 0).unwrap();
+        let font = resources.font(&mut document, &operand("/F1")).unwrap();
+        assert_eq!(font.unwrap().base_font, "Times-Roman");
+        assert!(resources
+            .font(&mut document, &operand("/F2"))
+            .unwrap()
+            .is_none());
+        assert!(resources
+            .xobject(&mut document, &operand("/F1"))
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            resources
+                .color_space(&mut document, &PdfName::DeviceRGB)
+                .unwrap(),
+            Some(ColorSpace::DeviceRGB)
+        );
+    }
+
+    #[test]
+    fn inherited_resources() {
+        let mut document = test_files::document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /Resources 4 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 300 144] >>",
+            "<< /Font << /F1 5 0 R >> >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+        ]);
+        let resources = Resources::for_page(&mut document, 0).unwrap();
+        let font = resources.font(&mut document, &operand("/F1")).unwrap();
+        assert_eq!(font.unwrap().base_font, "Helvetica");
+    }
+
+    #[test]
+    fn tracemonkey_pdf_resources() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let resources = Resources::for_page(&mut document, 1).unwrap();
+        let form = resources
+            .xobject(&mut document, &operand("/Im1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(form.subtype(), Some(PdfName::Form));
+        assert!(form.reference.is_some());
+        // the form's own resources name the image it paints
+        let form_resources = match form.dictionary.get(&PdfName::Resources) {
+            Some(resources) => match document.resolve(resources.clone()).unwrap() {
+                PdfObject::Dictionary(resources) => Resources::new(resources),
+                _ => panic!("resource dictionary expected"),
+            },
+            None => panic!("form resources expected"),
+        };
+        let image = form_resources
+            .xobject(&mut document, &operand("/Im1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(image.subtype(), Some(PdfName::Image));
+        assert_eq!(image.reference, Some(Reference::new(52, 0)));
+        // an ICCBased space, with its profile stream resolved
+        let space = form_resources
+            .color_space(&mut document, &operand("/Cs1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(space.components(), 1);
+        let space = form_resources
+            .color_space(&mut document, &operand("/Cs2"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(space.components(), 3);
+        assert!(form_resources
+            .font(&mut document, &operand("/F1.0"))
+            .unwrap()
+            .is_some());
+        assert!(form_resources
+            .properties(&mut document, &operand("/Cs1"))
+            .unwrap()
+            .is_none());
+    }
+}
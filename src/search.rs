@@ -0,0 +1,203 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::layout::{page_layout, LayoutParams, PageLayout};
+use crate::pdf_document::PdfDocument;
+use crate::rect::Rect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SearchOptions {
+    // the pattern is a regular expression rather than a literal string
+    pub regex: bool,
+    pub case_insensitive: bool,
+    // any run of whitespace in the pattern matches any whitespace in the text, including line
+    // and block breaks
+    pub normalize_whitespace: bool,
+}
+
+// a match, with the rectangles to highlight in page space, one for each line it touches
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub page: u32,
+    pub text: String,
+    pub rects: Vec<Rect>,
+}
+
+// a glyph's part of the searched text and the line it's on
+#[derive(Debug)]
+struct Span {
+    start: usize,
+    end: usize,
+    bbox: Rect,
+    line: usize,
+}
+
+// the text of a page in reading order, with the glyph behind each part of it
+//
+// Words are separated by spaces, lines by newlines and blocks by blank lines, or all by single
+// spaces when whitespace is normalized.
+fn searchable_text(layout: &PageLayout, normalize_whitespace: bool) -> (String, Vec<Span>) {
+    let mut text = String::new();
+    let mut spans = vec![];
+    let mut line_number = 0;
+    for (b, block) in layout.blocks.iter().enumerate() {
+        for (l, line) in block.lines.iter().enumerate() {
+            for (w, word) in line.words.iter().enumerate() {
+                let separator = match (b, l, w) {
+                    (0, 0, 0) => "",
+                    _ if normalize_whitespace => " ",
+                    (_, 0, 0) => "\n\n",
+                    (_, _, 0) => "\n",
+                    _ => " ",
+                };
+                text.push_str(separator);
+                for glyph in word.glyphs.iter() {
+                    let start = text.len();
+                    text.push_str(&glyph.text);
+                    spans.push(Span {
+                        start,
+                        end: text.len(),
+                        bbox: glyph.page_bbox,
+                        line: line_number,
+                    });
+                }
+            }
+            line_number += 1;
+        }
+    }
+    (text, spans)
+}
+
+fn pattern(pattern: &str, options: &SearchOptions) -> crate::Result<Regex> {
+    let pattern = match (options.regex, options.normalize_whitespace) {
+        (true, _) => pattern.to_owned(),
+        (false, true) => {
+            let words: Vec<String> = pattern.split_whitespace().map(regex::escape).collect();
+            words.join(r"\s+")
+        }
+        (false, false) => regex::escape(pattern),
+    };
+    Ok(RegexBuilder::new(&pattern)
+        .case_insensitive(options.case_insensitive)
+        .build()?)
+}
+
+// finds a pattern in the text of a page, see search
+pub fn search_page(
+    document: &mut PdfDocument,
+    pageno: u32,
+    pattern_text: &str,
+    options: &SearchOptions,
+) -> crate::Result<Vec<Hit>> {
+    let regex = pattern(pattern_text, options)?;
+    let layout = page_layout(document, pageno, &LayoutParams::default())?;
+    Ok(find(&regex, &layout, pageno, options))
+}
+
+fn find(regex: &Regex, layout: &PageLayout, pageno: u32, options: &SearchOptions) -> Vec<Hit> {
+    let (text, spans) = searchable_text(layout, options.normalize_whitespace);
+    let mut hits = vec![];
+    for found in regex.find_iter(&text) {
+        let mut rects: Vec<(usize, Rect)> = vec![];
+        let touched = spans
+            .iter()
+            .filter(|span| span.start < found.end() && span.end > found.start());
+        for span in touched {
+            match rects.last_mut() {
+                Some((line, rect)) if *line == span.line => *rect = rect.union(&span.bbox),
+                _ => rects.push((span.line, span.bbox)),
+            }
+        }
+        if !rects.is_empty() {
+            hits.push(Hit {
+                page: pageno,
+                text: found.as_str().to_owned(),
+                rects: rects.into_iter().map(|(_, rect)| rect).collect(),
+            });
+        }
+    }
+    hits
+}
+
+// finds a string or regular expression in the text of every page, in reading order as found by
+// page_layout
+//
+// Matches may span TJ kerning gaps, since glyphs are grouped into words by position, and
+// lines, since line breaks are whitespace.
+pub fn search(
+    document: &mut PdfDocument,
+    pattern_text: &str,
+    options: &SearchOptions,
+) -> crate::Result<Vec<Hit>> {
+    let regex = pattern(pattern_text, options)?;
+    let mut hits = vec![];
+    for pageno in 0..document.page_count() {
+        let layout = page_layout(document, pageno, &LayoutParams::default())?;
+        hits.extend(find(&regex, &layout, pageno, options));
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files;
+
+    #[test]
+    fn literal_search() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let options = SearchOptions::default();
+        // the title is set with TJ kerning between its letters
+        let hits = search_page(&mut document, 0, "Trace-based Just-in-Time", &options).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].page, 0);
+        assert_eq!(hits[0].rects.len(), 1);
+        let rect = hits[0].rects[0];
+        assert!(rect.x0 > 80.0 && rect.x1 < 280.0 && rect.y0 > 690.0 && rect.y1 < 720.0);
+        let hits = search_page(&mut document, 0, "trace-BASED", &options).unwrap();
+        assert!(hits.is_empty());
+        let options = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        let hits = search_page(&mut document, 0, "trace-BASED", &options).unwrap();
+        assert_eq!(hits[0].text, "Trace-based");
+        assert_eq!(hits[0].rects[0].x0, rect.x0);
+        assert!(hits[0].rects[0].x1 < 178.0);
+    }
+
+    #[test]
+    fn search_across_lines() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let options = SearchOptions {
+            normalize_whitespace: true,
+            ..SearchOptions::default()
+        };
+        let hits = search_page(&mut document, 0, "com-  pile than", &options).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, "com- pile than");
+        let rects = &hits[0].rects;
+        assert_eq!(rects.len(), 2);
+        assert!(rects[0].y0 > rects[1].y1);
+        let options = SearchOptions::default();
+        assert!(search_page(&mut document, 0, "com- pile", &options)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn regex_search() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let options = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let hits = search(&mut document, r"\d+x-\d+x speedup", &options).unwrap();
+        let pages: Vec<u32> = hits.iter().map(|hit| hit.page).collect();
+        assert!(pages.contains(&1));
+        assert!(hits.iter().all(|hit| hit.text.ends_with("x speedup")));
+        assert!(matches!(
+            search(&mut document, "(", &options),
+            Err(crate::PdfError::PatternError(_))
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+include!(concat!(env!("OUT_DIR"), "/codegen_standard_fonts.rs"));
+
+// the metrics of one of the Standard 14 fonts, which readers must provide when they
+// aren't embedded
+#[derive(Debug)]
+pub struct StandardFont {
+    pub name: &'static str,
+    pub flags: u32,
+    pub font_bbox: [f64; 4],
+    pub italic_angle: f64,
+    pub ascent: f64,
+    pub descent: f64,
+    pub cap_height: f64,
+    widths: phf::Map<&'static str, f64>,
+}
+
+// other names which are commonly used for the standard fonts
+const ALIASES: [(&str, &str); 12] = [
+    ("Arial", "Helvetica"),
+    ("Arial,Bold", "Helvetica-Bold"),
+    ("Arial,Italic", "Helvetica-Oblique"),
+    ("Arial,BoldItalic", "Helvetica-BoldOblique"),
+    ("TimesNewRoman", "Times-Roman"),
+    ("TimesNewRoman,Bold", "Times-Bold"),
+    ("TimesNewRoman,Italic", "Times-Italic"),
+    ("TimesNewRoman,BoldItalic", "Times-BoldItalic"),
+    ("CourierNew", "Courier"),
+    ("CourierNew,Bold", "Courier-Bold"),
+    ("CourierNew,Italic", "Courier-Oblique"),
+    ("CourierNew,BoldItalic", "Courier-BoldOblique"),
+];
+
+// the standard fonts are distinguished by name
+impl PartialEq for StandardFont {
+    fn eq(&self, other: &StandardFont) -> bool {
+        self.name == other.name
+    }
+}
+
+impl StandardFont {
+    // the standard font for a /BaseFont name, ignoring any subset tag
+    pub fn find(base_font: &str) -> Option<&'static StandardFont> {
+        let name = strip_subset_tag(base_font);
+        let name = match ALIASES.iter().find(|(alias, _)| *alias == name) {
+            Some((_, standard)) => standard,
+            None => name,
+        };
+        STANDARD_FONTS.get(name)
+    }
+
+    // the advance width of a glyph, in thousandths of a unit of text space
+    pub fn width(&self, glyph: &str) -> Option<f64> {
+        self.widths.get(glyph).cloned()
+    }
+}
+
+// subset fonts have a six letter tag prefixed to their name, as in ABCDEF+Helvetica
+pub(crate) fn strip_subset_tag(base_font: &str) -> &str {
+    match base_font.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 => name,
+        _ => base_font,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_fonts() {
+        let helvetica = StandardFont::find("Helvetica").unwrap();
+        assert_eq!(helvetica.width("space"), Some(278.0));
+        assert_eq!(helvetica.width("W"), Some(944.0));
+        assert_eq!(helvetica.width("alpha"), None);
+        assert_eq!(helvetica.ascent, 718.0);
+        assert_eq!(
+            StandardFont::find("ABCDEF+Courier").unwrap().width("W"),
+            Some(600.0)
+        );
+        assert_eq!(
+            StandardFont::find("Arial,Bold").unwrap().name,
+            "Helvetica-Bold"
+        );
+        assert_eq!(
+            StandardFont::find("Symbol").unwrap().width("alpha"),
+            Some(631.0)
+        );
+        assert!(StandardFont::find("NimbusRomNo9L-Regu").is_none());
+    }
+}
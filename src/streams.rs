@@ -8,23 +8,13 @@ struct Filter {
     _decode_parms: Option<Dictionary>,
 }
 
-pub fn decode_stream(mut stream: Vec<u8>, stream_dict: Dictionary) -> crate::Result<Vec<u8>> {
-    let filters = filters(stream_dict)?;
+pub fn decode_stream(mut stream: Vec<u8>, mut stream_dict: Dictionary) -> crate::Result<Vec<u8>> {
+    let filters = filters(&mut stream_dict)?;
     for filter in filters.iter() {
         match filter.name {
-            PdfName::ASCIIHexDecode => {
-                return Err(PdfError::InternalError(
-                    "ASCIIHexDecode filter not implemented",
-                ))
-            }
-            PdfName::ASCII85Decode => {
-                return Err(PdfError::InternalError(
-                    "ASCII85Decode filter not implemented",
-                ))
-            }
-            PdfName::LZWDecode => {
-                return Err(PdfError::InternalError("LZWDecode filter not implemented"))
-            }
+            PdfName::ASCIIHexDecode => return Err(PdfError::Unsupported("ASCIIHexDecode filter")),
+            PdfName::ASCII85Decode => return Err(PdfError::Unsupported("ASCII85Decode filter")),
+            PdfName::LZWDecode => return Err(PdfError::Unsupported("LZWDecode filter")),
             PdfName::FlateDecode => {
                 stream = match inflate_bytes_zlib(&stream[..]) {
                     Ok(stream) => stream,
@@ -32,43 +22,30 @@ pub fn decode_stream(mut stream: Vec<u8>, stream_dict: Dictionary) -> crate::Res
                 }
             }
             PdfName::RunLengthDecode => {
-                return Err(PdfError::InternalError(
-                    "RunLengthDecode filter not implemented",
-                ))
-            }
-            PdfName::CCITTFaxDecode => {
-                return Err(PdfError::InternalError(
-                    "CCITTFaxDecode filter not implemented",
-                ))
-            }
-            PdfName::JBIG2Decode => {
-                return Err(PdfError::InternalError(
-                    "JBIG2Decode filter not implemented",
-                ))
-            }
-            PdfName::DCTDecode => {
-                return Err(PdfError::InternalError(
-                    "JBIG2Decode filter not implemented",
-                ))
+                return Err(PdfError::Unsupported("RunLengthDecode filter"))
             }
-            PdfName::Crypt => return Err(PdfError::InternalError("Crypt filter not implemented")),
+            PdfName::CCITTFaxDecode => return Err(PdfError::Unsupported("CCITTFaxDecode filter")),
+            PdfName::JBIG2Decode => return Err(PdfError::Unsupported("JBIG2Decode filter")),
+            PdfName::DCTDecode => return Err(PdfError::Unsupported("DCTDecode filter")),
+            PdfName::JPXDecode => return Err(PdfError::Unsupported("JPXDecode filter")),
+            PdfName::Crypt => return Err(PdfError::Unsupported("Crypt filter")),
             _ => return Err(PdfError::InvalidPdf("unknown filter")),
         }
     }
     Ok(stream)
 }
 
-fn filters(mut stream_dict: Dictionary) -> crate::Result<Vec<Filter>> {
+fn filters(stream_dict: &mut Dictionary) -> crate::Result<Vec<Filter>> {
     match (
         stream_dict.remove(&PdfName::Filter),
         stream_dict.remove(&PdfName::DecodeParms),
     ) {
         (Some(PdfObject::Name(name)), None) => Ok(vec![Filter {
-            name: name,
+            name,
             _decode_parms: None,
         }]),
         (Some(PdfObject::Name(name)), Some(PdfObject::Dictionary(dp))) => Ok(vec![Filter {
-            name: name,
+            name,
             _decode_parms: Some(dp),
         }]),
         (Some(PdfObject::Array(names)), None) => {
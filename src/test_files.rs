@@ -0,0 +1,33 @@
+use crate::pdf_document::PdfDocument;
+use crate::pdf_source::{ByteSource, PdfSource};
+use std::fs::File;
+
+// opens a PDF file in the testing directory, e.g. tracemonkey.pdf
+pub(crate) fn open(name: &str) -> PdfDocument {
+    let path = format!("{}/testing/{}", env!("CARGO_MANIFEST_DIR"), name);
+    PdfDocument::new(Box::new(PdfSource::new(File::open(path).unwrap()))).unwrap()
+}
+
+// a document with the numbered objects, from 1 0 obj, and a cross reference table
+pub(crate) fn document(objects: &[&str]) -> PdfDocument {
+    let mut buffer = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(buffer.len());
+        buffer.extend(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).bytes());
+    }
+    let xref = buffer.len();
+    buffer.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        buffer.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    buffer.extend(
+        format!(
+            "trailer\n<< /Root 1 0 R /Size {} >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .bytes(),
+    );
+    PdfDocument::new(Box::new(ByteSource::new(buffer))).unwrap()
+}
@@ -0,0 +1,111 @@
+use crate::graphics_state::GraphicsState;
+use crate::interpreter::{Interpreter, Visitor};
+use crate::pdf_document::PdfDocument;
+use crate::pdf_types::*;
+
+// a gap between strings on the same line wider than this fraction of the font size is a space
+const WORD_GAP: f64 = 0.15;
+// a baseline moved by more than this fraction of the font size starts a new line
+const LINE_GAP: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    end: (f64, f64),
+    direction: (f64, f64),
+    size: f64,
+}
+
+// collects the text shown on a page, in content stream order
+//
+// Spaces and line breaks are inserted by comparing where each string starts with where the
+// previous one ended, so they follow from TJ adjustments and text positioning operators.
+// Text shown in a font which can't be decoded is skipped.
+#[derive(Debug, Default)]
+pub struct TextExtractor {
+    text: String,
+    last: Option<Position>,
+}
+
+impl TextExtractor {
+    pub fn new() -> TextExtractor {
+        TextExtractor::default()
+    }
+
+    pub fn text(self) -> String {
+        self.text
+    }
+
+    fn separate(&mut self, start: (f64, f64)) {
+        let last = match self.last {
+            Some(last) => last,
+            None => return,
+        };
+        let (dx, dy) = (start.0 - last.end.0, start.1 - last.end.1);
+        let along = dx * last.direction.0 + dy * last.direction.1;
+        let across = dy * last.direction.0 - dx * last.direction.1;
+        if across.abs() > LINE_GAP * last.size {
+            self.text.truncate(self.text.trim_end_matches(' ').len());
+            self.text.push('\n');
+        } else if along > WORD_GAP * last.size && !self.text.ends_with(char::is_whitespace) {
+            self.text.push(' ');
+        }
+    }
+}
+
+impl Visitor for TextExtractor {
+    fn show_text(&mut self, text: &PdfString, state: &GraphicsState) {
+        let font = match &state.text.font {
+            Some(font) => font,
+            None => return,
+        };
+        let matrix = state.text.matrix.multiply(&state.ctm);
+        let start = matrix.transform(0.0, state.text.rise);
+        let (tx, ty) = state.text.displacement(text);
+        let end = matrix.transform(tx, ty + state.text.rise);
+        let (dx, dy) = if font.is_vertical() {
+            (-matrix.c, -matrix.d)
+        } else {
+            (matrix.a, matrix.b)
+        };
+        let scale = dx.hypot(dy);
+        let direction = if scale > 0.0 {
+            (dx / scale, dy / scale)
+        } else {
+            (1.0, 0.0)
+        };
+        let size = state.text.font_size.abs() * matrix.c.hypot(matrix.d);
+        self.separate(start);
+        self.text.push_str(&font.decode(text));
+        self.last = Some(Position {
+            end,
+            direction,
+            size,
+        });
+    }
+}
+
+// the text of a page, see TextExtractor
+pub fn extract_text(document: &mut PdfDocument, pageno: u32) -> crate::Result<String> {
+    let contents = document.page_contents(pageno)?;
+    let mut extractor = TextExtractor::new();
+    let mut interpreter = Interpreter::for_page(document, pageno)?;
+    interpreter.recurse_forms(true);
+    interpreter.run(contents, &mut extractor)?;
+    Ok(extractor.text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files;
+
+    #[test]
+    fn tracemonkey_pdf_text() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let text = extract_text(&mut document, 0).unwrap();
+        assert!(text.starts_with("Trace-based Just-in-Time Type Specialization for Dynamic\n"));
+        assert!(text
+            .contains("\nDynamic languages such as JavaScript are more dif\u{fb01}cult to com-\n"));
+        assert!(text.contains("PLDI\u{2019}09, June 15\u{2013}20, 2009, Dublin, Ireland.\n"));
+    }
+}
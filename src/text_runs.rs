@@ -0,0 +1,173 @@
+use crate::graphics_state::{Color, GraphicsState};
+use crate::interpreter::{Interpreter, Visitor};
+use crate::matrix::Matrix;
+use crate::pdf_document::PdfDocument;
+use crate::pdf_types::*;
+use crate::rect::Rect;
+
+// the extent of glyphs above and below the baseline, in thousandths of the font size, for
+// fonts whose descriptor doesn't give them
+const DEFAULT_ASCENT: f64 = 800.0;
+const DEFAULT_DESCENT: f64 = -200.0;
+
+// a single glyph shown on a page
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    // the Unicode text for the glyph, U+FFFD if it can't be decoded
+    pub text: String,
+    pub bbox: Rect,
+    pub page_bbox: Rect,
+}
+
+// the glyphs shown by one string operand of a text showing operator
+//
+// Bounding boxes are given in default user space, where the CTM has been applied, and in page
+// space, where the page is upright as displayed (see PdfDocument::page_space).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun {
+    pub text: String,
+    pub base_font: String,
+    // the Tf operand, and the height of an em square in user space
+    pub font_size: f64,
+    pub size: f64,
+    pub fill_color: Color,
+    pub render_mode: u32,
+    // the direction the text advances in page space, in degrees counterclockwise from the
+    // x axis, -90 for upright vertical text
+    pub angle: f64,
+    pub bbox: Rect,
+    pub page_bbox: Rect,
+    pub glyphs: Vec<Glyph>,
+}
+
+// collects the text runs shown on a page, in content stream order
+//
+// Text shown in a font which couldn't be loaded is skipped.
+#[derive(Debug, Default)]
+pub struct TextRunCollector {
+    page_space: Matrix,
+    runs: Vec<TextRun>,
+}
+
+impl TextRunCollector {
+    // page_space maps default user space to page space
+    pub fn new(page_space: Matrix) -> TextRunCollector {
+        TextRunCollector {
+            page_space,
+            runs: vec![],
+        }
+    }
+
+    pub fn runs(self) -> Vec<TextRun> {
+        self.runs
+    }
+}
+
+impl Visitor for TextRunCollector {
+    fn show_text(&mut self, text: &PdfString, state: &GraphicsState) {
+        let font = match &state.text.font {
+            Some(font) => font,
+            None => return,
+        };
+        let text_state = &state.text;
+        let matrix = text_state.matrix.multiply(&state.ctm);
+        let scale = text_state.font_size / 1000.0;
+        let (ascent, descent) = match font.descriptor() {
+            Some(descriptor) if descriptor.ascent > descriptor.descent => {
+                (descriptor.ascent, descriptor.descent)
+            }
+            _ => (DEFAULT_ASCENT, DEFAULT_DESCENT),
+        };
+        let vertical = font.is_vertical();
+        let mut glyphs = vec![];
+        let (mut tx, mut ty) = (0.0, text_state.rise);
+        for (code, (dx, dy)) in text_state.glyph_advances(text).into_iter() {
+            // vertical glyphs hang below their origin, centered on it
+            let extent = if vertical {
+                let half = font.width(code) * scale / 2.0;
+                Rect::new(
+                    tx - half,
+                    ty + font.vertical_advance(code) * scale,
+                    tx + half,
+                    ty,
+                )
+            } else {
+                let width = font.width(code) * scale * text_state.horizontal_scaling;
+                Rect::new(tx, ty + descent * scale, tx + width, ty + ascent * scale)
+            };
+            let bbox = extent.transform(&matrix);
+            glyphs.push(Glyph {
+                text: font.unicode(code).unwrap_or_else(|| '\u{fffd}'.to_string()),
+                bbox,
+                page_bbox: bbox.transform(&self.page_space),
+            });
+            tx += dx;
+            ty += dy;
+        }
+        let bbox = match glyphs.split_first() {
+            Some((first, rest)) => rest
+                .iter()
+                .fold(first.bbox, |bbox, glyph| bbox.union(&glyph.bbox)),
+            None => return,
+        };
+        let page = matrix.multiply(&self.page_space);
+        let (dx, dy) = if vertical {
+            (-page.c, -page.d)
+        } else {
+            (page.a, page.b)
+        };
+        self.runs.push(TextRun {
+            text: glyphs.iter().map(|glyph| glyph.text.as_str()).collect(),
+            base_font: font.base_font.clone(),
+            font_size: text_state.font_size,
+            size: text_state.font_size.abs() * matrix.c.hypot(matrix.d),
+            fill_color: state.fill_color.clone(),
+            render_mode: text_state.render_mode,
+            angle: dy.atan2(dx).to_degrees(),
+            bbox,
+            page_bbox: bbox.transform(&self.page_space),
+            glyphs,
+        });
+    }
+}
+
+// the text runs on a page, see TextRunCollector
+pub fn text_runs(document: &mut PdfDocument, pageno: u32) -> crate::Result<Vec<TextRun>> {
+    let contents = document.page_contents(pageno)?;
+    let mut collector = TextRunCollector::new(document.page_space(pageno)?);
+    let mut interpreter = Interpreter::for_page(document, pageno)?;
+    interpreter.recurse_forms(true);
+    interpreter.run(contents, &mut collector)?;
+    Ok(collector.runs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_files;
+
+    #[test]
+    fn tracemonkey_pdf_text_runs() {
+        let mut document = test_files::open("tracemonkey.pdf");
+        let runs = text_runs(&mut document, 0).unwrap();
+        // TJ kerning splits the title into several runs
+        let texts: Vec<&str> = runs[..4].iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, vec!["T", "race-based", "J", "ust-in-T"]);
+        let run = &runs[1];
+        assert_eq!(run.base_font, "TACTGM+NimbusRomNo9L-Medi");
+        assert_eq!(run.font_size, run.size);
+        assert_eq!(run.render_mode, 0);
+        assert_eq!(run.angle, 0.0);
+        assert_eq!(run.fill_color, Color::default());
+        assert_eq!(run.glyphs.len(), 10);
+        // the title is near the top of an unrotated page
+        assert!(run.bbox.y0 > 690.0 && run.bbox.y1 < 720.0);
+        assert_eq!(run.bbox, run.page_bbox);
+        // runs continue where the previous one ended, less the kerning
+        assert!((run.bbox.x0 - runs[0].bbox.x1).abs() < 2.0);
+        let (r, a) = (&run.glyphs[0], &run.glyphs[1]);
+        assert_eq!(r.text, "r");
+        assert_eq!(r.bbox.x0, run.bbox.x0);
+        assert!(r.bbox.x1 > r.bbox.x0 && (r.bbox.x1 - a.bbox.x0).abs() < 1e-9);
+    }
+}
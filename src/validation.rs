@@ -0,0 +1,453 @@
+use crate::operations::{arity, Operation};
+use crate::page_contents::PageContents;
+use crate::pdf_types::*;
+use crate::PdfError;
+
+// PDF 32000-1 Annex C limits graphics state nesting to 28 levels
+const MAX_SAVE_DEPTH: usize = 28;
+
+// a problem found in a content stream, index is the position of the operation it refers to
+// (problems found at the end of the stream use the number of operations as their index)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub index: usize,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    // the content stream could not be tokenized any further
+    SyntaxError(String),
+    UnknownOperator,
+    InvalidOperandCount {
+        operator: PdfKeyword,
+        found: usize,
+    },
+    InvalidOperandType {
+        operator: PdfKeyword,
+        operand: usize,
+    },
+    // Q without a matching q
+    UnbalancedRestore,
+    // q nested more than MAX_SAVE_DEPTH levels
+    SaveNestingTooDeep,
+    // q without a matching Q at the end of the stream
+    UnclosedSave {
+        depth: usize,
+    },
+    NestedText,
+    // ET without a matching BT
+    UnbalancedEndText,
+    UnclosedText,
+    // EMC without a matching BMC or BDC
+    UnbalancedEndMarkedContent,
+    UnclosedMarkedContent {
+        depth: usize,
+    },
+    // EX without a matching BX
+    UnbalancedEndCompatibility,
+    UnclosedCompatibility {
+        depth: usize,
+    },
+    // a text showing or positioning operator outside BT/ET
+    TextOperatorOutsideText(PdfKeyword),
+    // an operator that isn't allowed inside BT/ET, such as path construction or q/Q
+    OperatorInsideText(PdfKeyword),
+    // a path painting or clipping operator without a current path
+    PaintWithoutPath(PdfKeyword),
+    // a path construction operator other than m or re without a current path
+    PathOperatorWithoutPath(PdfKeyword),
+    // a path wasn't ended by a painting operator before some other operator
+    UnterminatedPath(PdfKeyword),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand {
+    Number,
+    Integer,
+    Name,
+    String,
+    NumberArray,
+    TextArray,
+    NameOrDictionary,
+    InlineImage,
+}
+
+// expected operand types, None for operators with a variable number of operands
+fn signature(operator: &PdfKeyword) -> Option<&'static [Operand]> {
+    use Operand::*;
+    use PdfKeyword::*;
+    Some(match operator {
+        w | M | i | G | g | Tc | Tw | Tz | TL | Ts => &[Number],
+        j | J | Tr => &[Integer],
+        m | l | Td | TD | d0 => &[Number, Number],
+        RG | rg => &[Number, Number, Number],
+        K | k | re | v | y => &[Number, Number, Number, Number],
+        c | cm | d1 | Tm => &[Number, Number, Number, Number, Number, Number],
+        d => &[NumberArray, Number],
+        CS | cs | gs | ri | Do | sh | BMC | MP => &[Name],
+        BDC | DP => &[Name, NameOrDictionary],
+        Tf => &[Name, Number],
+        Tj | apos => &[String],
+        quote => &[Number, Number, String],
+        TJ => &[TextArray],
+        BI => &[InlineImage],
+        _ => return None,
+    })
+}
+
+fn is_number(operand: &PdfObject) -> bool {
+    matches!(operand, PdfObject::Number(_))
+}
+
+fn is_name(operand: &PdfObject) -> bool {
+    matches!(operand, PdfObject::Name(_) | PdfObject::Symbol(_))
+}
+
+fn matches_type(operand: &PdfObject, expected: Operand) -> bool {
+    match expected {
+        Operand::Number => is_number(operand),
+        Operand::Integer => matches!(operand, PdfObject::Number(PdfNumber::Integer(_))),
+        Operand::Name => is_name(operand),
+        Operand::String => matches!(operand, PdfObject::String(_)),
+        Operand::NumberArray => match operand {
+            PdfObject::Array(array) => array.iter().all(is_number),
+            _ => false,
+        },
+        Operand::TextArray => match operand {
+            PdfObject::Array(array) => array
+                .iter()
+                .all(|item| is_number(item) || matches!(item, PdfObject::String(_))),
+            _ => false,
+        },
+        Operand::NameOrDictionary => {
+            is_name(operand) || matches!(operand, PdfObject::Dictionary(_))
+        }
+        Operand::InlineImage => matches!(operand, PdfObject::InlineImage(_)),
+    }
+}
+
+// the position of the first operand which doesn't have the expected type
+fn invalid_operand(operation: &Operation) -> Option<usize> {
+    let operands = &operation.operands;
+    match operation.operator {
+        PdfKeyword::SC | PdfKeyword::sc => operands.iter().position(|o| !is_number(o)),
+        // SCN and scn take an optional pattern name after the color components
+        PdfKeyword::SCN | PdfKeyword::scn => {
+            let last = operands.len() - 1;
+            operands
+                .iter()
+                .enumerate()
+                .position(|(n, o)| !(is_number(o) || (n == last && is_name(o))))
+        }
+        ref operator => signature(operator)?
+            .iter()
+            .zip(operands.iter())
+            .position(|(&expected, operand)| !matches_type(operand, expected)),
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    diagnostics: Vec<Diagnostic>,
+    index: usize,
+    save_depth: usize,
+    marked_content_depth: usize,
+    compatibility_depth: usize,
+    in_text: bool,
+    in_path: bool,
+}
+
+impl Validator {
+    fn report(&mut self, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic {
+            index: self.index,
+            kind,
+        });
+    }
+
+    fn operation(&mut self, operation: &Operation) {
+        use PdfKeyword::*;
+        let operator = &operation.operator;
+        if arity(operator).is_none() {
+            if self.compatibility_depth == 0 {
+                self.report(DiagnosticKind::UnknownOperator);
+            }
+            return;
+        }
+        if let Some(operand) = invalid_operand(operation) {
+            self.report(DiagnosticKind::InvalidOperandType {
+                operator: operator.clone(),
+                operand,
+            });
+        }
+        match operator {
+            l | c | v | y | h | W | Wstar if !self.in_path => {
+                self.report(DiagnosticKind::PathOperatorWithoutPath(operator.clone()))
+            }
+            l | c | v | y | h | W | Wstar => {}
+            S | s | f | F | fstar | B | Bstar | b | bstar | n => {
+                if !self.in_path {
+                    self.report(DiagnosticKind::PaintWithoutPath(operator.clone()));
+                }
+                self.in_path = false;
+            }
+            m | re => {
+                if self.in_text {
+                    self.report(DiagnosticKind::OperatorInsideText(operator.clone()));
+                }
+                self.in_path = true;
+            }
+            _ => {
+                if self.in_path {
+                    self.report(DiagnosticKind::UnterminatedPath(operator.clone()));
+                    self.in_path = false;
+                }
+                self.other(operator);
+            }
+        }
+    }
+
+    fn other(&mut self, operator: &PdfKeyword) {
+        use PdfKeyword::*;
+        match operator {
+            q | Q | cm | Do | sh | BI if self.in_text => {
+                self.report(DiagnosticKind::OperatorInsideText(operator.clone()))
+            }
+            Tj | TJ | apos | quote | Td | TD | Tm | Tstar if !self.in_text => {
+                self.report(DiagnosticKind::TextOperatorOutsideText(operator.clone()))
+            }
+            _ => {}
+        }
+        match operator {
+            q => {
+                self.save_depth += 1;
+                if self.save_depth == MAX_SAVE_DEPTH + 1 {
+                    self.report(DiagnosticKind::SaveNestingTooDeep);
+                }
+            }
+            Q if self.save_depth == 0 => self.report(DiagnosticKind::UnbalancedRestore),
+            Q => self.save_depth -= 1,
+            BT if self.in_text => self.report(DiagnosticKind::NestedText),
+            BT => self.in_text = true,
+            ET if !self.in_text => self.report(DiagnosticKind::UnbalancedEndText),
+            ET => self.in_text = false,
+            BMC | BDC => self.marked_content_depth += 1,
+            EMC if self.marked_content_depth == 0 => {
+                self.report(DiagnosticKind::UnbalancedEndMarkedContent)
+            }
+            EMC => self.marked_content_depth -= 1,
+            BX => self.compatibility_depth += 1,
+            EX if self.compatibility_depth == 0 => {
+                self.report(DiagnosticKind::UnbalancedEndCompatibility)
+            }
+            EX => self.compatibility_depth -= 1,
+            _ => {}
+        }
+    }
+
+    fn error(&mut self, error: PdfError) -> bool {
+        match error.kind() {
+            PdfError::InvalidOperandCount { operator, found } => {
+                let kind = DiagnosticKind::InvalidOperandCount {
+                    operator: operator.clone(),
+                    found: *found,
+                };
+                self.report(kind);
+                true
+            }
+            _ => {
+                self.report(DiagnosticKind::SyntaxError(error.to_string()));
+                false
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Diagnostic> {
+        if self.in_path {
+            self.report(DiagnosticKind::UnterminatedPath(PdfKeyword::Unknown));
+        }
+        if self.in_text {
+            self.report(DiagnosticKind::UnclosedText);
+        }
+        if self.save_depth != 0 {
+            let depth = self.save_depth;
+            self.report(DiagnosticKind::UnclosedSave { depth });
+        }
+        if self.marked_content_depth != 0 {
+            let depth = self.marked_content_depth;
+            self.report(DiagnosticKind::UnclosedMarkedContent { depth });
+        }
+        if self.compatibility_depth != 0 {
+            let depth = self.compatibility_depth;
+            self.report(DiagnosticKind::UnclosedCompatibility { depth });
+        }
+        self.diagnostics
+    }
+}
+
+// checks a content stream for structural problems, an empty result means none were found
+pub fn validate(contents: PageContents) -> Vec<Diagnostic> {
+    let mut validator = Validator::default();
+    for operation in contents.operations() {
+        match operation {
+            Ok(operation) => validator.operation(&operation),
+            Err(error) => {
+                if !validator.error(error) {
+                    break;
+                }
+            }
+        }
+        validator.index += 1;
+    }
+    validator.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics(contents: &[u8]) -> Vec<(usize, DiagnosticKind)> {
+        validate(PageContents::new(contents.to_vec()))
+            .into_iter()
+            .map(|d| (d.index, d.kind))
+            .collect()
+    }
+
+    #[test]
+    fn valid_contents() {
+        let contents = b"q 1 0 0 1 0 0 cm 0 0 m 10 10 l S /Span <</MCID 0>> BDC BT /F1 12 Tf \
+                         1 0 0 1 72 720 Tm [(A) -120 (B)] TJ T* (C) Tj 1 2 (D) \" ET EMC \
+                         0 0 10 10 re W n BX 1 foo EX Q";
+        assert_eq!(diagnostics(contents), vec![]);
+    }
+
+    #[test]
+    fn save_restore() {
+        assert_eq!(
+            diagnostics(b"q Q Q q q Q"),
+            vec![
+                (2, DiagnosticKind::UnbalancedRestore),
+                (6, DiagnosticKind::UnclosedSave { depth: 1 })
+            ]
+        );
+        let deep = [
+            b"q ".repeat(MAX_SAVE_DEPTH + 1),
+            b"Q ".repeat(MAX_SAVE_DEPTH + 1),
+        ]
+        .concat();
+        assert_eq!(
+            diagnostics(&deep),
+            vec![(MAX_SAVE_DEPTH, DiagnosticKind::SaveNestingTooDeep)]
+        );
+    }
+
+    #[test]
+    fn text_objects() {
+        assert_eq!(
+            diagnostics(b"BT BT ET ET (x) Tj BT q 0 0 m"),
+            vec![
+                (1, DiagnosticKind::NestedText),
+                (3, DiagnosticKind::UnbalancedEndText),
+                (4, DiagnosticKind::TextOperatorOutsideText(PdfKeyword::Tj)),
+                (6, DiagnosticKind::OperatorInsideText(PdfKeyword::q)),
+                (7, DiagnosticKind::OperatorInsideText(PdfKeyword::m)),
+                (8, DiagnosticKind::UnterminatedPath(PdfKeyword::Unknown)),
+                (8, DiagnosticKind::UnclosedText),
+                (8, DiagnosticKind::UnclosedSave { depth: 1 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn marked_content_and_compatibility() {
+        assert_eq!(
+            diagnostics(b"/A BMC EMC EMC foo BX bar EX EX /B BMC BX"),
+            vec![
+                (2, DiagnosticKind::UnbalancedEndMarkedContent),
+                (3, DiagnosticKind::UnknownOperator),
+                (7, DiagnosticKind::UnbalancedEndCompatibility),
+                (10, DiagnosticKind::UnclosedMarkedContent { depth: 1 }),
+                (10, DiagnosticKind::UnclosedCompatibility { depth: 1 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            diagnostics(b"S 10 10 l 0 0 m 1 1 l 0 g f 0 0 m W n"),
+            vec![
+                (0, DiagnosticKind::PaintWithoutPath(PdfKeyword::S)),
+                (1, DiagnosticKind::PathOperatorWithoutPath(PdfKeyword::l)),
+                (4, DiagnosticKind::UnterminatedPath(PdfKeyword::g)),
+                (5, DiagnosticKind::PaintWithoutPath(PdfKeyword::f)),
+            ]
+        );
+    }
+
+    #[test]
+    fn operands() {
+        assert_eq!(
+            diagnostics(b"(x) w 1.5 j 1 2 re /GS0 gs (GS1) gs [1 (a)] 0 d /P 1 /x scn 0 /x SCN"),
+            vec![
+                (
+                    0,
+                    DiagnosticKind::InvalidOperandType {
+                        operator: PdfKeyword::w,
+                        operand: 0
+                    }
+                ),
+                (
+                    1,
+                    DiagnosticKind::InvalidOperandType {
+                        operator: PdfKeyword::j,
+                        operand: 0
+                    }
+                ),
+                (
+                    2,
+                    DiagnosticKind::InvalidOperandCount {
+                        operator: PdfKeyword::re,
+                        found: 2
+                    }
+                ),
+                (
+                    4,
+                    DiagnosticKind::InvalidOperandType {
+                        operator: PdfKeyword::gs,
+                        operand: 0
+                    }
+                ),
+                (
+                    5,
+                    DiagnosticKind::InvalidOperandType {
+                        operator: PdfKeyword::d,
+                        operand: 0
+                    }
+                ),
+                (
+                    6,
+                    DiagnosticKind::InvalidOperandType {
+                        operator: PdfKeyword::scn,
+                        operand: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_error() {
+        let diagnostics = diagnostics(b"q ) Q");
+        assert_eq!(diagnostics.len(), 2);
+        assert!(matches!(
+            diagnostics[0],
+            (1, DiagnosticKind::SyntaxError(_))
+        ));
+        assert_eq!(
+            diagnostics[1],
+            (1, DiagnosticKind::UnclosedSave { depth: 1 })
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use crate::pdf_types::*;
+
+// a spec violation which was recovered from while parsing, in lenient mode or, for a wrong
+// stream length, in either mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub offset: u64,
+    pub reference: Option<Reference>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    // the stream keyword was followed by a bare CR (or no end-of-line marker at all)
+    StreamLineEnding,
+    // an indirect object was not terminated by endobj
+    MissingEndobj,
+    // a dictionary had a key without a value, the value was taken to be null
+    OddLengthDictionary,
+    // an indirect object's generation number didn't match the reference to it, found is as
+    // written since it needn't be a valid generation number
+    GenerationMismatch { expected: u16, found: i64 },
+    // a stream's /Length was missing, unresolvable or wrong, actual is the length used instead
+    StreamLength { declared: Option<u64>, actual: u64 },
+}